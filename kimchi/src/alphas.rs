@@ -112,6 +112,24 @@ impl<F: Field> Alphas<F> {
         }
     }
 
+    /// Returns the exponents registered for the given [ArgumentType], if any.
+    /// Unlike [Self::get_exponents], this does not panic if the type was not registered.
+    pub fn registered_exponents(&self, ty: ArgumentType) -> Option<Range<u32>> {
+        let ty = if matches!(ty, ArgumentType::Gate(_)) {
+            ArgumentType::Gate(GateType::Zero)
+        } else {
+            ty
+        };
+        self.mapping
+            .get(&ty)
+            .map(|(start, powers)| *start..start + powers)
+    }
+
+    /// Returns the number of powers of alpha registered so far
+    pub fn num_powers(&self) -> u32 {
+        self.next_power
+    }
+
     /// Instantiates the ranges with an actual field element `alpha`.
     /// Once you call this function, you cannot register new constraints via [Self::register].
     pub fn instantiate(&mut self, alpha: F) {
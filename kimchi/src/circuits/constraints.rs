@@ -7,7 +7,7 @@ use crate::{
         gate::{CircuitGate, GateType},
         lookup::{
             index::LookupConstraintSystem,
-            lookups::LookupFeatures,
+            lookups::{LookupFeatures, LookupPattern, LookupPatterns},
             tables::{GateLookupTables, LookupTable},
         },
         polynomial::{WitnessEvals, WitnessOverDomains, WitnessShifts},
@@ -41,7 +41,7 @@ use std::sync::Arc;
     derive(ocaml::IntoValue, ocaml::FromValue, ocaml_gen::Struct)
 )]
 #[cfg_attr(feature = "wasm_types", wasm_bindgen::prelude::wasm_bindgen)]
-#[derive(Copy, Clone, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
 pub struct FeatureFlags {
     /// RangeCheck0 gate
     pub range_check0: bool,
@@ -624,18 +624,55 @@ pub fn zk_rows_strict_lower_bound(num_chunks: usize) -> usize {
 }
 
 impl FeatureFlags {
-    pub fn from_gates_and_lookup_features<F: PrimeField>(
-        gates: &[CircuitGate<F>],
-        lookup_features: LookupFeatures,
-    ) -> FeatureFlags {
-        let mut feature_flags = FeatureFlags {
+    /// Feature flags enabling every optional gate and lookup feature
+    pub fn all() -> FeatureFlags {
+        FeatureFlags {
+            range_check0: true,
+            range_check1: true,
+            foreign_field_add: true,
+            foreign_field_mul: true,
+            xor: true,
+            rot: true,
+            lookup_features: LookupFeatures {
+                patterns: LookupPatterns {
+                    xor: true,
+                    lookup: true,
+                    range_check: true,
+                    foreign_field_mul: true,
+                },
+                joint_lookup_used: true,
+                uses_runtime_tables: true,
+            },
+        }
+    }
+
+    /// Feature flags disabling every optional gate and lookup feature
+    pub fn none() -> FeatureFlags {
+        FeatureFlags {
             range_check0: false,
             range_check1: false,
-            lookup_features,
             foreign_field_add: false,
             foreign_field_mul: false,
             xor: false,
             rot: false,
+            lookup_features: LookupFeatures::default(),
+        }
+    }
+
+    /// Returns a [FeatureFlagsBuilder], starting from [Self::none]
+    pub fn builder() -> FeatureFlagsBuilder {
+        FeatureFlagsBuilder {
+            feature_flags: FeatureFlags::none(),
+        }
+    }
+
+    pub fn from_gates_and_lookup_features<F: PrimeField>(
+        gates: &[CircuitGate<F>],
+        lookup_features: LookupFeatures,
+    ) -> FeatureFlags {
+        let mut feature_flags = FeatureFlags {
+            lookup_features,
+            ..FeatureFlags::none()
         };
 
         for gate in gates {
@@ -664,6 +701,86 @@ impl FeatureFlags {
     }
 }
 
+/// Builder of [FeatureFlags], where every feature is disabled unless set.
+///
+/// ```
+/// use kimchi::circuits::constraints::FeatureFlags;
+///
+/// let feature_flags = FeatureFlags::builder().xor(true).range_check(true).build();
+/// assert!(feature_flags.xor && feature_flags.range_check0 && !feature_flags.rot);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct FeatureFlagsBuilder {
+    feature_flags: FeatureFlags,
+}
+
+impl FeatureFlagsBuilder {
+    /// Enables or disables both range check gates
+    pub fn range_check(self, enabled: bool) -> Self {
+        self.range_check0(enabled).range_check1(enabled)
+    }
+
+    /// Enables or disables the [GateType::RangeCheck0] gate
+    pub fn range_check0(mut self, enabled: bool) -> Self {
+        self.feature_flags.range_check0 = enabled;
+        self
+    }
+
+    /// Enables or disables the [GateType::RangeCheck1] gate
+    pub fn range_check1(mut self, enabled: bool) -> Self {
+        self.feature_flags.range_check1 = enabled;
+        self
+    }
+
+    /// Enables or disables the [GateType::ForeignFieldAdd] gate
+    pub fn foreign_field_add(mut self, enabled: bool) -> Self {
+        self.feature_flags.foreign_field_add = enabled;
+        self
+    }
+
+    /// Enables or disables the [GateType::ForeignFieldMul] gate
+    pub fn foreign_field_mul(mut self, enabled: bool) -> Self {
+        self.feature_flags.foreign_field_mul = enabled;
+        self
+    }
+
+    /// Enables or disables the [GateType::Xor16] gate
+    pub fn xor(mut self, enabled: bool) -> Self {
+        self.feature_flags.xor = enabled;
+        self
+    }
+
+    /// Enables or disables the [GateType::Rot64] gate
+    pub fn rot(mut self, enabled: bool) -> Self {
+        self.feature_flags.rot = enabled;
+        self
+    }
+
+    /// Enables or disables the given lookup pattern.
+    /// Note that the patterns used by the enabled gates are not enabled automatically.
+    pub fn lookup_pattern(mut self, pattern: LookupPattern, enabled: bool) -> Self {
+        self.feature_flags.lookup_features.patterns[pattern] = enabled;
+        self
+    }
+
+    /// Sets whether joint lookups are used
+    pub fn joint_lookup_used(mut self, joint_lookup_used: bool) -> Self {
+        self.feature_flags.lookup_features.joint_lookup_used = joint_lookup_used;
+        self
+    }
+
+    /// Sets whether runtime lookup tables are used
+    pub fn uses_runtime_tables(mut self, uses_runtime_tables: bool) -> Self {
+        self.feature_flags.lookup_features.uses_runtime_tables = uses_runtime_tables;
+        self
+    }
+
+    /// Returns the [FeatureFlags] set so far
+    pub fn build(self) -> FeatureFlags {
+        self.feature_flags
+    }
+}
+
 impl<F: PrimeField + SquareRootField> Builder<F> {
     /// Set up the number of public inputs.
     /// If not invoked, it equals `0` by default.
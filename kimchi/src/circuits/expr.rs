@@ -17,8 +17,10 @@ use ark_ff::{FftField, Field, One, PrimeField, Zero};
 use ark_poly::{
     univariate::DensePolynomial, EvaluationDomain, Evaluations, Radix2EvaluationDomain as D,
 };
+use ark_std::{cfg_into_iter, cfg_iter_mut};
 use itertools::Itertools;
 use o1_utils::{foreign_field::ForeignFieldHelpers, FieldHelpers};
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::ops::{Add, AddAssign, Mul, Neg, Sub};
@@ -57,6 +59,13 @@ pub enum ExprError<Column> {
     MissingRuntime,
 }
 
+/// Errors that can arise when merging two linearizations
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MergeError<Column> {
+    #[error("both linearizations define a term for the column {0:?}")]
+    ConflictingColumn(Column),
+}
+
 pub struct Challenges<F> {
     /// The challenge alpha from the PLONK IOP.
     pub alpha: F,
@@ -589,7 +598,7 @@ pub enum Op2 {
 }
 
 /// The feature flags that can be used to enable or disable parts of constraints.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[cfg_attr(
     feature = "ocaml_types",
     derive(ocaml::IntoValue, ocaml::FromValue, ocaml_gen::Enum)
@@ -614,6 +623,32 @@ impl FeatureFlag {
     fn is_enabled(&self) -> bool {
         todo!("Handle features")
     }
+
+    /// Returns whether the feature is enabled by the given set of feature flags
+    pub fn is_enabled_in(&self, features: &FeatureFlags) -> bool {
+        use FeatureFlag::*;
+        match self {
+            RangeCheck0 => features.range_check0,
+            RangeCheck1 => features.range_check1,
+            ForeignFieldAdd => features.foreign_field_add,
+            ForeignFieldMul => features.foreign_field_mul,
+            Xor => features.xor,
+            Rot => features.rot,
+            LookupTables => features.lookup_features.patterns != LookupPatterns::default(),
+            RuntimeLookupTables => features.lookup_features.uses_runtime_tables,
+            LookupPattern(pattern) => features.lookup_features.patterns[*pattern],
+            TableWidth(width) => features
+                .lookup_features
+                .patterns
+                .into_iter()
+                .any(|feature| feature.max_joint_size() >= (*width as u32)),
+            LookupsPerRow(count) => features
+                .lookup_features
+                .patterns
+                .into_iter()
+                .any(|feature| feature.max_lookups_per_row() >= (*count as usize)),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -774,32 +809,7 @@ where
                 }
             }
             IfFeature(feature, c1, c2) => {
-                let is_enabled = {
-                    use FeatureFlag::*;
-                    match feature {
-                        RangeCheck0 => features.range_check0,
-                        RangeCheck1 => features.range_check1,
-                        ForeignFieldAdd => features.foreign_field_add,
-                        ForeignFieldMul => features.foreign_field_mul,
-                        Xor => features.xor,
-                        Rot => features.rot,
-                        LookupTables => {
-                            features.lookup_features.patterns != LookupPatterns::default()
-                        }
-                        RuntimeLookupTables => features.lookup_features.uses_runtime_tables,
-                        LookupPattern(pattern) => features.lookup_features.patterns[*pattern],
-                        TableWidth(width) => features
-                            .lookup_features
-                            .patterns
-                            .into_iter()
-                            .any(|feature| feature.max_joint_size() >= (*width as u32)),
-                        LookupsPerRow(count) => features
-                            .lookup_features
-                            .patterns
-                            .into_iter()
-                            .any(|feature| feature.max_lookups_per_row() >= (*count as usize)),
-                    }
-                };
+                let is_enabled = feature.is_enabled_in(features);
                 if is_enabled {
                     let (c1_reduced, _) = c1.apply_feature_flags_inner(features);
                     (c1_reduced, false)
@@ -816,6 +826,46 @@ where
     }
 }
 
+impl<T> Operations<T> {
+    /// Returns the set of feature flags that gate any part of the expression,
+    /// including those nested inside other `IfFeature` branches.
+    pub fn referenced_feature_flags(&self) -> HashSet<FeatureFlag> {
+        let mut flags = HashSet::new();
+        self.collect_feature_flags(&mut flags);
+        flags
+    }
+
+    /// Returns the number of nodes of the expression tree,
+    /// where each cached subexpression is counted every time it appears.
+    pub fn num_nodes(&self) -> usize {
+        use Operations::*;
+        1 + match self {
+            Atom(_) => 0,
+            Double(c) | Square(c) | Pow(c, _) | Cache(_, c) => c.num_nodes(),
+            Add(c1, c2) | Sub(c1, c2) | Mul(c1, c2) | IfFeature(_, c1, c2) => {
+                c1.num_nodes() + c2.num_nodes()
+            }
+        }
+    }
+
+    fn collect_feature_flags(&self, flags: &mut HashSet<FeatureFlag>) {
+        use Operations::*;
+        match self {
+            Atom(_) => (),
+            Double(c) | Square(c) | Pow(c, _) | Cache(_, c) => c.collect_feature_flags(flags),
+            Add(c1, c2) | Sub(c1, c2) | Mul(c1, c2) => {
+                c1.collect_feature_flags(flags);
+                c2.collect_feature_flags(flags);
+            }
+            IfFeature(feature, c1, c2) => {
+                flags.insert(*feature);
+                c1.collect_feature_flags(flags);
+                c2.collect_feature_flags(flags);
+            }
+        }
+    }
+}
+
 /// For efficiency of evaluation, we compile expressions to
 /// [reverse Polish notation](https://en.wikipedia.org/wiki/Reverse_Polish_notation)
 /// expressions, which are vectors of the below tokens.
@@ -859,6 +909,7 @@ impl<Column: Copy> Variable<Column> {
 
 impl<F: FftField, Column: Copy> PolishToken<F, Column> {
     /// Evaluate an RPN expression to a field element.
+    /// See [evaluate_polish] for the constants and challenges it expects.
     pub fn evaluate<Evaluations: ColumnEvaluations<F, Column = Column>>(
         toks: &[PolishToken<F, Column>],
         d: D<F>,
@@ -950,6 +1001,33 @@ impl<F: FftField, Column: Copy> PolishToken<F, Column> {
     }
 }
 
+/// Evaluates the RPN expression `tokens`, as produced by [Expr::to_polish], to a field element,
+/// in the same way as [Expr::evaluate_] evaluates the original expression.
+///
+/// This only needs what a verifier has at hand:
+/// - `evals`, the evaluations of the columns at `pt` for the current row and at `pt * omega`
+///   for the next one,
+/// - `d`, the domain over which the vanishing and Lagrange basis polynomials are evaluated,
+/// - `c`, of which the endomorphism coefficient, the MDS matrix and the number of
+///   zero-knowledge rows are used by the corresponding tokens,
+/// - `chals`, of which `alpha`, `beta` and `gamma` are used by the corresponding tokens,
+///   and `joint_combiner` must be set if the tokens use it (as joint lookups do).
+pub fn evaluate_polish<F, Column, Evaluations>(
+    tokens: &[PolishToken<F, Column>],
+    d: D<F>,
+    pt: F,
+    evals: &Evaluations,
+    c: &Constants<F>,
+    chals: &Challenges<F>,
+) -> Result<F, ExprError<Column>>
+where
+    F: FftField,
+    Column: Copy,
+    Evaluations: ColumnEvaluations<F, Column = Column>,
+{
+    PolishToken::evaluate(tokens, d, pt, evals, c, chals)
+}
+
 impl<C, Column> Expr<C, Column> {
     /// Convenience function for constructing cell variables.
     pub fn cell(col: Column, row: CurrOrNext) -> Expr<C, Column> {
@@ -969,6 +1047,12 @@ impl<C, Column> Expr<C, Column> {
         Expr::Atom(ExprInner::Constant(c))
     }
 
+    /// Degree of the expression as a polynomial in the columns,
+    /// where every cell and Lagrange basis counts for one.
+    pub fn constraint_degree(&self) -> u64 {
+        self.degree(1, 0)
+    }
+
     fn degree(&self, d1_size: u64, zk_rows: u64) -> u64 {
         use ExprInner::*;
         use Operations::*;
@@ -1150,7 +1234,7 @@ impl<'a, F: FftField> EvalResult<'a, F> {
     ) -> Evaluations<F, D<F>> {
         let n = res_domain.1.size();
         Evaluations::<F, D<F>>::from_vec_and_domain(
-            (0..n).into_par_iter().map(g).collect(),
+            cfg_into_iter!(0..n).map(g).collect(),
             res_domain.1,
         )
     }
@@ -1168,7 +1252,7 @@ impl<'a, F: FftField> EvalResult<'a, F> {
             (Constant(x), Constant(y)) => Constant(x + y),
             (Evals { domain, mut evals }, Constant(x))
             | (Constant(x), Evals { domain, mut evals }) => {
-                evals.evals.par_iter_mut().for_each(|e| *e += x);
+                cfg_iter_mut!(evals.evals).for_each(|e| *e += x);
                 Evals { domain, evals }
             }
             (
@@ -1190,8 +1274,7 @@ impl<'a, F: FftField> EvalResult<'a, F> {
                 let n = res_domain.1.size();
                 let scale = (domain as usize) / (res_domain.0 as usize);
                 assert!(scale != 0);
-                let v: Vec<_> = (0..n)
-                    .into_par_iter()
+                let v: Vec<_> = cfg_into_iter!(0..n)
                     .map(|i| {
                         x + evals.evals[(scale * i + (domain as usize) * shift) % evals.evals.len()]
                     })
@@ -1242,7 +1325,7 @@ impl<'a, F: FftField> EvalResult<'a, F> {
             ) => {
                 let scale = (d_sub as usize) / (d as usize);
                 assert!(scale != 0);
-                evals.evals.par_iter_mut().enumerate().for_each(|(i, e)| {
+                cfg_iter_mut!(evals.evals).enumerate().for_each(|(i, e)| {
                     *e += es_sub.evals[(scale * i + (d_sub as usize) * s) % es_sub.evals.len()];
                 });
                 Evals { evals, domain: d }
@@ -1265,8 +1348,7 @@ impl<'a, F: FftField> EvalResult<'a, F> {
                 assert!(scale2 != 0);
 
                 let n = res_domain.1.size();
-                let v: Vec<_> = (0..n)
-                    .into_par_iter()
+                let v: Vec<_> = cfg_into_iter!(0..n)
                     .map(|i| {
                         es1.evals[(scale1 * i + (d1 as usize) * s1) % es1.evals.len()]
                             + es2.evals[(scale2 * i + (d2 as usize) * s2) % es2.evals.len()]
@@ -1286,11 +1368,11 @@ impl<'a, F: FftField> EvalResult<'a, F> {
         match (self, other) {
             (Constant(x), Constant(y)) => Constant(x - y),
             (Evals { domain, mut evals }, Constant(x)) => {
-                evals.evals.par_iter_mut().for_each(|e| *e -= x);
+                cfg_iter_mut!(evals.evals).for_each(|e| *e -= x);
                 Evals { domain, evals }
             }
             (Constant(x), Evals { domain, mut evals }) => {
-                evals.evals.par_iter_mut().for_each(|e| *e = x - *e);
+                cfg_iter_mut!(evals.evals).for_each(|e| *e = x - *e);
                 Evals { domain, evals }
             }
             (
@@ -1351,7 +1433,7 @@ impl<'a, F: FftField> EvalResult<'a, F> {
             ) => {
                 let scale = (d_sub as usize) / (d as usize);
                 assert!(scale != 0);
-                evals.evals.par_iter_mut().enumerate().for_each(|(i, e)| {
+                cfg_iter_mut!(evals.evals).enumerate().for_each(|(i, e)| {
                     *e = es_sub.evals[(scale * i + (d_sub as usize) * s) % es_sub.evals.len()] - *e;
                 });
                 Evals { evals, domain: d }
@@ -1369,7 +1451,7 @@ impl<'a, F: FftField> EvalResult<'a, F> {
             ) => {
                 let scale = (d_sub as usize) / (d as usize);
                 assert!(scale != 0);
-                evals.evals.par_iter_mut().enumerate().for_each(|(i, e)| {
+                cfg_iter_mut!(evals.evals).enumerate().for_each(|(i, e)| {
                     *e -= es_sub.evals[(scale * i + (d_sub as usize) * s) % es_sub.evals.len()];
                 });
                 Evals { evals, domain: d }
@@ -1417,7 +1499,7 @@ impl<'a, F: FftField> EvalResult<'a, F> {
         match self {
             Constant(x) => Constant(x.square()),
             Evals { domain, mut evals } => {
-                evals.evals.par_iter_mut().for_each(|e| {
+                cfg_iter_mut!(evals.evals).for_each(|e| {
                     e.square_in_place();
                 });
                 Evals { domain, evals }
@@ -1442,7 +1524,7 @@ impl<'a, F: FftField> EvalResult<'a, F> {
             (Constant(x), Constant(y)) => Constant(x * y),
             (Evals { domain, mut evals }, Constant(x))
             | (Constant(x), Evals { domain, mut evals }) => {
-                evals.evals.par_iter_mut().for_each(|e| *e *= x);
+                cfg_iter_mut!(evals.evals).for_each(|e| *e *= x);
                 Evals { domain, evals }
             }
             (
@@ -1508,7 +1590,7 @@ impl<'a, F: FftField> EvalResult<'a, F> {
             ) => {
                 let scale = (d_sub as usize) / (d as usize);
                 assert!(scale != 0);
-                evals.evals.par_iter_mut().enumerate().for_each(|(i, e)| {
+                cfg_iter_mut!(evals.evals).enumerate().for_each(|(i, e)| {
                     *e *= es_sub.evals[(scale * i + (d_sub as usize) * s) % es_sub.evals.len()];
                 });
                 Evals { evals, domain: d }
@@ -1901,7 +1983,7 @@ impl<F: FftField, Column: Copy + GenericColumn> Expr<F, Column> {
                     Either::Left(x) => {
                         let x = match x {
                             EvalResult::Evals { domain, mut evals } => {
-                                evals.evals.par_iter_mut().for_each(|x| {
+                                cfg_iter_mut!(evals.evals).for_each(|x| {
                                     x.double_in_place();
                                 });
                                 return Either::Left(EvalResult::Evals { domain, evals });
@@ -2073,6 +2155,26 @@ impl<A, Column: Copy> Linearization<A, Column> {
     }
 }
 
+impl<A: Add<Output = A>, Column: PartialEq + Copy> Linearization<A, Column> {
+    /// Merge two linearizations over disjoint sets of columns, adding up their constant terms.
+    /// Returns an error if both linearizations define a term for the same column.
+    pub fn merge(self, other: Self) -> Result<Self, MergeError<Column>> {
+        if let Some((col, _)) = other
+            .index_terms
+            .iter()
+            .find(|(col, _)| self.index_terms.iter().any(|(c, _)| c == col))
+        {
+            return Err(MergeError::ConflictingColumn(*col));
+        }
+        let mut index_terms = self.index_terms;
+        index_terms.extend(other.index_terms);
+        Ok(Linearization {
+            constant_term: self.constant_term + other.constant_term,
+            index_terms,
+        })
+    }
+}
+
 impl<F: FftField, Column: PartialEq + Copy + GenericColumn>
     Linearization<Expr<ConstantExpr<F>, Column>, Column>
 {
@@ -2110,7 +2212,7 @@ impl<F: FftField, Column: Copy + Debug> Linearization<Vec<PolishToken<F, Column>
                 .get_column(idx)
                 .unwrap_or_else(|| panic!("Index polynomial {idx:?} not found"));
             let scale = e.evals.len() / n;
-            res.par_iter_mut()
+            cfg_iter_mut!(res)
                 .enumerate()
                 .for_each(|(i, r)| *r += c * e.evals[scale * i]);
         });
@@ -2148,7 +2250,7 @@ impl<F: FftField, Column: Debug + PartialEq + Copy + GenericColumn>
                 .get_column(idx)
                 .unwrap_or_else(|| panic!("Index polynomial {idx:?} not found"));
             let scale = e.evals.len() / n;
-            res.par_iter_mut()
+            cfg_iter_mut!(res)
                 .enumerate()
                 .for_each(|(i, r)| *r += c * e.evals[scale * i])
         });
@@ -3333,10 +3435,11 @@ pub mod test {
     use super::*;
     use crate::{
         circuits::{
-            constraints::ConstraintSystem, expr::constraints::ExprOps, gate::CircuitGate,
-            polynomials::generic::GenericGateSpec, wires::Wire,
+            berkeley_columns::Column, constraints::ConstraintSystem, expr::constraints::ExprOps,
+            gate::CircuitGate, polynomials::generic::GenericGateSpec, wires::Wire,
         },
         curve::KimchiCurve,
+        proof::ProofEvaluations,
         prover_index::ProverIndex,
     };
     use ark_ff::UniformRand;
@@ -3480,4 +3583,91 @@ pub mod test {
         );
         assert_eq!(test_4::<Fp, Fp>(Fp::from(5u64)), Fp::from(160u64));
     }
+
+    #[test]
+    fn test_linearization_merge() {
+        let lin1: Linearization<E<Fp>, Column> = Linearization {
+            constant_term: E::one(),
+            index_terms: vec![(Column::Coefficient(0), E::from(2u64))],
+        };
+        let lin2: Linearization<E<Fp>, Column> = Linearization {
+            constant_term: E::from(3u64),
+            index_terms: vec![(Column::Coefficient(1), E::from(4u64))],
+        };
+        let merged = lin1.clone().merge(lin2).unwrap();
+        assert_eq!(merged.constant_term, E::one() + E::from(3u64));
+        assert_eq!(
+            merged.index_terms,
+            vec![
+                (Column::Coefficient(0), E::from(2u64)),
+                (Column::Coefficient(1), E::from(4u64))
+            ]
+        );
+
+        let conflicting: Linearization<E<Fp>, Column> = Linearization {
+            constant_term: E::zero(),
+            index_terms: vec![(Column::Coefficient(0), E::from(5u64))],
+        };
+        assert_eq!(
+            lin1.merge(conflicting).unwrap_err(),
+            MergeError::ConflictingColumn(Column::Coefficient(0))
+        );
+    }
+
+    #[test]
+    fn test_evaluate_polish() {
+        let rng = &mut StdRng::from_seed([0u8; 32]);
+
+        // (w0 * w1' + 3)^2 - alpha * vanishing polynomial
+        let expr: E<Fp> = (witness_curr(0) * witness_next(1) + E::from(3u64)).pow(2)
+            - E::from(ChallengeTerm::Alpha)
+                * Expr::Atom(ExprInner::VanishesOnZeroKnowledgeAndPreviousRows);
+        let tokens = {
+            use PolishToken::*;
+            let cell = |col, row| Cell(Variable { col, row });
+            vec![
+                cell(Column::Witness(0), CurrOrNext::Curr),
+                cell(Column::Witness(1), CurrOrNext::Next),
+                Mul,
+                Constant(ConstantTerm::Literal(Fp::from(3u64))),
+                Add,
+                Pow(2),
+                Challenge(ChallengeTerm::Alpha),
+                VanishesOnZeroKnowledgeAndPreviousRows,
+                Mul,
+                Sub,
+            ]
+        };
+
+        let constants = Constants {
+            endo_coefficient: Fp::rand(rng),
+            mds: &Vesta::sponge_params().mds,
+            zk_rows: 3,
+        };
+        let challenges = Challenges {
+            alpha: Fp::rand(rng),
+            beta: Fp::rand(rng),
+            gamma: Fp::rand(rng),
+            joint_combiner: None,
+        };
+        let evals = ProofEvaluations::dummy_with_witness_evaluations(
+            array::from_fn(|_| Fp::rand(rng)),
+            array::from_fn(|_| Fp::rand(rng)),
+        );
+        let d = D::<Fp>::new(8).unwrap();
+        let pt = Fp::rand(rng);
+
+        let expected = expr
+            .evaluate_(d, pt, &evals, &constants, &challenges)
+            .unwrap();
+        assert_eq!(
+            evaluate_polish(&tokens, d, pt, &evals, &constants, &challenges).unwrap(),
+            expected
+        );
+        // the hand-written tokens evaluate like the ones the expression compiles to
+        assert_eq!(
+            evaluate_polish(&expr.to_polish(), d, pt, &evals, &constants, &challenges).unwrap(),
+            expected
+        );
+    }
 }
@@ -37,7 +37,7 @@ fn max_lookups_per_row(kinds: LookupPatterns) -> usize {
 }
 
 /// Flags for each of the hard-coded lookup patterns.
-#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[cfg_attr(
     feature = "ocaml_types",
     derive(ocaml::IntoValue, ocaml::FromValue, ocaml_gen::Struct)
@@ -129,7 +129,7 @@ impl LookupPatterns {
     }
 }
 
-#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[cfg_attr(
     feature = "ocaml_types",
     derive(ocaml::IntoValue, ocaml::FromValue, ocaml_gen::Struct)
@@ -156,6 +156,14 @@ impl LookupFeatures {
             joint_lookup_used,
         }
     }
+
+    /// Returns the same lookup features, with or without runtime lookup tables
+    pub fn with_runtime_tables(self, uses_runtime_tables: bool) -> Self {
+        LookupFeatures {
+            uses_runtime_tables,
+            ..self
+        }
+    }
 }
 
 /// Describes the desired lookup configuration.
@@ -200,10 +200,10 @@ pub fn create<F: PrimeField>(
     }
 
     // Create the foreign field multiplication witness rows
-    let left_input = left_input.to_field_limbs();
-    let right_input = right_input.to_field_limbs();
-    let remainder = remainder.to_compact_field_limbs();
-    let quotient = quotient.to_field_limbs();
+    let left_input: [F; 3] = left_input.to_field_limbs();
+    let right_input: [F; 3] = right_input.to_field_limbs();
+    let remainder: [F; 2] = remainder.to_compact_field_limbs();
+    let quotient: [F; 3] = quotient.to_field_limbs();
     witness::init(
         &mut witness,
         0,
@@ -220,7 +220,7 @@ pub fn create<F: PrimeField>(
             "quotient0" => quotient[0],
             "quotient1" => quotient[1],
             "quotient2" => quotient[2],
-            "quotient_hi_bound" => quotient_hi_bound.into(),
+            "quotient_hi_bound" => quotient_hi_bound,
             "product1_lo" => product1_lo,
             "product1_hi_0" => product1_hi_0,
             "product1_hi_1" => product1_hi_1,
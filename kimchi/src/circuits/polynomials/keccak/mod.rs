@@ -76,6 +76,16 @@ pub const RC: [u64; ROUNDS + 1] = [
     0x8000000080008008,
 ];
 
+/// Byte order in which a bytestring of 16-bit dense quarters is laid out, see [Keccak::bytestring_with]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Endianness {
+    /// Least significant byte first, as in the Keccak state, which gives the usual digest (default)
+    #[default]
+    Little,
+    /// The whole bytestring of [Endianness::Little] reversed, see [Keccak::bytestring_be]
+    Big,
+}
+
 /// Naive Keccak structure
 pub struct Keccak {}
 
@@ -157,6 +167,11 @@ impl Keccak {
 
     /// On input a vector of 16-bit dense quarters, outputs a vector of 8-bit bytes in the right order for Keccak
     pub fn bytestring(dense: &[u64]) -> Vec<u64> {
+        Self::bytestring_with(dense, Endianness::default())
+    }
+
+    /// On input a vector of 16-bit dense quarters, outputs a vector of 8-bit bytes in little endian order
+    pub fn bytestring_le(dense: &[u64]) -> Vec<u64> {
         dense
             .iter()
             .map(|x| vec![x % 256, x / 256])
@@ -167,6 +182,22 @@ impl Keccak {
             .collect()
     }
 
+    /// On input a vector of 16-bit dense quarters, outputs a vector of 8-bit bytes in big endian order,
+    /// that is, the reverse of [Keccak::bytestring_le]
+    pub fn bytestring_be(dense: &[u64]) -> Vec<u64> {
+        let mut bytes = Self::bytestring_le(dense);
+        bytes.reverse();
+        bytes
+    }
+
+    /// On input a vector of 16-bit dense quarters, outputs a vector of 8-bit bytes in the given order
+    pub fn bytestring_with(dense: &[u64], endianness: Endianness) -> Vec<u64> {
+        match endianness {
+            Endianness::Little => Self::bytestring_le(dense),
+            Endianness::Big => Self::bytestring_be(dense),
+        }
+    }
+
     /// On input a 200-byte vector, generates a vector of 100 expanded quarters representing the 1600-bit state
     pub fn expand_state(state: &[u8]) -> Vec<u64> {
         let mut expanded = vec![];
@@ -186,13 +217,27 @@ impl Keccak {
 
     /// Pads the message with the 10*1 rule until reaching a length that is a multiple of the rate
     pub fn pad(message: &[u8]) -> Vec<u8> {
+        Self::pad_with(message, 0x01)
+    }
+
+    /// Pads the message with the 10*1 rule until reaching a length that is a multiple of the rate,
+    /// where the first byte of the padding is the given domain separation byte
+    /// (`0x01` for Keccak and `0x06` for SHA3)
+    pub fn pad_with(message: &[u8], domain: u8) -> Vec<u8> {
+        Self::pad_with_rate(message, domain, RATE_IN_BYTES)
+    }
+
+    /// Pads the message with the 10*1 rule until reaching a length that is a multiple of
+    /// the given rate in bytes, where the first byte of the padding is the given domain
+    /// separation byte, see [Self::pad_with]
+    pub fn pad_with_rate(message: &[u8], domain: u8, rate_in_bytes: usize) -> Vec<u8> {
         let msg_len = message.len();
-        let pad_len = Self::padded_length(msg_len);
+        let pad_len = (msg_len / rate_in_bytes + 1) * rate_in_bytes;
         let mut padded = vec![0; pad_len];
         for (i, byte) in message.iter().enumerate() {
             padded[i] = *byte;
         }
-        padded[msg_len] = 0x01;
+        padded[msg_len] = domain;
         padded[pad_len - 1] += 0x80;
 
         padded
@@ -396,6 +441,19 @@ mod tests {
         assert_eq!(not, Keccak::collapse(&[sparse_not])[0]);
     }
 
+    #[test]
+    // Checks that the big endian bytestring is the byte-reversed little endian one
+    fn test_bytestring_endianness() {
+        let dense = Keccak::decompose(0x0123456789abcdef);
+        let le = Keccak::bytestring_le(&dense);
+        let be = Keccak::bytestring_be(&dense);
+        assert_eq!(le, vec![0xef, 0xcd, 0xab, 0x89, 0x67, 0x45, 0x23, 0x01]);
+        assert_eq!(be, vec![0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef]);
+        assert_eq!(Keccak::bytestring(&dense), le);
+        assert_eq!(Keccak::bytestring_with(&dense, Endianness::Big), be);
+        assert_eq!(be.iter().rev().copied().collect::<Vec<u64>>(), le);
+    }
+
     #[test]
     // Checks that the padding length is correctly computed
     fn test_pad_length() {
@@ -425,4 +483,23 @@ mod tests {
         assert_eq!(padded[message.len()], 0x01);
         assert_eq!(padded[padded.len() - 1], 0x80);
     }
+
+    #[test]
+    // Tests that a rate-aligned message gets a whole extra block of padding
+    fn test_num_blocks() {
+        for (length, blocks) in [
+            (0, 1),
+            (RATE_IN_BYTES - 1, 1),
+            (RATE_IN_BYTES, 2),
+            (RATE_IN_BYTES + 1, 2),
+            (2 * RATE_IN_BYTES, 3),
+        ] {
+            assert_eq!(Keccak::num_blocks(length), blocks, "length {length}");
+            let padded = Keccak::pad(&vec![0xab; length]);
+            assert_eq!(padded.len(), blocks * RATE_IN_BYTES, "length {length}");
+            // The padding starts right after the message and ends with the last byte
+            assert_eq!(padded[length] & 0x01, 0x01);
+            assert_eq!(padded[padded.len() - 1] & 0x80, 0x80);
+        }
+    }
 }
@@ -55,10 +55,12 @@ use ark_poly::{
     EvaluationDomain, Evaluations, Radix2EvaluationDomain as D,
 };
 use ark_poly::{Polynomial, UVPolynomial};
+use ark_std::cfg_into_iter;
 use blake2::{Blake2b512, Digest};
 use o1_utils::{ExtendedDensePolynomial, ExtendedEvaluations};
 use poly_commitment::OpenProof;
 use rand::{CryptoRng, RngCore};
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use std::array;
 
@@ -352,8 +354,7 @@ impl<F: PrimeField, G: KimchiCurve<ScalarField = F>, OpeningProof: OpenProof<G>>
         let evals8 = &self.column_evaluations.permutation_coefficients8[PERMUTS - 1].evals;
         const STRIDE: usize = 8;
         let n = evals8.len() / STRIDE;
-        let evals = (0..n)
-            .into_par_iter()
+        let evals = cfg_into_iter!(0..n)
             .map(|i| scalar * evals8[STRIDE * i])
             .collect();
         Evaluations::from_vec_and_domain(evals, D::new(n).unwrap())
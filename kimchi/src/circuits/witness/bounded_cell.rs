@@ -0,0 +1,33 @@
+use super::{variables::Variables, WitnessCell, WitnessCtx};
+use ark_ff::PrimeField;
+
+/// Witness cell assigned from a variable that must be smaller than a given bound
+/// See [Variables] for more details
+pub struct BoundedCell<'a, F: PrimeField> {
+    name: &'a str,
+    bound: F,
+}
+
+impl<'a, F: PrimeField> BoundedCell<'a, F> {
+    /// Create witness cell assigned from a variable name, whose value
+    /// must be in the range `[0, bound)` when interpreted as an integer
+    pub fn create(name: &'a str, bound: F) -> Box<BoundedCell<'a, F>> {
+        Box::new(BoundedCell { name, bound })
+    }
+}
+
+impl<'a, F: PrimeField, const W: usize> WitnessCell<F, F, W> for BoundedCell<'a, F> {
+    fn value(&self, _witness: &mut [Vec<F>; W], variables: &Variables<F>, _ctx: WitnessCtx) -> F {
+        let value = variables[self.name];
+        assert!(
+            value.into_repr() < self.bound.into_repr(),
+            "variable `{}` out of bounds of `BoundedCell`",
+            self.name
+        );
+        value
+    }
+
+    fn variable_names(&self) -> Vec<&str> {
+        vec![self.name]
+    }
+}
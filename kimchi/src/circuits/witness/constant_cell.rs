@@ -1,4 +1,4 @@
-use super::{variables::Variables, WitnessCell};
+use super::{variables::Variables, WitnessCell, WitnessCtx};
 use ark_ff::Field;
 
 /// Witness cell with constant value
@@ -13,8 +13,16 @@ impl<F: Field> ConstantCell<F> {
     }
 }
 
-impl<F: Field, const W: usize> WitnessCell<F, F, W> for ConstantCell<F> {
-    fn value(&self, _witness: &mut [Vec<F>; W], _variables: &Variables<F>, _index: usize) -> F {
+impl<F: Field, T, const W: usize> WitnessCell<F, T, W> for ConstantCell<F> {
+    fn value(&self, _witness: &mut [Vec<F>; W], _variables: &Variables<T>, _ctx: WitnessCtx) -> F {
         self.value
     }
 }
+
+/// Create a row of witness cells with constant values, one per byte of `bytes`
+pub fn bytes_row<F: Field, const W: usize>(bytes: &[u8]) -> Vec<Box<dyn WitnessCell<F, F, W>>> {
+    bytes
+        .iter()
+        .map(|byte| -> Box<dyn WitnessCell<F, F, W>> { ConstantCell::create(F::from(*byte)) })
+        .collect()
+}
@@ -1,7 +1,7 @@
 use ark_ff::Field;
 use o1_utils::FieldHelpers;
 
-use super::{variables::Variables, WitnessCell};
+use super::{variables::Variables, CellDep, WitnessCell, WitnessCtx};
 
 /// Witness cell copied from bits of another witness cell
 pub struct CopyBitsCell {
@@ -23,9 +23,16 @@ impl CopyBitsCell {
     }
 }
 
-impl<F: Field, const W: usize> WitnessCell<F, F, W> for CopyBitsCell {
-    fn value(&self, witness: &mut [Vec<F>; W], _variables: &Variables<F>, _index: usize) -> F {
+impl<F: Field, T, const W: usize> WitnessCell<F, T, W> for CopyBitsCell {
+    fn value(&self, witness: &mut [Vec<F>; W], _variables: &Variables<T>, _ctx: WitnessCtx) -> F {
         F::from_bits(&witness[self.col][self.row].to_bits()[self.start..self.end])
             .expect("failed to deserialize field bits for copy bits cell")
     }
+
+    fn dependencies(&self) -> Vec<CellDep> {
+        vec![CellDep::Cell {
+            row: self.row,
+            col: self.col,
+        }]
+    }
 }
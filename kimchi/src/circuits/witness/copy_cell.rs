@@ -1,6 +1,6 @@
 use ark_ff::Field;
 
-use super::{variables::Variables, WitnessCell};
+use super::{variables::Variables, CellDep, WitnessCell, WitnessCtx};
 
 /// Witness cell copied from another witness cell
 pub struct CopyCell {
@@ -15,8 +15,15 @@ impl CopyCell {
     }
 }
 
-impl<F: Field, const W: usize> WitnessCell<F, F, W> for CopyCell {
-    fn value(&self, witness: &mut [Vec<F>; W], _variables: &Variables<F>, _index: usize) -> F {
+impl<F: Field, T, const W: usize> WitnessCell<F, T, W> for CopyCell {
+    fn value(&self, witness: &mut [Vec<F>; W], _variables: &Variables<T>, _ctx: WitnessCtx) -> F {
         witness[self.col][self.row]
     }
+
+    fn dependencies(&self) -> Vec<CellDep> {
+        vec![CellDep::Cell {
+            row: self.row,
+            col: self.col,
+        }]
+    }
 }
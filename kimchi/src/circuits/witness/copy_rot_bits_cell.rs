@@ -0,0 +1,52 @@
+use ark_ff::Field;
+use o1_utils::FieldHelpers;
+
+use super::{variables::Variables, CellDep, WitnessCell, WitnessCtx};
+
+/// Witness cell copied from bits of another witness cell, rotated within the window of bits
+pub struct CopyRotBitsCell {
+    row: usize,
+    col: usize,
+    start: usize, // inclusive
+    end: usize,   // exclusive
+    rot: usize,
+}
+
+impl CopyRotBitsCell {
+    /// Create witness cell copied from bits [start, end) of the witness cell at position (row, col),
+    /// rotated to the left by `rot` bits within the window of width `end - start`.
+    /// The window must not be empty, and the rotation is taken modulo its width.
+    pub fn create(
+        row: usize,
+        col: usize,
+        start: usize,
+        end: usize,
+        rot: usize,
+    ) -> Box<CopyRotBitsCell> {
+        assert!(start < end, "empty window of bits in `CopyRotBitsCell`");
+        Box::new(CopyRotBitsCell {
+            row,
+            col,
+            start,
+            end,
+            rot,
+        })
+    }
+}
+
+impl<F: Field, T, const W: usize> WitnessCell<F, T, W> for CopyRotBitsCell {
+    fn value(&self, witness: &mut [Vec<F>; W], _variables: &Variables<T>, _ctx: WitnessCtx) -> F {
+        let mut bits = witness[self.col][self.row].to_bits()[self.start..self.end].to_vec();
+        // bits are little-endian, so rotating to the left moves them towards the end
+        let width = bits.len();
+        bits.rotate_right(self.rot % width);
+        F::from_bits(&bits).expect("failed to deserialize field bits for copy rot bits cell")
+    }
+
+    fn dependencies(&self) -> Vec<CellDep> {
+        vec![CellDep::Cell {
+            row: self.row,
+            col: self.col,
+        }]
+    }
+}
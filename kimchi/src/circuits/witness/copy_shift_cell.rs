@@ -1,4 +1,4 @@
-use super::{variables::Variables, WitnessCell};
+use super::{variables::Variables, CellDep, WitnessCell, WitnessCtx};
 use ark_ff::Field;
 
 /// Witness cell copied from another cell and shifted
@@ -15,8 +15,15 @@ impl CopyShiftCell {
     }
 }
 
-impl<F: Field, const W: usize> WitnessCell<F, F, W> for CopyShiftCell {
-    fn value(&self, witness: &mut [Vec<F>; W], _variables: &Variables<F>, _index: usize) -> F {
+impl<F: Field, T, const W: usize> WitnessCell<F, T, W> for CopyShiftCell {
+    fn value(&self, witness: &mut [Vec<F>; W], _variables: &Variables<T>, _ctx: WitnessCtx) -> F {
         F::from(2u32).pow([self.shift]) * witness[self.col][self.row]
     }
+
+    fn dependencies(&self) -> Vec<CellDep> {
+        vec![CellDep::Cell {
+            row: self.row,
+            col: self.col,
+        }]
+    }
 }
@@ -0,0 +1,42 @@
+use super::{variables::Variables, CellDep, WitnessCell, WitnessCtx};
+use ark_ff::PrimeField;
+use num_bigint::BigUint;
+use num_integer::Integer;
+use o1_utils::FieldHelpers;
+
+/// Witness cell copied from another cell and shifted to the right
+pub struct CopyShiftRightCell {
+    row: usize,
+    col: usize,
+    shift: u64,
+}
+
+impl CopyShiftRightCell {
+    /// Create witness cell copied from the witness cell at position (row, col) and then divided by 2^shift,
+    /// whose integer representation must be a multiple of 2^shift
+    pub fn create(row: usize, col: usize, shift: u64) -> Box<CopyShiftRightCell> {
+        Box::new(CopyShiftRightCell { row, col, shift })
+    }
+}
+
+impl<F: PrimeField, T, const W: usize> WitnessCell<F, T, W> for CopyShiftRightCell {
+    fn value(&self, witness: &mut [Vec<F>; W], _variables: &Variables<T>, _ctx: WitnessCtx) -> F {
+        let value = witness[self.col][self.row].to_biguint();
+        let (quotient, remainder) = value.div_rem(&(BigUint::from(1u32) << self.shift));
+        assert!(
+            remainder == BigUint::from(0u32),
+            "cell ({}, {}) is not a multiple of 2^{} in `CopyShiftRightCell`",
+            self.row,
+            self.col,
+            self.shift
+        );
+        F::from_biguint(&quotient).expect("quotient should fit in the field")
+    }
+
+    fn dependencies(&self) -> Vec<CellDep> {
+        vec![CellDep::Cell {
+            row: self.row,
+            col: self.col,
+        }]
+    }
+}
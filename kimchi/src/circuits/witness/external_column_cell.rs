@@ -0,0 +1,26 @@
+use super::{variables::Variables, WitnessCell, WitnessCtx};
+use ark_ff::Field;
+use std::sync::Arc;
+
+/// Witness cell assigned from a column computed outside of the layout
+pub struct ExternalColumnCell<F: Field> {
+    values: Arc<Vec<F>>,
+}
+
+impl<F: Field> ExternalColumnCell<F> {
+    /// Create witness cell assigned from the element of the external column `values`
+    /// located at the same row of the witness as the cell
+    pub fn create(values: Arc<Vec<F>>) -> Box<ExternalColumnCell<F>> {
+        Box::new(ExternalColumnCell { values })
+    }
+}
+
+impl<F: Field, T, const W: usize> WitnessCell<F, T, W> for ExternalColumnCell<F> {
+    fn value(&self, _witness: &mut [Vec<F>; W], _variables: &Variables<T>, ctx: WitnessCtx) -> F {
+        assert!(
+            ctx.row + ctx.offset < self.values.len(),
+            "row out of bounds of `ExternalColumnCell`"
+        );
+        self.values[ctx.row + ctx.offset]
+    }
+}
@@ -0,0 +1,30 @@
+use super::{variables::Variables, WitnessCell, WitnessCtx};
+use ark_ff::PrimeField;
+use num_bigint::BigUint;
+use o1_utils::FieldHelpers;
+
+/// Witness cell assigned from a variable reduced modulo a foreign field modulus
+/// See [Variables] for more details
+pub struct ForeignReduceCell<'a> {
+    name: &'a str,
+    modulus: BigUint,
+}
+
+impl<'a> ForeignReduceCell<'a> {
+    /// Create witness cell assigned from the integer value of a variable name
+    /// reduced modulo the foreign field `modulus`
+    pub fn create(name: &'a str, modulus: BigUint) -> Box<ForeignReduceCell<'a>> {
+        Box::new(ForeignReduceCell { name, modulus })
+    }
+}
+
+impl<'a, F: PrimeField, const W: usize> WitnessCell<F, F, W> for ForeignReduceCell<'a> {
+    fn value(&self, _witness: &mut [Vec<F>; W], variables: &Variables<F>, _ctx: WitnessCtx) -> F {
+        F::from_biguint(&(variables[self.name].to_biguint() % &self.modulus))
+            .expect("failed to deserialize reduced value for foreign reduce cell")
+    }
+
+    fn variable_names(&self) -> Vec<&str> {
+        vec![self.name]
+    }
+}
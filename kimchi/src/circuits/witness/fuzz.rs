@@ -0,0 +1,85 @@
+use super::{
+    try_init, validate_witness, variables, ConstantCell, CopyBitsCell, CopyCell, CopyShiftCell,
+    VariableCell, Variables, WitnessCell,
+};
+use crate::{circuits::polynomial::COLUMNS, error::WitnessError};
+use ark_ff::PrimeField;
+use rand::{Rng, SeedableRng};
+use std::array;
+
+/// Maximum number of rows of the layouts derived by [fuzz_init]
+const FUZZ_MAX_ROWS: usize = 4;
+
+/// Fuzzing entry point for the witness cell framework, meant to be called from a `cargo-fuzz` target.
+/// It deterministically derives a layout of at most [FUZZ_MAX_ROWS] rows and a variable scope
+/// from `data`, and initializes a witness with them.
+///
+/// Malformed layouts are reported as a [WitnessError] by [try_init]. Note that the derived
+/// layouts only reference cells and bit ranges that are within bounds of the witness,
+/// so that any panic found is a bug.
+pub fn fuzz_init<F: PrimeField>(data: &[u8]) -> Result<(), WitnessError> {
+    // Once the input is exhausted, keep reading zeros
+    let mut bytes = data.iter().copied().chain(std::iter::repeat(0));
+    let mut next = move || bytes.next().unwrap() as usize;
+
+    let rows = next() % FUZZ_MAX_ROWS + 1;
+    let a = F::from(u64::from_le_bytes(array::from_fn(|_| next() as u8)));
+    let b = F::from(u64::from_le_bytes(array::from_fn(|_| next() as u8)));
+
+    let layout: Vec<Vec<Box<dyn WitnessCell<F>>>> = (0..rows)
+        .map(|_| {
+            (0..COLUMNS)
+                .map(|_| {
+                    let cell: Box<dyn WitnessCell<F>> = match next() % 5 {
+                        0 => ConstantCell::create(F::from(next() as u64)),
+                        1 => VariableCell::create(if next() % 2 == 0 { "a" } else { "b" }),
+                        2 => CopyCell::create(next() % rows, next() % COLUMNS),
+                        3 => CopyShiftCell::create(
+                            next() % rows,
+                            next() % COLUMNS,
+                            next() as u64 % 64,
+                        ),
+                        _ => {
+                            let (row, col) = (next() % rows, next() % COLUMNS);
+                            // Keep the range small enough to always fit in a field element
+                            let start = next() % 192;
+                            let end = start + next() % 65;
+                            CopyBitsCell::create(row, col, start, end)
+                        }
+                    };
+                    cell
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut witness: [Vec<F>; COLUMNS] = array::from_fn(|_| vec![F::zero(); rows]);
+    try_init(&mut witness, 0, &layout, &variables!(a, b))
+}
+
+/// Property testing helper for layouts: initializes `iters` witnesses from `layout`, with the
+/// variables drawn by `gen` from an rng of type `R` seeded with `seed`, and checks each of them
+/// against the layout with [validate_witness]. Generators should favour edge values like zero or `-1`.
+///
+/// # Panics
+///
+/// Will panic on the first witness that fails to initialize or to validate,
+/// with the iteration and the position of the offending cell.
+pub fn fuzz_layout<'a, F: PrimeField, T, R: Rng + SeedableRng, const W: usize>(
+    layout: &[Vec<Box<dyn WitnessCell<F, T, W> + '_>>],
+    gen: impl Fn(&mut R) -> Variables<'a, T>,
+    iters: usize,
+    seed: R::Seed,
+) {
+    let rng = &mut R::from_seed(seed);
+    for iter in 0..iters {
+        let variables = gen(rng);
+        let mut witness: [Vec<F>; W] = array::from_fn(|_| vec![F::zero(); layout.len()]);
+        if let Err(err) = try_init(&mut witness, 0, layout, &variables) {
+            panic!("failed to initialize witness at iteration {iter}: {err}");
+        }
+        if let Err((row, col)) = validate_witness(&witness, 0, layout, &variables) {
+            panic!("inconsistent witness cell ({row}, {col}) at iteration {iter}");
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use super::{variables::Variables, WitnessCell};
+use super::{variables::Variables, WitnessCell, WitnessCtx};
+use crate::error::WitnessError;
 use ark_ff::Field;
 
 /// Witness cell assigned from an indexable variable
@@ -19,11 +20,95 @@ impl<'a> IndexCell<'a> {
 }
 
 impl<'a, F: Field, const W: usize> WitnessCell<F, Vec<F>, W> for IndexCell<'a> {
-    fn value(&self, _witness: &mut [Vec<F>; W], variables: &Variables<Vec<F>>, index: usize) -> F {
-        assert!(index < self.length, "index out of bounds of `IndexCell`");
-        variables[self.name][index]
+    fn value(
+        &self,
+        _witness: &mut [Vec<F>; W],
+        variables: &Variables<Vec<F>>,
+        ctx: WitnessCtx,
+    ) -> F {
+        assert!(
+            ctx.index < self.length,
+            "index out of bounds of `IndexCell`"
+        );
+        variables[self.name][ctx.index]
     }
     fn length(&self) -> usize {
         self.length
     }
+
+    fn variable_names(&self) -> Vec<&str> {
+        vec![self.name]
+    }
+
+    fn check_variables(&self, variables: &Variables<Vec<F>>) -> Result<(), WitnessError> {
+        check_length(self.name, self.length, variables)
+    }
+}
+
+/// Checks that the indexable variable `name` is present and has at least `length` elements
+fn check_length<F>(
+    name: &str,
+    length: usize,
+    variables: &Variables<Vec<F>>,
+) -> Result<(), WitnessError> {
+    if !variables.contains(name) {
+        return Err(WitnessError::MissingVariable(name.to_string()));
+    }
+    let len = variables[name].len();
+    if len < length {
+        return Err(WitnessError::VariableTooShort {
+            name: name.to_string(),
+            len,
+            need: length,
+        });
+    }
+    Ok(())
+}
+
+/// Witness cell assigned from an indexable variable, spanning the whole variable
+/// See [Variables] for more details
+///
+/// Note that the rows of a layout are initialized iterating `0..length()` for each cell,
+/// so the cell covers one column per element of the variable, in order.
+pub struct DynIndexCell<'a> {
+    name: &'a str,
+    length: usize,
+}
+
+impl<'a> DynIndexCell<'a> {
+    /// Create witness cell assigned from a variable name, whose length
+    /// is the length of that variable in `variables`
+    pub fn create<F>(name: &'a str, variables: &Variables<Vec<F>>) -> Box<DynIndexCell<'a>> {
+        Box::new(DynIndexCell {
+            name,
+            length: variables[name].len(),
+        })
+    }
+}
+
+impl<'a, F: Field, const W: usize> WitnessCell<F, Vec<F>, W> for DynIndexCell<'a> {
+    fn value(
+        &self,
+        _witness: &mut [Vec<F>; W],
+        variables: &Variables<Vec<F>>,
+        ctx: WitnessCtx,
+    ) -> F {
+        assert!(
+            ctx.index < self.length,
+            "index out of bounds of `DynIndexCell`"
+        );
+        variables[self.name][ctx.index]
+    }
+
+    fn length(&self) -> usize {
+        self.length
+    }
+
+    fn variable_names(&self) -> Vec<&str> {
+        vec![self.name]
+    }
+
+    fn check_variables(&self, variables: &Variables<Vec<F>>) -> Result<(), WitnessError> {
+        check_length(self.name, self.length, variables)
+    }
 }
@@ -0,0 +1,43 @@
+use super::{variables::Variables, CellDep, WitnessCell, WitnessCtx};
+use ark_ff::PrimeField;
+use o1_utils::FieldHelpers;
+
+/// Witness cell copied from a column of the witness at a row given by another witness cell
+pub struct IndirectCell {
+    index_row: usize,
+    index_col: usize,
+    src_col: usize,
+}
+
+impl IndirectCell {
+    /// Create witness cell copied from the column `src_col` at the row given by
+    /// the integer value of the witness cell at position (index_row, index_col)
+    pub fn create(index_row: usize, index_col: usize, src_col: usize) -> Box<IndirectCell> {
+        Box::new(IndirectCell {
+            index_row,
+            index_col,
+            src_col,
+        })
+    }
+}
+
+impl<F: PrimeField, T, const W: usize> WitnessCell<F, T, W> for IndirectCell {
+    fn value(&self, witness: &mut [Vec<F>; W], _variables: &Variables<T>, _ctx: WitnessCtx) -> F {
+        let index = witness[self.index_col][self.index_row].to_biguint();
+        let row = usize::try_from(&index)
+            .ok()
+            .filter(|&row| row < witness[self.src_col].len())
+            .unwrap_or_else(|| panic!("index {index} out of bounds of `IndirectCell`"));
+        witness[self.src_col][row]
+    }
+
+    fn dependencies(&self) -> Vec<CellDep> {
+        vec![
+            CellDep::Cell {
+                row: self.index_row,
+                col: self.index_col,
+            },
+            CellDep::Column(self.src_col),
+        ]
+    }
+}
@@ -0,0 +1,38 @@
+use super::{variables::Variables, CellDep, WitnessCell, WitnessCtx};
+use ark_ff::Field;
+
+/// Witness cell holding a weighted sum of other witness cells
+pub struct InnerProductCell<F: Field> {
+    terms: Vec<((usize, usize), F)>,
+}
+
+impl<F: Field> InnerProductCell<F> {
+    /// Create a witness cell holding the sum of `coeff * witness[col][row]` over the
+    /// given `((row, col), coeff)` terms. Note that the source cells must be initialized
+    /// before this cell.
+    pub fn create(terms: &[((usize, usize), F)]) -> Box<InnerProductCell<F>> {
+        Box::new(InnerProductCell {
+            terms: terms.to_vec(),
+        })
+    }
+}
+
+impl<F: Field, T, const W: usize> WitnessCell<F, T, W> for InnerProductCell<F> {
+    fn value(&self, witness: &mut [Vec<F>; W], _variables: &Variables<T>, _ctx: WitnessCtx) -> F {
+        self.terms
+            .iter()
+            .fold(F::zero(), |acc, ((row, col), coeff)| {
+                acc + *coeff * witness[*col][*row]
+            })
+    }
+
+    fn dependencies(&self) -> Vec<CellDep> {
+        self.terms
+            .iter()
+            .map(|((row, col), _)| CellDep::Cell {
+                row: *row,
+                col: *col,
+            })
+            .collect()
+    }
+}
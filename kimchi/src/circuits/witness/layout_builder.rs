@@ -0,0 +1,66 @@
+use super::{try_init, variables::Variables, WitnessCell};
+use crate::{circuits::polynomial::COLUMNS, error::WitnessError};
+use ark_ff::PrimeField;
+
+/// Writes successive layouts into a witness, each one right below the rows of the previous one,
+/// so that gadgets can be stacked without keeping track of their offsets, see [LayoutBuilder::add]
+pub struct LayoutBuilder<'a, F: PrimeField, const W: usize = COLUMNS> {
+    witness: &'a mut [Vec<F>; W],
+    start: usize,
+    offset: usize,
+}
+
+impl<'a, F: PrimeField, const W: usize> LayoutBuilder<'a, F, W> {
+    /// Create a builder writing layouts into `witness` from row `offset` onwards
+    pub fn new(witness: &'a mut [Vec<F>; W], offset: usize) -> Self {
+        LayoutBuilder {
+            witness,
+            start: offset,
+            offset,
+        }
+    }
+
+    /// Initializes `layout` with `variables` right below the previously added layouts,
+    /// as [init](super::init) does, growing the witness with zero rows if it is too short.
+    /// Cells referring to absolute rows, like [CopyCell](super::CopyCell), are not moved
+    /// along with the layout, unlike [RelativeCopyCell](super::RelativeCopyCell).
+    pub fn add<T>(
+        &mut self,
+        layout: &[Vec<Box<dyn WitnessCell<F, T, W> + '_>>],
+        variables: &Variables<T>,
+    ) -> &mut Self {
+        self.try_add(layout, variables)
+            .expect("failed to initialize witness")
+    }
+
+    /// Fallible version of [LayoutBuilder::add]
+    ///
+    /// # Errors
+    ///
+    /// Will give error if any cell of `layout` fails to initialize, see [try_init].
+    pub fn try_add<T>(
+        &mut self,
+        layout: &[Vec<Box<dyn WitnessCell<F, T, W> + '_>>],
+        variables: &Variables<T>,
+    ) -> Result<&mut Self, WitnessError> {
+        let need = self.offset + layout.len();
+        for column in self.witness.iter_mut() {
+            if column.len() < need {
+                column.resize(need, F::zero());
+            }
+        }
+        try_init(self.witness, self.offset, layout, variables)?;
+        self.offset = need;
+        Ok(self)
+    }
+
+    /// Returns the row at which the next layout will be written
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the total number of rows written by the added layouts
+    pub fn finish(self) -> usize {
+        self.offset - self.start
+    }
+}
@@ -0,0 +1,185 @@
+use super::{
+    ConstantCell, CopyBitsCell, CopyCell, CopyShiftCell, IndexCell, VariableBitsCell, VariableCell,
+    Variables, WitnessCell,
+};
+use crate::error::EncodingError;
+use ark_ff::Field;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_with::serde_as;
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+/// Serializable description of a witness cell, mirroring each of the concrete cells.
+/// See [to_layout] and [to_index_layout] to build the layout described by a specification.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LayoutSpec<F: Field> {
+    /// See [ConstantCell]
+    Constant {
+        #[serde_as(as = "o1_utils::serialization::SerdeAs")]
+        value: F,
+    },
+    /// See [CopyCell]
+    Copy { row: usize, col: usize },
+    /// See [CopyBitsCell]
+    CopyBits {
+        row: usize,
+        col: usize,
+        start: usize,
+        end: usize,
+    },
+    /// See [CopyShiftCell]
+    CopyShift { row: usize, col: usize, shift: u64 },
+    /// See [VariableCell]
+    Variable { name: String },
+    /// See [VariableBitsCell]
+    VariableBits {
+        name: String,
+        start: usize,
+        end: Option<usize>,
+    },
+    /// See [IndexCell]
+    Index {
+        name: String,
+        from: usize,
+        to: usize,
+    },
+}
+
+impl<F: Field> LayoutSpec<F> {
+    /// Builds the witness cell of the specification, if it does not read any variable
+    fn to_constant_cell<'a, T, const W: usize>(
+        &self,
+    ) -> Option<Box<dyn WitnessCell<F, T, W> + 'a>> {
+        match self {
+            LayoutSpec::Constant { value } => Some(ConstantCell::create(*value)),
+            LayoutSpec::Copy { row, col } => Some(CopyCell::create(*row, *col)),
+            LayoutSpec::CopyBits {
+                row,
+                col,
+                start,
+                end,
+            } => Some(CopyBitsCell::create(*row, *col, *start, *end)),
+            LayoutSpec::CopyShift { row, col, shift } => {
+                Some(CopyShiftCell::create(*row, *col, *shift))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Builds the layout described by `spec`, whose variables are single field elements.
+/// Panics if the specification contains an [IndexCell], see [to_index_layout] instead.
+pub fn to_layout<F: Field, const W: usize>(
+    spec: &[Vec<LayoutSpec<F>>],
+) -> Vec<Vec<Box<dyn WitnessCell<F, F, W> + '_>>> {
+    spec.iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| -> Box<dyn WitnessCell<F, F, W> + '_> {
+                    match cell {
+                        LayoutSpec::Variable { name } => VariableCell::create(name),
+                        LayoutSpec::VariableBits { name, start, end } => {
+                            VariableBitsCell::create(name, *start, *end)
+                        }
+                        LayoutSpec::Index { .. } => {
+                            panic!(
+                                "`IndexCell` requires indexable variables, use `to_index_layout`"
+                            )
+                        }
+                        _ => cell.to_constant_cell().unwrap(),
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Builds the layout described by `spec`, whose variables are vectors of field elements.
+/// Panics if the specification contains a [VariableCell] or a [VariableBitsCell], see [to_layout] instead.
+#[allow(clippy::type_complexity)]
+pub fn to_index_layout<F: Field, const W: usize>(
+    spec: &[Vec<LayoutSpec<F>>],
+) -> Vec<Vec<Box<dyn WitnessCell<F, Vec<F>, W> + '_>>> {
+    spec.iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| -> Box<dyn WitnessCell<F, Vec<F>, W> + '_> {
+                    match cell {
+                        LayoutSpec::Index { name, from, to } => IndexCell::create(name, *from, *to),
+                        LayoutSpec::Variable { .. } | LayoutSpec::VariableBits { .. } => {
+                            panic!("variable cells require field variables, use `to_layout`")
+                        }
+                        _ => cell.to_constant_cell().unwrap(),
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Layout specification together with the values of its variables, reproducing
+/// a witness computation, see [dump_fixture] and [load_fixture]
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WitnessFixture<F: Field> {
+    /// Specification of the layout, see [to_layout]
+    #[serde(bound = "LayoutSpec<F>: Serialize + DeserializeOwned")]
+    pub layout: Vec<Vec<LayoutSpec<F>>>,
+    /// Names and values of the variables, sorted by name
+    #[serde_as(as = "Vec<(_, o1_utils::serialization::SerdeAs)>")]
+    pub variables: Vec<(String, F)>,
+}
+
+impl<F: Field> WitnessFixture<F> {
+    /// Returns the variables of the fixture, to initialize the witness of its layout
+    pub fn variables(&self) -> Variables<F> {
+        let mut variables = Variables::create();
+        for (name, value) in self.variables.iter() {
+            variables.insert(name, *value);
+        }
+        variables
+    }
+}
+
+/// Writes the layout described by `layout` and the values of its `variables` to a fixture
+/// file, so that a test can reload it with [load_fixture] and reproduce the same witness.
+///
+/// # Errors
+///
+/// Will give error if it fails to create the file or to serialize the fixture.
+pub fn dump_fixture<F: Field>(
+    layout: &[Vec<LayoutSpec<F>>],
+    variables: &Variables<F>,
+    path: &Path,
+) -> Result<(), EncodingError> {
+    let mut variables: Vec<(String, F)> = variables
+        .iter()
+        .map(|(name, value)| (name.to_string(), *value))
+        .collect();
+    variables.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let fixture = WitnessFixture {
+        layout: layout.to_vec(),
+        variables,
+    };
+
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+    Ok(fixture.serialize(&mut rmp_serde::Serializer::new(writer))?)
+}
+
+/// Reads a fixture written by [dump_fixture]
+///
+/// # Errors
+///
+/// Will give error if it fails to open the file or to deserialize the fixture.
+pub fn load_fixture<F: Field>(path: &Path) -> Result<WitnessFixture<F>, EncodingError> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    Ok(WitnessFixture::deserialize(
+        &mut rmp_serde::Deserializer::new(reader),
+    )?)
+}
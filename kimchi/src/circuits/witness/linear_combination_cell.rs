@@ -0,0 +1,30 @@
+use super::{variables::Variables, WitnessCell, WitnessCtx};
+use ark_ff::Field;
+
+/// Witness cell holding a weighted sum of variables
+/// See [Variables] for more details
+pub struct LinearCombinationCell<'a, F: Field> {
+    terms: Vec<(F, &'a str)>,
+}
+
+impl<'a, F: Field> LinearCombinationCell<'a, F> {
+    /// Create witness cell holding the sum of `coeff * variables[name]` over the
+    /// given `(coeff, name)` terms. An empty combination is zero.
+    pub fn create(terms: &[(F, &'a str)]) -> Box<LinearCombinationCell<'a, F>> {
+        Box::new(LinearCombinationCell {
+            terms: terms.to_vec(),
+        })
+    }
+}
+
+impl<'a, F: Field, const W: usize> WitnessCell<F, F, W> for LinearCombinationCell<'a, F> {
+    fn value(&self, _witness: &mut [Vec<F>; W], variables: &Variables<F>, _ctx: WitnessCtx) -> F {
+        self.terms.iter().fold(F::zero(), |acc, (coeff, name)| {
+            acc + *coeff * variables[*name]
+        })
+    }
+
+    fn variable_names(&self) -> Vec<&str> {
+        self.terms.iter().map(|(_, name)| *name).collect()
+    }
+}
@@ -0,0 +1,57 @@
+use super::{variables::Variables, CellDep, WitnessCell, WitnessCtx};
+use crate::error::WitnessError;
+use ark_ff::Field;
+
+/// Witness cell assigned from another witness cell only if a predicate holds,
+/// otherwise the current value of the witness is left unchanged
+pub struct MaybeCell<'a, F: Field, T, const W: usize, P: Fn(&Variables<T>) -> bool> {
+    predicate: P,
+    inner: Box<dyn WitnessCell<F, T, W> + 'a>,
+}
+
+impl<'a, F: Field, T, const W: usize, P: Fn(&Variables<T>) -> bool> MaybeCell<'a, F, T, W, P> {
+    /// Create witness cell assigned from `inner` if `predicate` holds for the variables,
+    /// and keeping the value already present in the witness otherwise
+    pub fn create(
+        predicate: P,
+        inner: Box<dyn WitnessCell<F, T, W> + 'a>,
+    ) -> Box<MaybeCell<'a, F, T, W, P>> {
+        Box::new(MaybeCell { predicate, inner })
+    }
+}
+
+impl<'a, F: Field, T, const W: usize, P: Fn(&Variables<T>) -> bool> WitnessCell<F, T, W>
+    for MaybeCell<'a, F, T, W, P>
+{
+    fn value(&self, witness: &mut [Vec<F>; W], variables: &Variables<T>, ctx: WitnessCtx) -> F {
+        if (self.predicate)(variables) {
+            self.inner.value(witness, variables, ctx)
+        } else {
+            witness[ctx.col][ctx.offset + ctx.row]
+        }
+    }
+
+    fn length(&self) -> usize {
+        self.inner.length()
+    }
+
+    fn variable_names(&self) -> Vec<&str> {
+        self.inner.variable_names()
+    }
+
+    // The inner cell only reads its variables when the predicate holds
+    fn check_variables(&self, variables: &Variables<T>) -> Result<(), WitnessError> {
+        if (self.predicate)(variables) {
+            self.inner.check_variables(variables)
+        } else {
+            Ok(())
+        }
+    }
+
+    // Keeping the current value reads the cell itself
+    fn dependencies(&self) -> Vec<CellDep> {
+        let mut dependencies = self.inner.dependencies();
+        dependencies.push(CellDep::OwnColumn { row_delta: 0 });
+        dependencies
+    }
+}
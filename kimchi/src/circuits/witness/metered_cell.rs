@@ -0,0 +1,102 @@
+use super::{variables::Variables, CellDep, WitnessCell, WitnessCtx};
+use crate::error::WitnessError;
+use ark_ff::Field;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Number of values computed by the cells of a given type, and the total time spent on them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CellMetric {
+    pub count: u64,
+    pub time: Duration,
+}
+
+/// Collector of the [CellMetric] of each type of [MeteredCell], shared across threads
+#[derive(Debug, Default)]
+pub struct CellMetrics {
+    metrics: Mutex<BTreeMap<&'static str, CellMetric>>,
+}
+
+impl CellMetrics {
+    /// Create an empty collector, to be shared by the metered cells of a layout
+    pub fn create() -> Arc<CellMetrics> {
+        Arc::new(CellMetrics::default())
+    }
+
+    /// Returns the metrics collected so far, indexed by the name of the cell type
+    pub fn report(&self) -> BTreeMap<&'static str, CellMetric> {
+        self.metrics.lock().expect("poisoned cell metrics").clone()
+    }
+
+    fn record(&self, name: &'static str, time: Duration) {
+        let mut metrics = self.metrics.lock().expect("poisoned cell metrics");
+        let metric = metrics.entry(name).or_default();
+        metric.count += 1;
+        metric.time += time;
+    }
+}
+
+/// Witness cell assigned from another witness cell, recording in a [CellMetrics]
+/// the time spent computing each of its values
+pub struct MeteredCell<'a, F: Field, T, const W: usize> {
+    name: &'static str,
+    inner: Box<dyn WitnessCell<F, T, W> + 'a>,
+    metrics: Arc<CellMetrics>,
+}
+
+impl<'a, F: Field, T, const W: usize> MeteredCell<'a, F, T, W> {
+    /// Create witness cell assigned from `inner`, whose metrics are collected in `metrics`
+    /// under the name of its type (without module path nor generics, e.g. `CopyCell`)
+    pub fn create<C: WitnessCell<F, T, W> + 'a>(
+        inner: Box<C>,
+        metrics: &Arc<CellMetrics>,
+    ) -> Box<MeteredCell<'a, F, T, W>> {
+        let type_name = std::any::type_name::<C>();
+        let name = type_name
+            .split('<')
+            .next()
+            .and_then(|path| path.rsplit("::").next())
+            .unwrap_or(type_name);
+        Box::new(MeteredCell {
+            name,
+            inner,
+            metrics: metrics.clone(),
+        })
+    }
+}
+
+impl<'a, F: Field, T, const W: usize> WitnessCell<F, T, W> for MeteredCell<'a, F, T, W> {
+    fn value(&self, witness: &mut [Vec<F>; W], variables: &Variables<T>, ctx: WitnessCtx) -> F {
+        let start = Instant::now();
+        let value = self.inner.value(witness, variables, ctx);
+        self.metrics.record(self.name, start.elapsed());
+        value
+    }
+
+    fn length(&self) -> usize {
+        self.inner.length()
+    }
+
+    fn is_unknown(&self) -> bool {
+        self.inner.is_unknown()
+    }
+
+    fn variable_names(&self) -> Vec<&str> {
+        self.inner.variable_names()
+    }
+
+    fn check_variables(&self, variables: &Variables<T>) -> Result<(), WitnessError> {
+        self.inner.check_variables(variables)
+    }
+
+    fn dependencies(&self) -> Vec<CellDep> {
+        self.inner.dependencies()
+    }
+
+    fn is_output(&self) -> bool {
+        self.inner.is_output()
+    }
+}
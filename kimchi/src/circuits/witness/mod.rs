@@ -1,101 +1,611 @@
 use ark_ff::{Field, PrimeField};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::array;
 
+mod bounded_cell;
 mod constant_cell;
 mod copy_bits_cell;
 mod copy_cell;
+mod copy_rot_bits_cell;
 mod copy_shift_cell;
+mod copy_shift_right_cell;
+mod external_column_cell;
+mod foreign_reduce_cell;
+#[cfg(any(test, feature = "fuzzing"))]
+mod fuzz;
 mod index_cell;
+mod indirect_cell;
+mod inner_product_cell;
+mod layout_builder;
+mod layout_spec;
+mod linear_combination_cell;
+mod maybe_cell;
+#[cfg(feature = "metrics")]
+mod metered_cell;
+mod mux_cell;
+mod negate_cell;
+mod output_cell;
+mod partial;
+mod poseidon_cell;
+mod product_cell;
+mod range_check_cell;
+mod relative_copy_cell;
+mod rows;
+mod select_copy_cell;
+mod sqrt_cell;
+mod twos_complement_cell;
+mod unknown_cell;
 mod variable_bits_cell;
 mod variable_cell;
+mod variable_product_cell;
 mod variables;
 
 pub use self::{
-    constant_cell::ConstantCell,
+    bounded_cell::BoundedCell,
+    constant_cell::{bytes_row, ConstantCell},
     copy_bits_cell::CopyBitsCell,
     copy_cell::CopyCell,
+    copy_rot_bits_cell::CopyRotBitsCell,
     copy_shift_cell::CopyShiftCell,
-    index_cell::IndexCell,
+    copy_shift_right_cell::CopyShiftRightCell,
+    external_column_cell::ExternalColumnCell,
+    foreign_reduce_cell::ForeignReduceCell,
+    index_cell::{DynIndexCell, IndexCell},
+    indirect_cell::IndirectCell,
+    inner_product_cell::InnerProductCell,
+    layout_builder::LayoutBuilder,
+    layout_spec::{
+        dump_fixture, load_fixture, to_index_layout, to_layout, LayoutSpec, WitnessFixture,
+    },
+    linear_combination_cell::LinearCombinationCell,
+    maybe_cell::MaybeCell,
+    mux_cell::MuxCell,
+    negate_cell::NegateCell,
+    output_cell::OutputCell,
+    partial::{finalize, init_partial},
+    poseidon_cell::PoseidonCell,
+    product_cell::ProductCell,
+    range_check_cell::RangeCheckCell,
+    relative_copy_cell::RelativeCopyCell,
+    rows::WitnessRows,
+    select_copy_cell::SelectCopyCell,
+    sqrt_cell::SqrtCell,
+    twos_complement_cell::TwosComplementCell,
+    unknown_cell::UnknownCell,
     variable_bits_cell::VariableBitsCell,
     variable_cell::VariableCell,
+    variable_product_cell::VariableProductCell,
     variables::{variable_map, variables, Variables},
 };
 
+#[cfg(any(test, feature = "fuzzing"))]
+pub use self::fuzz::{fuzz_init, fuzz_layout};
+#[cfg(feature = "metrics")]
+pub use self::metered_cell::{CellMetric, CellMetrics, MeteredCell};
+
 use super::polynomial::COLUMNS;
+use crate::error::WitnessError;
+
+/// Position of the witness cell whose value is computed, see [WitnessCell::value]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WitnessCtx {
+    /// The row offset of the witness before initialization
+    pub offset: usize,
+    /// The row index inside the layout, so that the cell is at row `offset + row` of the witness
+    pub row: usize,
+    /// The column index inside the witness
+    pub col: usize,
+    /// The index within the variable (for IndexCell, 0 otherwise)
+    pub index: usize,
+}
 
 /// Witness cell interface. By default, the witness cell is a single element of type F.
 pub trait WitnessCell<F: Field, T = F, const W: usize = COLUMNS> {
-    fn value(&self, witness: &mut [Vec<F>; W], variables: &Variables<T>, index: usize) -> F;
+    /// Computes the value of the cell located at the position given by `ctx`
+    fn value(&self, witness: &mut [Vec<F>; W], variables: &Variables<T>, ctx: WitnessCtx) -> F;
 
     // Length is 1 by default (T is single F element) unless overridden
     fn length(&self) -> usize {
         1
     }
+
+    // Cells are known by default, unless they are meant to be filled later on
+    fn is_unknown(&self) -> bool {
+        false
+    }
+
+    // Names of the variables read by the cell, none by default
+    fn variable_names(&self) -> Vec<&str> {
+        vec![]
+    }
+
+    // Checks that the variables the cell reads for the given ones are present,
+    // which are all of its variable names by default
+    fn check_variables(&self, variables: &Variables<T>) -> Result<(), WitnessError> {
+        match self
+            .variable_names()
+            .into_iter()
+            .find(|name| !variables.contains(name))
+        {
+            Some(name) => Err(WitnessError::MissingVariable(name.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    // Cells are not collected as outputs of the layout by default
+    fn is_output(&self) -> bool {
+        false
+    }
+
+    // Variables and witness cells read by the cell, only its variables by default.
+    // Cells reading the witness must report it, as it decides which rows can be computed independently
+    fn dependencies(&self) -> Vec<CellDep> {
+        self.variable_names()
+            .into_iter()
+            .map(CellDep::Variable)
+            .collect()
+    }
+}
+
+/// Dependency of a witness cell, see [WitnessCell::dependencies]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CellDep<'a> {
+    /// The cell reads the variable with the given name
+    Variable(&'a str),
+    /// The cell copies from the witness cell at the given position
+    Cell { row: usize, col: usize },
+    /// The cell reads the witness cell of column `col`, `row_delta` rows away from its own row
+    Relative { row_delta: i32, col: usize },
+    /// The cell reads its own column, `row_delta` rows away from its own row, within the layout
+    OwnColumn { row_delta: i32 },
+    /// The cell reads a row of the given column only known once the witness is initialized
+    Column(usize),
+}
+
+impl<'a> CellDep<'a> {
+    /// Whether the dependency is a variable rather than a cell of the witness
+    pub fn is_variable(&self) -> bool {
+        matches!(self, CellDep::Variable(_))
+    }
+}
+
+/// Collects the dependencies of all the cells of a layout, along with the position
+/// (row, col) of the cell depending on them, in column-major order
+pub fn layout_dependencies<'a, F: Field, T, const W: usize>(
+    layout: &'a [Vec<Box<dyn WitnessCell<F, T, W> + '_>>],
+) -> Vec<((usize, usize), CellDep<'a>)> {
+    let mut dependencies = vec![];
+    for (row, cells) in layout.iter().enumerate() {
+        let mut col = 0;
+        for cell in cells {
+            let cell_dependencies = cell.dependencies();
+            for _ in 0..cell.length() {
+                for dependency in cell_dependencies.iter() {
+                    dependencies.push(((row, col), *dependency));
+                }
+                col += 1;
+            }
+        }
+    }
+    dependencies.sort_by_key(|((row, col), _)| (*col, *row));
+    dependencies
 }
 
 /// Initialize a witness cell based on layout and computed variables
 /// Inputs:
 /// - witness: the witness to initialize with values
-/// - offset: the row offset of the witness before initialization
-/// - row: the row index inside the partial layout
-/// - col: the column index inside the witness
+/// - ctx: the position of the cell in the witness, see [WitnessCtx]
 /// - cell: the cell index inside the partial layout (for any but IndexCell, it must be the same as col)
-/// - index: the index within the variable (for IndexCell, 0 otherwise)
 /// - layout: the partial layout to initialize from
 /// - variables: the hashmap of variables to get the values from
-#[allow(clippy::too_many_arguments)]
-pub fn init_cell<F: PrimeField, T, const W: usize>(
+///
+/// Returns an error if the cell is out of bounds of the layout or the witness,
+/// if the variable it reads is missing, or if the witness cells it reads are out of bounds
+pub fn try_init_cell<F: PrimeField, T, const W: usize>(
+    witness: &mut [Vec<F>; W],
+    ctx: WitnessCtx,
+    cell: usize,
+    layout: &[Vec<Box<dyn WitnessCell<F, T, W> + '_>>],
+    variables: &Variables<T>,
+) -> Result<(), WitnessError> {
+    let WitnessCtx {
+        offset, row, col, ..
+    } = ctx;
+    let witness_cell = layout
+        .get(row)
+        .and_then(|cells| cells.get(cell))
+        .ok_or(WitnessError::LayoutOutOfBounds { row, cell })?;
+    if col >= W {
+        return Err(WitnessError::ColumnOutOfBounds { col, width: W });
+    }
+    if row + offset >= witness[col].len() {
+        return Err(WitnessError::RowOutOfBounds {
+            row: row + offset,
+            col,
+            len: witness[col].len(),
+        });
+    }
+    witness_cell.check_variables(variables)?;
+    check_dependencies(
+        &column_lengths(witness),
+        offset,
+        row,
+        col,
+        witness_cell.as_ref(),
+    )?;
+    witness[col][row + offset] = witness_cell.value(witness, variables, ctx);
+    Ok(())
+}
+
+/// Initialize a witness row based on layout and computed variables
+/// Returns an error if the row does not fit in the witness, naming the first cell
+/// (possibly an [IndexCell] spanning several columns) that overruns it
+pub fn try_init_row<F: PrimeField, T, const W: usize>(
+    witness: &mut [Vec<F>; W],
+    offset: usize,
+    row: usize,
+    layout: &[Vec<Box<dyn WitnessCell<F, T, W> + '_>>],
+    variables: &Variables<T>,
+) -> Result<(), WitnessError> {
+    let cells = layout
+        .get(row)
+        .ok_or(WitnessError::LayoutOutOfBounds { row, cell: 0 })?;
+    // Check that every cell fits in the witness before writing anything
+    check_row(row, cells)?;
+    let mut col = 0;
+    for (cell, witness_cell) in cells.iter().enumerate() {
+        // The loop will only run more than once if the cell is an IndexCell
+        for index in 0..witness_cell.length() {
+            let ctx = WitnessCtx {
+                offset,
+                row,
+                col,
+                index,
+            };
+            try_init_cell(witness, ctx, cell, layout, variables)?;
+            col += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Initialize a witness based on layout and computed variables
+/// Returns an error instead of panicking on malformed layouts, checking the whole layout once
+/// before writing anything. Note that cells may still panic on values of the witness or of the
+/// variables they cannot handle (e.g. a non-boolean selector), which are not layout errors.
+pub fn try_init<F: PrimeField, T, const W: usize>(
     witness: &mut [Vec<F>; W],
     offset: usize,
+    layout: &[Vec<Box<dyn WitnessCell<F, T, W> + '_>>],
+    variables: &Variables<T>,
+) -> Result<(), WitnessError> {
+    // Check that the layout fits in the witness before writing anything
+    check_layout(&column_lengths(witness), offset, layout, variables)?;
+    for (row, cells) in layout.iter().enumerate() {
+        fill_row(witness, offset, row, cells, variables);
+    }
+    Ok(())
+}
+
+/// Checks that every cell of a row of the layout fits in the witness,
+/// naming the first cell (possibly an [IndexCell] spanning several columns) that overruns it
+fn check_row<F: Field, T, const W: usize, C: WitnessCell<F, T, W> + ?Sized>(
+    row: usize,
+    cells: &[Box<C>],
+) -> Result<(), WitnessError> {
+    let mut start = 0;
+    for (cell, witness_cell) in cells.iter().enumerate() {
+        let end = start + witness_cell.length();
+        if end > W {
+            return Err(WitnessError::CellOutOfBounds {
+                row,
+                cell,
+                start,
+                end,
+                width: W,
+            });
+        }
+        start = end;
+    }
+    Ok(())
+}
+
+/// Lengths of the columns of the witness
+fn column_lengths<F, const W: usize>(witness: &[Vec<F>; W]) -> [usize; W] {
+    array::from_fn(|col| witness[col].len())
+}
+
+/// Checks that the witness cells read by the cell at row `offset + row` and column `col`
+/// are in bounds of a witness with columns of the given `lengths`, see [WitnessCell::dependencies]
+fn check_dependencies<F: Field, T, const W: usize, C: WitnessCell<F, T, W> + ?Sized>(
+    lengths: &[usize; W],
+    offset: usize,
     row: usize,
     col: usize,
-    cell: usize,
-    index: usize,
-    layout: &[Vec<Box<dyn WitnessCell<F, T, W>>>],
+    cell: &C,
+) -> Result<(), WitnessError> {
+    for dependency in cell.dependencies() {
+        let (dep_row, dep_col) = match dependency {
+            // Variables are checked separately and the own column is read within the layout
+            CellDep::Variable(_) | CellDep::OwnColumn { .. } => continue,
+            CellDep::Cell { row, col } => (Some(row as i64), col),
+            CellDep::Relative { row_delta, col } => {
+                (Some((offset + row) as i64 + row_delta as i64), col)
+            }
+            CellDep::Column(col) => (None, col),
+        };
+        if dep_col >= W {
+            return Err(WitnessError::ColumnOutOfBounds {
+                col: dep_col,
+                width: W,
+            });
+        }
+        if let Some(dep_row) = dep_row {
+            if dep_row < 0 || dep_row as usize >= lengths[dep_col] {
+                return Err(WitnessError::DependencyOutOfBounds {
+                    row: offset + row,
+                    col,
+                    dep_row,
+                    dep_col,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the whole layout fits at the given offset in a witness with columns of the given
+/// `lengths`, that the variables read by its cells are present and that the witness cells they
+/// read are in bounds
+fn check_layout<F: Field, T, const W: usize, C: WitnessCell<F, T, W> + ?Sized>(
+    lengths: &[usize; W],
+    offset: usize,
+    layout: &[Vec<Box<C>>],
     variables: &Variables<T>,
-) {
-    witness[col][row + offset] = layout[row][cell].value(witness, variables, index);
+) -> Result<(), WitnessError> {
+    let need = layout.len() + offset;
+    if let Some(&have) = lengths.iter().find(|&&have| have < need) {
+        return Err(WitnessError::InsufficientRows { have, need });
+    }
+    for (row, cells) in layout.iter().enumerate() {
+        check_row(row, cells)?;
+        let mut col = 0;
+        for cell in cells.iter() {
+            cell.check_variables(variables)?;
+            check_dependencies(lengths, offset, row, col, cell.as_ref())?;
+            col += cell.length();
+        }
+    }
+    Ok(())
 }
 
-/// Initialize a witness row based on layout and computed variables
-pub fn init_row<F: PrimeField, T, const W: usize>(
+/// Writes the values of the cells of a row of the layout into the witness,
+/// once the layout was checked with [check_layout]
+fn fill_row<F: Field, T, const W: usize, C: WitnessCell<F, T, W> + ?Sized>(
     witness: &mut [Vec<F>; W],
     offset: usize,
     row: usize,
-    layout: &[Vec<Box<dyn WitnessCell<F, T, W>>>],
+    cells: &[Box<C>],
     variables: &Variables<T>,
 ) {
     let mut col = 0;
-    for cell in 0..layout[row].len() {
+    for cell in cells.iter() {
         // The loop will only run more than once if the cell is an IndexCell
-        for index in 0..layout[row][cell].length() {
-            init_cell(witness, offset, row, col, cell, index, layout, variables);
+        for index in 0..cell.length() {
+            witness[col][row + offset] = cell.value(
+                witness,
+                variables,
+                WitnessCtx {
+                    offset,
+                    row,
+                    col,
+                    index,
+                },
+            );
             col += 1;
         }
     }
 }
 
+/// Initialize a witness cell based on layout and computed variables
+/// See [try_init_cell] for more details
+pub fn init_cell<F: PrimeField, T, const W: usize>(
+    witness: &mut [Vec<F>; W],
+    ctx: WitnessCtx,
+    cell: usize,
+    layout: &[Vec<Box<dyn WitnessCell<F, T, W> + '_>>],
+    variables: &Variables<T>,
+) {
+    try_init_cell(witness, ctx, cell, layout, variables).expect("failed to initialize witness cell")
+}
+
+/// Initialize a witness row based on layout and computed variables
+pub fn init_row<F: PrimeField, T, const W: usize>(
+    witness: &mut [Vec<F>; W],
+    offset: usize,
+    row: usize,
+    layout: &[Vec<Box<dyn WitnessCell<F, T, W> + '_>>],
+    variables: &Variables<T>,
+) {
+    try_init_row(witness, offset, row, layout, variables).expect("failed to initialize witness row")
+}
+
 /// Initialize a witness based on layout and computed variables
 pub fn init<F: PrimeField, T, const W: usize>(
     witness: &mut [Vec<F>; W],
     offset: usize,
-    layout: &[Vec<Box<dyn WitnessCell<F, T, W>>>],
+    layout: &[Vec<Box<dyn WitnessCell<F, T, W> + '_>>],
+    variables: &Variables<T>,
+) {
+    try_init(witness, offset, layout, variables).expect("failed to initialize witness")
+}
+
+/// Initialize a witness based on layout and computed variables, as [init] does,
+/// and returns the values of the [OutputCell]s of the layout in row order
+pub fn init_with_outputs<F: PrimeField, T, const W: usize>(
+    witness: &mut [Vec<F>; W],
+    offset: usize,
+    layout: &[Vec<Box<dyn WitnessCell<F, T, W> + '_>>],
+    variables: &Variables<T>,
+) -> Vec<F> {
+    init(witness, offset, layout, variables);
+
+    let mut outputs = vec![];
+    for (row, cells) in layout.iter().enumerate() {
+        let mut col = 0;
+        for cell in cells.iter() {
+            for _ in 0..cell.length() {
+                if cell.is_output() {
+                    outputs.push(witness[col][offset + row]);
+                }
+                col += 1;
+            }
+        }
+    }
+    outputs
+}
+
+/// Checks that a witness was produced by the given layout and variables, recomputing the value of
+/// every cell of the layout and comparing it to the one stored in the witness.
+/// Returns the position (row, col) of the witness of the first mismatch, if any.
+pub fn validate_witness<F: PrimeField, T, const W: usize>(
+    witness: &[Vec<F>; W],
+    offset: usize,
+    layout: &[Vec<Box<dyn WitnessCell<F, T, W> + '_>>],
+    variables: &Variables<T>,
+) -> Result<(), (usize, usize)> {
+    // Cells get mutable access to the witness, so work on a copy of it
+    let mut scratch = witness.clone();
+    for (row, cells) in layout.iter().enumerate() {
+        let mut col = 0;
+        for cell in cells.iter() {
+            // The loop will only run more than once if the cell is an IndexCell
+            for index in 0..cell.length() {
+                let value = cell.value(
+                    &mut scratch,
+                    variables,
+                    WitnessCtx {
+                        offset,
+                        row,
+                        col,
+                        index,
+                    },
+                );
+                if value != witness[col][row + offset] {
+                    return Err((row + offset, col));
+                }
+                col += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Initialize a witness based on layout and computed variables, growing the columns of the witness
+/// with zeros when they are too short to hold the layout at the given offset.
+/// Note that [init] remains strict and fails if the columns are not large enough.
+pub fn init_resize<F: PrimeField, T, const W: usize>(
+    witness: &mut [Vec<F>; W],
+    offset: usize,
+    layout: &[Vec<Box<dyn WitnessCell<F, T, W> + '_>>],
     variables: &Variables<T>,
 ) {
-    for row in 0..layout.len() {
-        init_row(witness, offset, row, layout, variables);
+    let rows = offset + layout.len();
+    for column in witness.iter_mut() {
+        if column.len() < rows {
+            column.resize(rows, F::zero());
+        }
     }
+    init(witness, offset, layout, variables);
+}
+
+/// Initialize a witness based on layout and computed variables, distributing the rows across threads.
+/// The rows whose cells only depend on variables (see [WitnessCell::dependencies]) are computed in
+/// parallel first, and then the rows depending on other cells (e.g. [CopyCell] or [CopyBitsCell])
+/// are computed sequentially, in order.
+/// Note that the result equals the one of [try_init] as long as no row reads a later row of the layout,
+/// and that the layout is checked the same way before anything is written.
+#[cfg(feature = "parallel")]
+pub fn try_par_init<F: PrimeField, T: Sync, const W: usize>(
+    witness: &mut [Vec<F>; W],
+    offset: usize,
+    layout: &[Vec<Box<dyn WitnessCell<F, T, W> + Sync + '_>>],
+    variables: &Variables<T>,
+) -> Result<(), WitnessError> {
+    check_layout(&column_lengths(witness), offset, layout, variables)?;
+
+    let independent: Vec<bool> = layout
+        .iter()
+        .map(|cells| {
+            cells
+                .iter()
+                .all(|cell| cell.dependencies().iter().all(CellDep::is_variable))
+        })
+        .collect();
+
+    let rows: Vec<(usize, Vec<F>)> = (0..layout.len())
+        .into_par_iter()
+        .filter(|&row| independent[row])
+        .map(|row| {
+            // The cells of the row do not read the witness, so an empty one is enough
+            let mut empty: [Vec<F>; W] = array::from_fn(|_| vec![]);
+            let mut values = vec![];
+            for cell in layout[row].iter() {
+                // The loop will only run more than once if the cell is an IndexCell
+                for index in 0..cell.length() {
+                    let col = values.len();
+                    values.push(cell.value(
+                        &mut empty,
+                        variables,
+                        WitnessCtx {
+                            offset,
+                            row,
+                            col,
+                            index,
+                        },
+                    ));
+                }
+            }
+            (row, values)
+        })
+        .collect();
+    for (row, values) in rows {
+        for (col, value) in values.into_iter().enumerate() {
+            witness[col][row + offset] = value;
+        }
+    }
+
+    for row in (0..layout.len()).filter(|&row| !independent[row]) {
+        fill_row(witness, offset, row, &layout[row], variables);
+    }
+    Ok(())
+}
+
+/// Initialize a witness based on layout and computed variables, distributing the rows across threads
+/// See [try_par_init] for more details
+#[cfg(feature = "parallel")]
+pub fn par_init<F: PrimeField, T: Sync, const W: usize>(
+    witness: &mut [Vec<F>; W],
+    offset: usize,
+    layout: &[Vec<Box<dyn WitnessCell<F, T, W> + Sync + '_>>],
+    variables: &Variables<T>,
+) {
+    try_par_init(witness, offset, layout, variables).expect("failed to initialize witness")
 }
 
 #[cfg(test)]
 mod tests {
-    use std::array;
+    use std::sync::Arc;
 
     use super::*;
 
-    use crate::circuits::polynomial::COLUMNS;
+    use crate::{circuits::polynomial::COLUMNS, error::WitnessError};
     use ark_ec::AffineCurve;
     use ark_ff::{Field, One, Zero};
     use mina_curves::pasta::Pallas;
+    use num_bigint::BigUint;
+    use o1_utils::FieldHelpers;
     type PallasField = <Pallas as AffineCurve>::BaseField;
 
     #[test]
@@ -128,7 +638,18 @@ mod tests {
         }
 
         // Set a single cell to zero
-        init_cell(&mut witness, 0, 0, 4, 4, 0, &layout, &variables!());
+        init_cell(
+            &mut witness,
+            WitnessCtx {
+                offset: 0,
+                row: 0,
+                col: 4,
+                index: 0,
+            },
+            4,
+            &layout,
+            &variables!(),
+        );
         assert_eq!(witness[4][0], PallasField::zero());
 
         // Set all the cells to zero
@@ -141,9 +662,8 @@ mod tests {
         }
     }
 
-    #[test]
-    fn mixed_layout() {
-        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField>>>> = vec![
+    fn mixed_layout_cells() -> Vec<Vec<Box<dyn WitnessCell<PallasField>>>> {
+        vec![
             vec![
                 ConstantCell::create(PallasField::from(12u32)),
                 ConstantCell::create(PallasField::from(0xa5a3u32)),
@@ -178,7 +698,12 @@ mod tests {
                 ConstantCell::create(PallasField::zero()),
                 VariableCell::create("final_value"),
             ],
-        ];
+        ]
+    }
+
+    #[test]
+    fn mixed_layout() {
+        let layout = mixed_layout_cells();
 
         let mut witness: [Vec<PallasField>; COLUMNS] =
             array::from_fn(|_| vec![PallasField::zero(); 2]);
@@ -231,4 +756,1535 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn product_layout() {
+        let factors = [2u32, 3, 5, 7];
+        let seed = PallasField::from(11u32);
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = factors
+            .iter()
+            .map(|factor| {
+                let row: Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>> = vec![
+                    ConstantCell::create(PallasField::from(*factor)),
+                    ProductCell::create(0, seed),
+                ];
+                row
+            })
+            .collect();
+
+        let mut witness: [Vec<PallasField>; 2] =
+            array::from_fn(|_| vec![PallasField::zero(); factors.len()]);
+        init(&mut witness, 0, &layout, &variables!());
+
+        let mut product = seed;
+        for (row, factor) in factors.iter().enumerate() {
+            product *= PallasField::from(*factor);
+            assert_eq!(witness[1][row], product);
+        }
+    }
+
+    #[test]
+    fn external_column_layout() {
+        let values = Arc::new(vec![
+            PallasField::from(10u32),
+            PallasField::from(20u32),
+            PallasField::from(30u32),
+        ]);
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 1>>>> = (0..values.len())
+            .map(|_| {
+                let row: Vec<Box<dyn WitnessCell<PallasField, PallasField, 1>>> =
+                    vec![ExternalColumnCell::create(values.clone())];
+                row
+            })
+            .collect();
+
+        let mut witness: [Vec<PallasField>; 1] =
+            array::from_fn(|_| vec![PallasField::zero(); values.len()]);
+        init(&mut witness, 0, &layout, &variables!());
+
+        assert_eq!(witness[0], *values);
+    }
+
+    #[test]
+    fn maybe_layout() {
+        let prefilled = PallasField::from(42u32);
+        let inner = PallasField::from(7u32);
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = vec![vec![
+            MaybeCell::create(
+                |variables: &Variables<PallasField>| variables["write"] == PallasField::one(),
+                ConstantCell::create(inner),
+            ),
+            MaybeCell::create(
+                |variables: &Variables<PallasField>| variables["write"] != PallasField::one(),
+                ConstantCell::create(inner),
+            ),
+        ]];
+
+        let mut witness: [Vec<PallasField>; 2] = array::from_fn(|_| vec![prefilled; 1]);
+        let write = PallasField::one();
+        init(&mut witness, 0, &layout, &variables!(write));
+
+        assert_eq!(witness[0][0], inner);
+        assert_eq!(witness[1][0], prefilled);
+    }
+
+    #[test]
+    fn maybe_layout_variables() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 1>>>> =
+            vec![vec![MaybeCell::create(
+                |_| false,
+                VariableCell::create("x"),
+            )]];
+        let mut witness: [Vec<PallasField>; 1] = array::from_fn(|_| vec![PallasField::one(); 1]);
+        // The variable is not read when the predicate does not hold
+        assert_eq!(try_init(&mut witness, 0, &layout, &variables!()), Ok(()));
+        assert_eq!(witness[0][0], PallasField::one());
+
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 1>>>> =
+            vec![vec![MaybeCell::create(|_| true, VariableCell::create("x"))]];
+        assert_eq!(
+            try_init(&mut witness, 0, &layout, &variables!()),
+            Err(WitnessError::MissingVariable("x".to_string()))
+        );
+    }
+
+    #[test]
+    fn inner_product_layout() {
+        let a = PallasField::from(2u32);
+        let b = PallasField::from(9u32);
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 3>>>> = vec![vec![
+            ConstantCell::create(a),
+            ConstantCell::create(b),
+            InnerProductCell::create(&[
+                ((0, 0), PallasField::from(3u32)),
+                ((0, 1), PallasField::from(5u32)),
+            ]),
+        ]];
+
+        let mut witness: [Vec<PallasField>; 3] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        init(&mut witness, 0, &layout, &variables!());
+
+        assert_eq!(
+            witness[2][0],
+            PallasField::from(3u32) * a + PallasField::from(5u32) * b
+        );
+        assert_eq!(witness[2][0], PallasField::from(51u32));
+    }
+
+    #[test]
+    fn layout_builder_stacks_layouts() {
+        let layout = mixed_layout_cells();
+        let values = |sum_of_products: PallasField| {
+            let something_else = sum_of_products * PallasField::from(5u32);
+            let final_value = (something_else + PallasField::one()).pow([2u64]);
+            (sum_of_products, something_else, final_value)
+        };
+        let first = values(PallasField::from(1337u32));
+        let second = values(PallasField::from(42u32));
+
+        // The witness starts empty and grows with each layout
+        let mut witness: [Vec<PallasField>; COLUMNS] = array::from_fn(|_| vec![]);
+        let mut builder = LayoutBuilder::new(&mut witness, 0);
+        {
+            let (sum_of_products, something_else, final_value) = first;
+            builder.add(
+                &layout,
+                &variables!(sum_of_products, something_else, final_value),
+            );
+        }
+        assert_eq!(builder.offset(), 2);
+        {
+            let (sum_of_products, something_else, final_value) = second;
+            builder.add(
+                &layout,
+                &variables!(sum_of_products, something_else, final_value),
+            );
+        }
+        assert_eq!(builder.finish(), 4);
+
+        // Copy cells refer to the absolute row 0, whose values are the same in both regions
+        assert_eq!(witness[0].len(), 4);
+        for (offset, (sum_of_products, something_else, final_value)) in [(0, first), (2, second)] {
+            assert_eq!(witness[3][offset], PallasField::from(12u32));
+            assert_eq!(witness[4][offset], PallasField::from(0x3u32));
+            assert_eq!(witness[5][offset], PallasField::from(0x800000u32));
+            assert_eq!(witness[6][offset], sum_of_products);
+            assert_eq!(witness[0][offset + 1], PallasField::from(12u32));
+            assert_eq!(witness[1][offset + 1], PallasField::from(0xau32));
+            assert_eq!(witness[2][offset + 1], PallasField::from(0x80000u32));
+            assert_eq!(witness[3][offset + 1], sum_of_products);
+            assert_eq!(witness[7][offset + 1], something_else);
+            assert_eq!(witness[14][offset + 1], final_value);
+        }
+    }
+
+    #[test]
+    fn bounded_layout() {
+        let bound = PallasField::from(256u32);
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 1>>>> =
+            vec![vec![BoundedCell::create("value", bound)]];
+
+        let mut witness: [Vec<PallasField>; 1] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        let value = PallasField::from(255u32);
+        init(&mut witness, 0, &layout, &variables!(value));
+
+        assert_eq!(witness[0][0], value);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds of `BoundedCell`")]
+    fn bounded_layout_out_of_bounds() {
+        let bound = PallasField::from(256u32);
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 1>>>> =
+            vec![vec![BoundedCell::create("value", bound)]];
+
+        let mut witness: [Vec<PallasField>; 1] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        let value = bound;
+        init(&mut witness, 0, &layout, &variables!(value));
+    }
+
+    #[test]
+    fn copy_rot_bits_layout() {
+        let value: u64 = 0xa5c3;
+        let (start, end) = (4, 12);
+        let width = end - start;
+        let window = (value >> start) & ((1 << width) - 1);
+
+        for rot in 0..=width {
+            let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = vec![vec![
+                VariableCell::create("value"),
+                CopyRotBitsCell::create(0, 0, start, end, rot),
+            ]];
+
+            let mut witness: [Vec<PallasField>; 2] =
+                array::from_fn(|_| vec![PallasField::zero(); 1]);
+            let value = PallasField::from(value);
+            init(&mut witness, 0, &layout, &variables!(value));
+
+            let rot = rot % width;
+            let rotated = ((window << rot) | (window >> (width - rot))) & ((1 << width) - 1);
+            assert_eq!(witness[1][0], PallasField::from(rotated));
+        }
+    }
+
+    #[test]
+    fn copy_shift_right_layout() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = vec![vec![
+            VariableCell::create("value"),
+            CopyShiftRightCell::create(0, 0, 4),
+        ]];
+
+        let mut witness: [Vec<PallasField>; 2] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        let value = PallasField::from(0x1230u32);
+        init(&mut witness, 0, &layout, &variables!(value));
+
+        assert_eq!(witness[1][0], PallasField::from(0x123u32));
+    }
+
+    #[test]
+    #[should_panic(expected = "cell (0, 0) is not a multiple of 2^4 in `CopyShiftRightCell`")]
+    fn copy_shift_right_layout_not_divisible() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = vec![vec![
+            VariableCell::create("value"),
+            CopyShiftRightCell::create(0, 0, 4),
+        ]];
+
+        let mut witness: [Vec<PallasField>; 2] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        let value = PallasField::from(0x1238u32);
+        init(&mut witness, 0, &layout, &variables!(value));
+    }
+
+    #[test]
+    fn fuzz_init_seeds() {
+        let seeds: [&[u8]; 5] = [
+            &[],
+            &[0xff; 64],
+            &[
+                3, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 4, 255, 255, 191, 64,
+            ],
+            b"witness cell framework regression seed",
+            &[0x04; 512],
+        ];
+        for seed in seeds {
+            // Errors are fine, panics are not
+            let _ = fuzz_init::<PallasField>(seed);
+        }
+    }
+
+    #[test]
+    fn fuzz_mixed_layout() {
+        use ark_ff::UniformRand;
+        use rand::Rng;
+
+        let layout = mixed_layout_cells();
+        let gen = |rng: &mut rand::rngs::StdRng| {
+            let sum_of_products = match rng.gen_range(0..3) {
+                0 => PallasField::zero(),
+                1 => -PallasField::one(),
+                _ => PallasField::rand(rng),
+            };
+            let something_else = sum_of_products * PallasField::from(5u32);
+            let final_value = (something_else + PallasField::one()).pow([2u64]);
+            variables!(sum_of_products, something_else, final_value)
+        };
+        fuzz_layout(&layout, gen, 32, [7u8; 32]);
+    }
+
+    #[test]
+    #[should_panic(expected = "inconsistent witness cell (0, 0) at iteration 0")]
+    fn fuzz_layout_catches_inconsistent_cells() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        // A cell whose value changes every time it is computed
+        struct CounterCell(AtomicU64);
+        impl WitnessCell<PallasField, PallasField, 1> for CounterCell {
+            fn value(
+                &self,
+                _witness: &mut [Vec<PallasField>; 1],
+                _variables: &Variables<PallasField>,
+                _ctx: WitnessCtx,
+            ) -> PallasField {
+                PallasField::from(self.0.fetch_add(1, Ordering::Relaxed))
+            }
+        }
+
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 1>>>> =
+            vec![vec![Box::new(CounterCell(AtomicU64::new(0)))]];
+        fuzz_layout(
+            &layout,
+            |_: &mut rand::rngs::StdRng| variables!(),
+            1,
+            [0u8; 32],
+        );
+    }
+
+    #[test]
+    fn partial_layout() {
+        let value = PallasField::from(5u32);
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> =
+            vec![vec![ConstantCell::create(value), UnknownCell::create()]];
+
+        let mut witness: [Vec<Option<PallasField>>; 2] = array::from_fn(|_| vec![None; 1]);
+        init_partial(&mut witness, 0, &layout, &variables!());
+
+        assert_eq!(witness[0][0], Some(value));
+        assert_eq!(witness[1][0], None);
+        assert_eq!(
+            finalize(witness.clone(), None),
+            Err(WitnessError::UnknownCell { row: 0, col: 1 })
+        );
+        let witness = finalize(witness, Some(PallasField::one())).unwrap();
+        assert_eq!(witness, [vec![value], vec![PallasField::one()]]);
+    }
+
+    #[test]
+    fn range_check_layout() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 1>>>> =
+            vec![vec![RangeCheckCell::create("value", 8)]];
+
+        let mut witness: [Vec<PallasField>; 1] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        let value = PallasField::from(0xffu32);
+        init(&mut witness, 0, &layout, &variables!(value));
+
+        assert_eq!(witness[0][0], value);
+    }
+
+    #[test]
+    #[should_panic(expected = "variable `value` does not fit in 8 bits")]
+    fn range_check_layout_overflow() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 1>>>> =
+            vec![vec![RangeCheckCell::create("value", 8)]];
+
+        let mut witness: [Vec<PallasField>; 1] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        let value = PallasField::from(0x100u32);
+        init(&mut witness, 0, &layout, &variables!(value));
+    }
+
+    #[test]
+    fn bytes_row_layout() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 3>>>> =
+            vec![bytes_row(&[1, 2, 3])];
+
+        let mut witness: [Vec<PallasField>; 3] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        init(&mut witness, 0, &layout, &variables!());
+
+        for (col, byte) in [1u8, 2, 3].iter().enumerate() {
+            assert_eq!(witness[col][0], PallasField::from(*byte));
+        }
+    }
+
+    #[test]
+    fn try_init_errors() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = vec![vec![
+            ConstantCell::create(PallasField::one()),
+            VariableCell::create("value"),
+        ]];
+
+        let mut witness: [Vec<PallasField>; 2] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        assert_eq!(
+            try_init(&mut witness, 0, &layout, &variables!()),
+            Err(WitnessError::MissingVariable("value".to_string()))
+        );
+        let value = PallasField::from(3u32);
+        assert_eq!(
+            try_init_row(&mut witness, 1, 0, &layout, &variables!(value)),
+            Err(WitnessError::RowOutOfBounds {
+                row: 1,
+                col: 0,
+                len: 1
+            })
+        );
+        assert_eq!(
+            try_init_cell(
+                &mut witness,
+                WitnessCtx {
+                    offset: 0,
+                    row: 0,
+                    col: 0,
+                    index: 0
+                },
+                2,
+                &layout,
+                &variables!(value)
+            ),
+            Err(WitnessError::LayoutOutOfBounds { row: 0, cell: 2 })
+        );
+        assert_eq!(
+            try_init(&mut witness, 0, &layout, &variables!(value)),
+            Ok(())
+        );
+        assert_eq!(witness[1][0], value);
+
+        let wide: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = vec![vec![
+            ConstantCell::create(PallasField::one()),
+            ConstantCell::create(PallasField::one()),
+            ConstantCell::create(PallasField::one()),
+        ]];
+        assert_eq!(
+            try_init(&mut witness, 0, &wide, &variables!()),
+            Err(WitnessError::CellOutOfBounds {
+                row: 0,
+                cell: 2,
+                start: 2,
+                end: 3,
+                width: 2
+            })
+        );
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn wide_index_layout() -> Vec<Vec<Box<dyn WitnessCell<PallasField, Vec<PallasField>, 3>>>> {
+        vec![vec![
+            ConstantCell::create(PallasField::one()),
+            IndexCell::create("values", 0, 4),
+        ]]
+    }
+
+    #[test]
+    fn index_cell_out_of_bounds() {
+        let values: Vec<PallasField> = (1..=4u64).map(PallasField::from).collect();
+        let mut witness: [Vec<PallasField>; 3] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        let error = try_init(&mut witness, 0, &wide_index_layout(), &variables!(values));
+        assert_eq!(
+            error,
+            Err(WitnessError::CellOutOfBounds {
+                row: 0,
+                cell: 1,
+                start: 1,
+                end: 5,
+                width: 3
+            })
+        );
+
+        // Nothing was written
+        assert!(witness.iter().all(|column| column[0].is_zero()));
+    }
+
+    #[test]
+    #[allow(clippy::type_complexity)]
+    fn index_cell_variable_too_short() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, Vec<PallasField>, 3>>>> =
+            vec![vec![IndexCell::create("values", 0, 3)]];
+        let values: Vec<PallasField> = (1..=2u64).map(PallasField::from).collect();
+        let mut witness: [Vec<PallasField>; 3] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        assert_eq!(
+            try_init(&mut witness, 0, &layout, &variables!(values)),
+            Err(WitnessError::VariableTooShort {
+                name: "values".to_string(),
+                len: 2,
+                need: 3
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "CellOutOfBounds { row: 0, cell: 1, start: 1, end: 5, width: 3 }")]
+    fn index_cell_out_of_bounds_panics() {
+        let values: Vec<PallasField> = (1..=4u64).map(PallasField::from).collect();
+        let mut witness: [Vec<PallasField>; 3] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        init_row(
+            &mut witness,
+            0,
+            0,
+            &wide_index_layout(),
+            &variables!(values),
+        );
+    }
+
+    #[test]
+    fn mux_layout() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 1>>>> =
+            vec![vec![MuxCell::create("selector", "a", "b")]];
+
+        let a = PallasField::from(10u32);
+        let b = PallasField::from(20u32);
+        let mut witness: [Vec<PallasField>; 1] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+
+        let selector = PallasField::one();
+        init(&mut witness, 0, &layout, &variables!(selector, a, b));
+        assert_eq!(witness[0][0], a);
+
+        let selector = PallasField::zero();
+        init(&mut witness, 0, &layout, &variables!(selector, a, b));
+        assert_eq!(witness[0][0], b);
+    }
+
+    #[test]
+    #[should_panic(expected = "selector `selector` of `MuxCell` is not boolean")]
+    fn mux_layout_non_boolean_selector() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 1>>>> =
+            vec![vec![MuxCell::create("selector", "a", "b")]];
+
+        let a = PallasField::from(10u32);
+        let b = PallasField::from(20u32);
+        let selector = PallasField::from(2u32);
+        let mut witness: [Vec<PallasField>; 1] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        init(&mut witness, 0, &layout, &variables!(selector, a, b));
+    }
+
+    #[test]
+    fn try_init_insufficient_rows() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 1>>>> = vec![
+            vec![ConstantCell::create(PallasField::one())],
+            vec![ConstantCell::create(PallasField::one())],
+        ];
+
+        let mut witness: [Vec<PallasField>; 1] = array::from_fn(|_| vec![PallasField::zero(); 6]);
+        assert_eq!(
+            try_init(&mut witness, 5, &layout, &variables!()),
+            Err(WitnessError::InsufficientRows { have: 6, need: 7 })
+        );
+        // Nothing was written
+        assert_eq!(witness[0][5], PallasField::zero());
+    }
+
+    #[test]
+    fn linear_combination_layout() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = vec![vec![
+            LinearCombinationCell::create(&[
+                (PallasField::from(2u32), "a"),
+                (PallasField::from(3u32), "b"),
+                (-PallasField::one(), "c"),
+            ]),
+            LinearCombinationCell::create(&[]),
+        ]];
+
+        let a = PallasField::from(5u32);
+        let b = PallasField::from(7u32);
+        let c = PallasField::from(4u32);
+        let mut witness: [Vec<PallasField>; 2] = array::from_fn(|_| vec![PallasField::one(); 1]);
+        init(&mut witness, 0, &layout, &variables!(a, b, c));
+
+        // 2 * 5 + 3 * 7 - 4
+        assert_eq!(witness[0][0], PallasField::from(27u32));
+        assert_eq!(witness[1][0], PallasField::zero());
+    }
+
+    #[test]
+    fn foreign_reduce_layout() {
+        // Largest 64-bit prime
+        let modulus = BigUint::from(u64::MAX - 58);
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 1>>>> =
+            vec![vec![ForeignReduceCell::create("value", modulus.clone())]];
+
+        let big =
+            BigUint::from(3u32) * &modulus + BigUint::from(1234u32) + (BigUint::from(1u32) << 130);
+        let value = PallasField::from_biguint(&big).unwrap();
+        let mut witness: [Vec<PallasField>; 1] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        init(&mut witness, 0, &layout, &variables!(value));
+
+        assert_eq!(
+            witness[0][0],
+            PallasField::from_biguint(&(big % modulus)).unwrap()
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn par_init_layout() {
+        type SyncCell = dyn WitnessCell<PallasField, PallasField, 3> + Sync;
+        let rows = 4096;
+        let layout: Vec<Vec<Box<SyncCell>>> = (0..rows)
+            .map(|row| {
+                let cells: Vec<Box<SyncCell>> = if row % 3 == 0 {
+                    vec![
+                        ConstantCell::create(PallasField::from(row as u64)),
+                        VariableCell::create("x"),
+                        LinearCombinationCell::create(&[(PallasField::from(row as u64), "x")]),
+                    ]
+                } else {
+                    vec![
+                        CopyShiftCell::create(row - 1, 0, 1),
+                        CopyCell::create(row - 1, 2),
+                        ProductCell::create(0, PallasField::one()),
+                    ]
+                };
+                cells
+            })
+            .collect();
+
+        let x = PallasField::from(7u32);
+        let mut par_witness: [Vec<PallasField>; 3] =
+            array::from_fn(|_| vec![PallasField::zero(); rows]);
+        par_init(&mut par_witness, 0, &layout, &variables!(x));
+
+        // The same layout, without requiring its cells to be shareable across threads
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 3>>>> = layout
+            .into_iter()
+            .map(|cells| {
+                cells
+                    .into_iter()
+                    .map(|cell| cell as Box<dyn WitnessCell<PallasField, PallasField, 3>>)
+                    .collect()
+            })
+            .collect();
+        let mut witness: [Vec<PallasField>; 3] =
+            array::from_fn(|_| vec![PallasField::zero(); rows]);
+        init(&mut witness, 0, &layout, &variables!(x));
+
+        assert_eq!(witness, par_witness);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn try_par_init_errors() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2> + Sync>>> = vec![
+            vec![
+                ConstantCell::create(PallasField::one()),
+                VariableCell::create("value"),
+            ],
+            vec![
+                CopyCell::create(0, 1),
+                ConstantCell::create(PallasField::one()),
+            ],
+        ];
+
+        let value = PallasField::from(3u32);
+        let mut witness: [Vec<PallasField>; 2] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        assert_eq!(
+            try_par_init(&mut witness, 0, &layout, &variables!(value)),
+            Err(WitnessError::InsufficientRows { have: 1, need: 2 })
+        );
+
+        let mut witness: [Vec<PallasField>; 2] = array::from_fn(|_| vec![PallasField::zero(); 2]);
+        assert_eq!(
+            try_par_init(&mut witness, 0, &layout, &variables!()),
+            Err(WitnessError::MissingVariable("value".to_string()))
+        );
+        // Nothing was written
+        assert!(witness.iter().flatten().all(|value| value.is_zero()));
+
+        assert_eq!(
+            try_par_init(&mut witness, 0, &layout, &variables!(value)),
+            Ok(())
+        );
+        assert_eq!(witness[0][1], value);
+    }
+
+    #[test]
+    fn relative_copy_layout() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = vec![
+            vec![
+                VariableCell::create("value"),
+                ConstantCell::create(PallasField::zero()),
+            ],
+            vec![
+                ConstantCell::create(PallasField::one()),
+                RelativeCopyCell::create(-1, 0),
+            ],
+        ];
+
+        let value = PallasField::from(42u32);
+        let mut witness: [Vec<PallasField>; 2] = array::from_fn(|_| vec![PallasField::zero(); 6]);
+        init(&mut witness, 0, &layout, &variables!(value));
+        init(&mut witness, 3, &layout, &variables!(value));
+
+        for offset in [0, 3] {
+            assert_eq!(witness[0][offset], value);
+            assert_eq!(witness[1][offset + 1], value);
+        }
+    }
+
+    #[test]
+    fn relative_copy_layout_out_of_bounds() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 1>>>> =
+            vec![vec![RelativeCopyCell::create(-1, 0)]];
+
+        let mut witness: [Vec<PallasField>; 1] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        let error = WitnessError::DependencyOutOfBounds {
+            row: 0,
+            col: 0,
+            dep_row: -1,
+            dep_col: 0,
+        };
+        assert_eq!(
+            try_init(&mut witness, 0, &layout, &variables!()),
+            Err(error.clone())
+        );
+        assert_eq!(
+            try_init_cell(
+                &mut witness,
+                WitnessCtx::default(),
+                0,
+                &layout,
+                &variables!()
+            ),
+            Err(error)
+        );
+    }
+
+    #[test]
+    fn copy_layout_out_of_bounds() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = vec![vec![
+            CopyCell::create(3, 0),
+            TwosComplementCell::create(0, 2, 8),
+        ]];
+
+        let mut witness: [Vec<PallasField>; 2] = array::from_fn(|_| vec![PallasField::zero(); 2]);
+        assert_eq!(
+            try_init(&mut witness, 0, &layout, &variables!()),
+            Err(WitnessError::DependencyOutOfBounds {
+                row: 0,
+                col: 0,
+                dep_row: 3,
+                dep_col: 0
+            })
+        );
+        assert_eq!(
+            try_init_cell(
+                &mut witness,
+                WitnessCtx {
+                    offset: 1,
+                    row: 0,
+                    col: 1,
+                    index: 0
+                },
+                1,
+                &layout,
+                &variables!()
+            ),
+            Err(WitnessError::ColumnOutOfBounds { col: 2, width: 2 })
+        );
+    }
+
+    #[test]
+    fn twos_complement_layout() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 3>>>> = vec![vec![
+            ConstantCell::create(PallasField::from(5u32)),
+            TwosComplementCell::create(0, 0, 8),
+            TwosComplementCell::create(0, 1, 8),
+        ]];
+
+        let mut witness: [Vec<PallasField>; 3] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        init(&mut witness, 0, &layout, &variables!());
+
+        assert_eq!(witness[1][0], PallasField::from(0xFBu32));
+        // Negating twice yields the original value
+        assert_eq!(witness[2][0], PallasField::from(5u32));
+    }
+
+    #[test]
+    fn init_resize_layout() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = vec![
+            vec![
+                ConstantCell::create(PallasField::one()),
+                VariableCell::create("value"),
+            ],
+            vec![CopyCell::create(3, 1), CopyShiftCell::create(3, 0, 1)],
+        ];
+
+        let value = PallasField::from(9u32);
+        let mut witness: [Vec<PallasField>; 2] = array::from_fn(|_| vec![]);
+        init_resize(&mut witness, 3, &layout, &variables!(value));
+
+        assert_eq!(
+            witness,
+            [
+                vec![
+                    PallasField::zero(),
+                    PallasField::zero(),
+                    PallasField::zero(),
+                    PallasField::one(),
+                    value
+                ],
+                vec![
+                    PallasField::zero(),
+                    PallasField::zero(),
+                    PallasField::zero(),
+                    value,
+                    PallasField::from(2u32)
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_witness_layout() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = vec![
+            vec![
+                ConstantCell::create(PallasField::one()),
+                VariableCell::create("value"),
+            ],
+            vec![CopyCell::create(1, 1), CopyShiftCell::create(1, 0, 2)],
+        ];
+
+        let value = PallasField::from(9u32);
+        let mut witness: [Vec<PallasField>; 2] = array::from_fn(|_| vec![PallasField::zero(); 3]);
+        init(&mut witness, 1, &layout, &variables!(value));
+        assert_eq!(
+            validate_witness(&witness, 1, &layout, &variables!(value)),
+            Ok(())
+        );
+
+        witness[1][2] += PallasField::one();
+        assert_eq!(
+            validate_witness(&witness, 1, &layout, &variables!(value)),
+            Err((2, 1))
+        );
+    }
+
+    #[test]
+    fn indirect_layout() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 3>>>> = (0..4)
+            .map(|row| {
+                let cells: Vec<Box<dyn WitnessCell<PallasField, PallasField, 3>>> = vec![
+                    ConstantCell::create(PallasField::from(10 * row as u64)),
+                    ConstantCell::create(PallasField::from(2u32)),
+                    IndirectCell::create(row, 1, 0),
+                ];
+                cells
+            })
+            .collect();
+
+        let mut witness: [Vec<PallasField>; 3] = array::from_fn(|_| vec![PallasField::zero(); 4]);
+        init(&mut witness, 0, &layout, &variables!());
+
+        // Row 2 of the source column is initialized by the time the last rows read it
+        assert_eq!(witness[2][2], PallasField::from(20u32));
+        assert_eq!(witness[2][3], PallasField::from(20u32));
+    }
+
+    #[test]
+    #[should_panic(expected = "index 7 out of bounds of `IndirectCell`")]
+    fn indirect_layout_out_of_bounds() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = vec![vec![
+            ConstantCell::create(PallasField::from(7u32)),
+            IndirectCell::create(0, 0, 1),
+        ]];
+
+        let mut witness: [Vec<PallasField>; 2] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        init(&mut witness, 0, &layout, &variables!());
+    }
+
+    #[test]
+    fn layout_spec_roundtrip() {
+        let spec: Vec<Vec<LayoutSpec<PallasField>>> = vec![
+            vec![
+                LayoutSpec::Constant {
+                    value: PallasField::from(0x1234u32),
+                },
+                LayoutSpec::Variable {
+                    name: "value".to_string(),
+                },
+                LayoutSpec::VariableBits {
+                    name: "value".to_string(),
+                    start: 0,
+                    end: Some(4),
+                },
+            ],
+            vec![
+                LayoutSpec::Copy { row: 0, col: 1 },
+                LayoutSpec::CopyBits {
+                    row: 0,
+                    col: 0,
+                    start: 4,
+                    end: 12,
+                },
+                LayoutSpec::CopyShift {
+                    row: 0,
+                    col: 2,
+                    shift: 3,
+                },
+            ],
+        ];
+
+        let json = serde_json::to_string(&spec).unwrap();
+        let decoded: Vec<Vec<LayoutSpec<PallasField>>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, spec);
+
+        let value = PallasField::from(0xabu32);
+        let mut witness: [Vec<PallasField>; 3] = array::from_fn(|_| vec![PallasField::zero(); 2]);
+        init(&mut witness, 0, &to_layout(&spec), &variables!(value));
+        let mut decoded_witness: [Vec<PallasField>; 3] =
+            array::from_fn(|_| vec![PallasField::zero(); 2]);
+        init(
+            &mut decoded_witness,
+            0,
+            &to_layout(&decoded),
+            &variables!(value),
+        );
+
+        assert_eq!(witness, decoded_witness);
+        assert_eq!(witness[1][0], value);
+        assert_eq!(witness[1][1], PallasField::from(0x23u32));
+        assert_eq!(witness[2][1], PallasField::from(0xbu32 * 8));
+    }
+
+    #[test]
+    fn layout_spec_index() {
+        let spec: Vec<Vec<LayoutSpec<PallasField>>> = vec![vec![
+            LayoutSpec::Index {
+                name: "values".to_string(),
+                from: 0,
+                to: 2,
+            },
+            LayoutSpec::Copy { row: 0, col: 1 },
+        ]];
+
+        let values = vec![PallasField::from(1u32), PallasField::from(2u32)];
+        let mut witness: [Vec<PallasField>; 3] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        init(
+            &mut witness,
+            0,
+            &to_index_layout(&spec),
+            &variable_map!("values" => values.clone()),
+        );
+
+        assert_eq!(witness[0][0], values[0]);
+        assert_eq!(witness[1][0], values[1]);
+        assert_eq!(witness[2][0], values[1]);
+    }
+
+    #[test]
+    fn variable_product_layout() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 3>>>> = vec![vec![
+            VariableProductCell::create(&["a", "b"]),
+            VariableProductCell::create(&["a", "b", "c"]),
+            VariableProductCell::create(&[]),
+        ]];
+
+        let a = PallasField::from(5u32);
+        let b = PallasField::from(7u32);
+        let c = -PallasField::from(2u32);
+        let mut witness: [Vec<PallasField>; 3] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        init(&mut witness, 0, &layout, &variables!(a, b, c));
+
+        assert_eq!(witness[0][0], PallasField::from(35u32));
+        assert_eq!(witness[1][0], -PallasField::from(70u32));
+        assert_eq!(witness[2][0], PallasField::one());
+    }
+
+    #[test]
+    fn sqrt_layout() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 4>>>> = vec![vec![
+            SqrtCell::create("square"),
+            SqrtCell::create("non_square"),
+        ]];
+
+        let square = PallasField::from(4u32);
+        // The multiplicative generator of the field is not a square
+        let non_square = PallasField::from(5u32);
+        let mut witness: [Vec<PallasField>; 4] = array::from_fn(|_| vec![PallasField::one(); 1]);
+        init(&mut witness, 0, &layout, &variables!(square, non_square));
+
+        assert_eq!(witness[0][0].square(), square);
+        assert_eq!(witness[1][0], PallasField::one());
+        assert_eq!(witness[2][0], PallasField::zero());
+        assert_eq!(witness[3][0], PallasField::zero());
+    }
+
+    #[test]
+    fn variables_into() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 4>>>> = vec![vec![
+            VariableCell::create("a"),
+            VariableCell::create("b"),
+            VariableCell::create("c"),
+            VariableCell::create("d"),
+        ]];
+
+        let a = 12u64;
+        let b = 34u32;
+        let c = PallasField::from(56u32);
+        let d = true;
+        let mut witness: [Vec<PallasField>; 4] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        init(&mut witness, 0, &layout, &variables!(a, b, c, d));
+        assert_eq!(witness[0][0], PallasField::from(12u32));
+        assert_eq!(witness[1][0], PallasField::from(34u32));
+        assert_eq!(witness[2][0], c);
+        assert_eq!(witness[3][0], PallasField::one());
+
+        let mut mapped: [Vec<PallasField>; 4] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        init(
+            &mut mapped,
+            0,
+            &layout,
+            &variable_map!("a" => 12u64, "b" => 34u128, "c" => c, "d" => 1u8),
+        );
+        assert_eq!(mapped, witness);
+    }
+
+    #[test]
+    #[allow(clippy::type_complexity)]
+    fn dyn_index_layout() {
+        for len in 0..4u64 {
+            let values: Vec<PallasField> = (1..=len).map(PallasField::from).collect();
+            let variables = variable_map!("values" => values.clone());
+            let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, Vec<PallasField>, 5>>>> =
+                vec![vec![
+                    DynIndexCell::create("values", &variables),
+                    ConstantCell::create(PallasField::from(42u32)),
+                ]];
+
+            let mut witness: [Vec<PallasField>; 5] =
+                array::from_fn(|_| vec![PallasField::zero(); 1]);
+            init(&mut witness, 0, &layout, &variables);
+
+            for (col, value) in values.iter().enumerate() {
+                assert_eq!(witness[col][0], *value);
+            }
+            // The constant comes right after the variable-length region
+            assert_eq!(witness[len as usize][0], PallasField::from(42u32));
+        }
+    }
+
+    #[test]
+    fn witness_rows() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 3>>>> = vec![
+            vec![
+                VariableCell::create("a"),
+                VariableCell::create("b"),
+                ConstantCell::create(PallasField::from(3u32)),
+            ],
+            vec![
+                CopyCell::create(0, 1),
+                CopyShiftCell::create(0, 0, 4),
+                InnerProductCell::create(&[
+                    ((0, 0), PallasField::from(2u32)),
+                    ((0, 2), PallasField::one()),
+                ]),
+            ],
+            vec![
+                CopyCell::create(1, 2),
+                RelativeCopyCell::create(-2, 0),
+                CopyCell::create(1, 1),
+            ],
+        ];
+
+        let a = PallasField::from(5u32);
+        let b = PallasField::from(7u32);
+        let variables = variables!(a, b);
+        let mut witness: [Vec<PallasField>; 3] = array::from_fn(|_| vec![PallasField::zero(); 3]);
+        init(&mut witness, 0, &layout, &variables);
+
+        let rows = WitnessRows::new(&layout, &variables);
+        assert_eq!(rows.len(), 3);
+        let rows: Vec<_> = rows.collect();
+        for (row, values) in rows.iter().enumerate() {
+            for (col, value) in values.iter().enumerate() {
+                assert_eq!(*value, witness[col][row]);
+            }
+        }
+    }
+
+    #[test]
+    fn witness_rows_window() {
+        let len = 1024;
+        let external = Arc::new((0..len as u64).map(PallasField::from).collect::<Vec<_>>());
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 4>>>> = (0..len)
+            .map(|row| {
+                let cells: Vec<Box<dyn WitnessCell<PallasField, PallasField, 4>>> = vec![
+                    ConstantCell::create(PallasField::from(row as u64 % 5 + 1)),
+                    ProductCell::create(0, PallasField::one()),
+                    if row < 2 {
+                        VariableCell::create("a")
+                    } else {
+                        RelativeCopyCell::create(-2, 1)
+                    },
+                    ExternalColumnCell::create(external.clone()),
+                ];
+                cells
+            })
+            .collect();
+
+        let a = PallasField::from(3u32);
+        let variables = variables!(a);
+        let mut witness: [Vec<PallasField>; 4] = array::from_fn(|_| vec![PallasField::zero(); len]);
+        init(&mut witness, 0, &layout, &variables);
+
+        let mut rows = WitnessRows::new(&layout, &variables);
+        for row in 0..len {
+            let values = rows.next().unwrap();
+            for (column, value) in witness.iter().zip(values.iter()) {
+                assert_eq!(*value, column[row]);
+            }
+            // Only the two previous rows of the read columns are kept
+            assert!(rows.buffered_rows() <= 3);
+        }
+        assert!(rows.next().is_none());
+    }
+
+    #[test]
+    fn negate_layout() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> =
+            vec![vec![NegateCell::create("x"), NegateCell::create("zero")]];
+
+        let x = PallasField::from(12u32);
+        let zero = PallasField::zero();
+        let mut witness: [Vec<PallasField>; 2] = array::from_fn(|_| vec![PallasField::one(); 1]);
+        init(&mut witness, 0, &layout, &variables!(x, zero));
+
+        assert_eq!(witness[0][0], -x);
+        assert_eq!(witness[0][0] + x, PallasField::zero());
+        assert_eq!(witness[1][0], PallasField::zero());
+    }
+
+    #[test]
+    fn select_copy_layout() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 4>>>> = vec![
+            vec![
+                VariableCell::create("a"),
+                VariableCell::create("b"),
+                ConstantCell::create(PallasField::one()),
+                ConstantCell::create(PallasField::zero()),
+            ],
+            vec![
+                SelectCopyCell::create(0, 2, (0, 0), (0, 1)),
+                SelectCopyCell::create(0, 3, (0, 0), (0, 1)),
+                ConstantCell::create(PallasField::zero()),
+                ConstantCell::create(PallasField::zero()),
+            ],
+        ];
+
+        let a = PallasField::from(11u32);
+        let b = PallasField::from(22u32);
+        let mut witness: [Vec<PallasField>; 4] = array::from_fn(|_| vec![PallasField::zero(); 2]);
+        init(&mut witness, 0, &layout, &variables!(a, b));
+
+        assert_eq!(witness[0][1], a);
+        assert_eq!(witness[1][1], b);
+    }
+
+    #[test]
+    #[should_panic(expected = "of `SelectCopyCell` is not boolean")]
+    fn select_copy_not_boolean() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = vec![vec![
+            ConstantCell::create(PallasField::from(2u32)),
+            SelectCopyCell::create(0, 0, (0, 0), (0, 0)),
+        ]];
+
+        let mut witness: [Vec<PallasField>; 2] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        init(&mut witness, 0, &layout, &variables!());
+    }
+
+    #[test]
+    fn mixed_layout_dependencies() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField>>>> = vec![
+            vec![
+                ConstantCell::create(PallasField::from(12u32)),
+                ConstantCell::create(PallasField::from(0xa5a3u32)),
+                ConstantCell::create(PallasField::from(0x800u32)),
+                CopyCell::create(0, 0),
+                CopyBitsCell::create(0, 1, 0, 4),
+                CopyShiftCell::create(0, 2, 12),
+                VariableCell::create("sum_of_products"),
+            ],
+            vec![
+                CopyCell::create(0, 0),
+                CopyBitsCell::create(0, 1, 4, 8),
+                CopyShiftCell::create(0, 2, 8),
+                VariableCell::create("sum_of_products"),
+                ConstantCell::create(PallasField::zero()),
+                ConstantCell::create(PallasField::zero()),
+                ConstantCell::create(PallasField::zero()),
+                VariableCell::create("something_else"),
+                ConstantCell::create(PallasField::zero()),
+                ConstantCell::create(PallasField::zero()),
+                ConstantCell::create(PallasField::zero()),
+                ConstantCell::create(PallasField::zero()),
+                ConstantCell::create(PallasField::zero()),
+                ConstantCell::create(PallasField::zero()),
+                VariableBitsCell::create("final_value", 0, None),
+            ],
+        ];
+
+        assert_eq!(
+            layout_dependencies(&layout),
+            vec![
+                ((1, 0), CellDep::Cell { row: 0, col: 0 }),
+                ((1, 1), CellDep::Cell { row: 0, col: 1 }),
+                ((1, 2), CellDep::Cell { row: 0, col: 2 }),
+                ((0, 3), CellDep::Cell { row: 0, col: 0 }),
+                ((1, 3), CellDep::Variable("sum_of_products")),
+                ((0, 4), CellDep::Cell { row: 0, col: 1 }),
+                ((0, 5), CellDep::Cell { row: 0, col: 2 }),
+                ((0, 6), CellDep::Variable("sum_of_products")),
+                ((1, 7), CellDep::Variable("something_else")),
+                ((1, 14), CellDep::Variable("final_value")),
+            ]
+        );
+    }
+
+    #[test]
+    fn relative_copy_dependencies() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = vec![
+            vec![VariableCell::create("x"), UnknownCell::create()],
+            vec![UnknownCell::create(), RelativeCopyCell::create(-1, 0)],
+        ];
+        assert_eq!(
+            layout_dependencies(&layout),
+            vec![
+                ((0, 0), CellDep::Variable("x")),
+                (
+                    (1, 1),
+                    CellDep::Relative {
+                        row_delta: -1,
+                        col: 0
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn indirect_dependencies() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 3>>>> = vec![vec![
+            UnknownCell::create(),
+            UnknownCell::create(),
+            IndirectCell::create(0, 1, 0),
+        ]];
+        assert_eq!(
+            layout_dependencies(&layout),
+            vec![
+                ((0, 2), CellDep::Cell { row: 0, col: 1 }),
+                ((0, 2), CellDep::Column(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn product_dependencies() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = vec![vec![
+            UnknownCell::create(),
+            ProductCell::create(0, PallasField::one()),
+        ]];
+        assert_eq!(
+            layout_dependencies(&layout),
+            vec![
+                ((0, 1), CellDep::OwnColumn { row_delta: -1 }),
+                (
+                    (0, 1),
+                    CellDep::Relative {
+                        row_delta: 0,
+                        col: 0
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn twos_complement_dependencies() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = vec![
+            vec![UnknownCell::create(), UnknownCell::create()],
+            vec![UnknownCell::create(), TwosComplementCell::create(0, 0, 8)],
+        ];
+        assert_eq!(
+            layout_dependencies(&layout),
+            vec![((1, 1), CellDep::Cell { row: 0, col: 0 })]
+        );
+    }
+
+    #[test]
+    fn maybe_dependencies() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = vec![vec![
+            MaybeCell::create(|_| true, VariableCell::create("x")),
+            MaybeCell::create(|_| false, CopyCell::create(0, 0)),
+        ]];
+        assert_eq!(
+            layout_dependencies(&layout),
+            vec![
+                ((0, 0), CellDep::Variable("x")),
+                ((0, 0), CellDep::OwnColumn { row_delta: 0 }),
+                ((0, 1), CellDep::Cell { row: 0, col: 0 }),
+                ((0, 1), CellDep::OwnColumn { row_delta: 0 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn output_layout() {
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>>> = vec![
+            vec![
+                OutputCell::create(VariableCell::create("a")),
+                ConstantCell::create(PallasField::from(5u32)),
+            ],
+            vec![
+                ConstantCell::create(PallasField::from(6u32)),
+                OutputCell::create(CopyShiftCell::create(0, 1, 1)),
+            ],
+            vec![
+                OutputCell::create(VariableCell::create("b")),
+                CopyCell::create(0, 0),
+            ],
+        ];
+
+        let a = PallasField::from(3u32);
+        let b = PallasField::from(4u32);
+        let mut witness: [Vec<PallasField>; 2] = array::from_fn(|_| vec![PallasField::zero(); 3]);
+        let outputs = init_with_outputs(&mut witness, 0, &layout, &variables!(a, b));
+
+        assert_eq!(outputs, vec![a, PallasField::from(10u32), b]);
+        assert_eq!(witness[1][1], PallasField::from(10u32));
+    }
+
+    #[test]
+    fn variable_only_dependencies() {
+        let witness_reading: Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>> = vec![
+            CopyCell::create(0, 0),
+            CopyBitsCell::create(0, 0, 0, 4),
+            CopyShiftCell::create(0, 0, 4),
+            RelativeCopyCell::create(-1, 0),
+            IndirectCell::create(0, 0, 1),
+            SelectCopyCell::create(0, 0, (0, 0), (0, 1)),
+            InnerProductCell::create(&[((0, 0), PallasField::one())]),
+            TwosComplementCell::create(0, 0, 8),
+            ProductCell::create(0, PallasField::one()),
+            OutputCell::create(CopyCell::create(0, 0)),
+            MaybeCell::create(|_| true, VariableCell::create("x")),
+        ];
+        for cell in witness_reading.iter() {
+            assert!(!cell.dependencies().iter().all(CellDep::is_variable));
+        }
+
+        let variable_only: Vec<Box<dyn WitnessCell<PallasField, PallasField, 2>>> = vec![
+            ConstantCell::create(PallasField::one()),
+            VariableCell::create("x"),
+            VariableBitsCell::create("x", 0, None),
+            BoundedCell::create("x", PallasField::from(8u32)),
+            RangeCheckCell::create("x", 8),
+            MuxCell::create("s", "x", "y"),
+            LinearCombinationCell::create(&[(PallasField::one(), "x")]),
+            VariableProductCell::create(&["x", "y"]),
+            NegateCell::create("x"),
+            SqrtCell::create("x"),
+            UnknownCell::create(),
+            ExternalColumnCell::create(Arc::new(vec![PallasField::one()])),
+            ForeignReduceCell::create("x", BigUint::from(7u32)),
+            OutputCell::create(VariableCell::create("x")),
+        ];
+        for cell in variable_only.iter() {
+            assert!(cell.dependencies().iter().all(CellDep::is_variable));
+        }
+    }
+
+    #[test]
+    fn poseidon_layout() {
+        use crate::curve::KimchiCurve;
+        use mina_curves::pasta::Vesta;
+        use mina_poseidon::{
+            constants::PlonkSpongeConstantsKimchi,
+            poseidon::{ArithmeticSponge, Sponge},
+        };
+
+        let params = Vesta::sponge_params();
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField, PallasField, 3>>>> = vec![vec![
+            VariableCell::create("a"),
+            VariableCell::create("b"),
+            PoseidonCell::create(&[(0, 0), (0, 1)], params),
+        ]];
+
+        let a = PallasField::from(3u32);
+        let b = PallasField::from(5u32);
+        let mut witness: [Vec<PallasField>; 3] = array::from_fn(|_| vec![PallasField::zero(); 1]);
+        init(&mut witness, 0, &layout, &variables!(a, b));
+
+        let mut sponge = ArithmeticSponge::<PallasField, PlonkSpongeConstantsKimchi>::new(params);
+        sponge.absorb(&[a, b]);
+        assert_eq!(witness[2][0], sponge.squeeze());
+        assert_eq!(
+            layout_dependencies(&layout),
+            vec![
+                ((0, 0), CellDep::Variable("a")),
+                ((0, 1), CellDep::Variable("b")),
+                ((0, 2), CellDep::Cell { row: 0, col: 0 }),
+                ((0, 2), CellDep::Cell { row: 0, col: 1 }),
+            ]
+        );
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn metered_layout() {
+        let metrics = CellMetrics::create();
+        let constant = |value: u32| -> Box<dyn WitnessCell<PallasField>> {
+            MeteredCell::create(ConstantCell::create(PallasField::from(value)), &metrics)
+        };
+        let layout: Vec<Vec<Box<dyn WitnessCell<PallasField>>>> = vec![
+            vec![
+                constant(12),
+                constant(0xa5a3),
+                constant(0x800),
+                MeteredCell::create(CopyCell::create(0, 0), &metrics),
+                MeteredCell::create(CopyBitsCell::create(0, 1, 0, 4), &metrics),
+                MeteredCell::create(CopyShiftCell::create(0, 2, 12), &metrics),
+                MeteredCell::create(VariableCell::create("sum_of_products"), &metrics),
+                constant(0),
+                constant(0),
+                constant(0),
+                constant(0),
+                constant(0),
+                constant(0),
+                constant(0),
+                constant(0),
+            ],
+            vec![
+                MeteredCell::create(CopyCell::create(0, 0), &metrics),
+                MeteredCell::create(CopyBitsCell::create(0, 1, 4, 8), &metrics),
+                MeteredCell::create(CopyShiftCell::create(0, 2, 8), &metrics),
+                MeteredCell::create(VariableCell::create("sum_of_products"), &metrics),
+                constant(0),
+                constant(0),
+                constant(0),
+                MeteredCell::create(VariableCell::create("something_else"), &metrics),
+                constant(0),
+                constant(0),
+                constant(0),
+                constant(0),
+                constant(0),
+                constant(0),
+                MeteredCell::create(VariableCell::create("final_value"), &metrics),
+            ],
+        ];
+
+        let mut witness: [Vec<PallasField>; COLUMNS] =
+            array::from_fn(|_| vec![PallasField::zero(); 2]);
+        let sum_of_products = PallasField::from(1337u32);
+        let something_else = sum_of_products * PallasField::from(5u32);
+        let final_value = (something_else + PallasField::one()).pow([2u64]);
+        init(
+            &mut witness,
+            0,
+            &layout,
+            &variables!(sum_of_products, something_else, final_value),
+        );
+        assert_eq!(witness[14][1], final_value);
+
+        let counts: Vec<_> = metrics
+            .report()
+            .into_iter()
+            .map(|(name, metric)| (name, metric.count))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![
+                ("ConstantCell", 20),
+                ("CopyBitsCell", 2),
+                ("CopyCell", 2),
+                ("CopyShiftCell", 2),
+                ("VariableCell", 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn fixture_roundtrip() {
+        let constant = |value: u32| LayoutSpec::Constant {
+            value: PallasField::from(value),
+        };
+        let variable = |name: &str| LayoutSpec::Variable {
+            name: name.to_string(),
+        };
+        let mut row0 = vec![
+            constant(12),
+            constant(0xa5a3),
+            constant(0x800),
+            LayoutSpec::Copy { row: 0, col: 0 },
+            LayoutSpec::CopyBits {
+                row: 0,
+                col: 1,
+                start: 0,
+                end: 4,
+            },
+            LayoutSpec::CopyShift {
+                row: 0,
+                col: 2,
+                shift: 12,
+            },
+            variable("sum_of_products"),
+        ];
+        row0.resize(COLUMNS, constant(0));
+        let mut row1 = vec![
+            LayoutSpec::Copy { row: 0, col: 0 },
+            LayoutSpec::CopyBits {
+                row: 0,
+                col: 1,
+                start: 4,
+                end: 8,
+            },
+            LayoutSpec::CopyShift {
+                row: 0,
+                col: 2,
+                shift: 8,
+            },
+            variable("sum_of_products"),
+            constant(0),
+            constant(0),
+            constant(0),
+            variable("something_else"),
+        ];
+        row1.resize(COLUMNS - 1, constant(0));
+        row1.push(variable("final_value"));
+        let spec = vec![row0, row1];
+
+        let sum_of_products = PallasField::from(1337u32);
+        let something_else = sum_of_products * PallasField::from(5u32);
+        let final_value = (something_else + PallasField::one()).pow([2u64]);
+        let variables = variables!(sum_of_products, something_else, final_value);
+
+        let path = std::env::temp_dir().join("kimchi_mixed_layout_fixture.msgpack");
+        dump_fixture(&spec, &variables, &path).unwrap();
+        let fixture = load_fixture::<PallasField>(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(fixture.layout, spec);
+        assert!(matches!(
+            load_fixture::<PallasField>(&path),
+            Err(crate::error::EncodingError::Io(_))
+        ));
+
+        let mut witness: [Vec<PallasField>; COLUMNS] =
+            array::from_fn(|_| vec![PallasField::zero(); 2]);
+        init(&mut witness, 0, &to_layout(&spec), &variables);
+        let mut reloaded: [Vec<PallasField>; COLUMNS] =
+            array::from_fn(|_| vec![PallasField::zero(); 2]);
+        init(
+            &mut reloaded,
+            0,
+            &to_layout(&fixture.layout),
+            &fixture.variables(),
+        );
+
+        assert_eq!(witness, reloaded);
+        assert_eq!(reloaded[3][0], PallasField::from(12u32));
+        assert_eq!(reloaded[14][1], final_value);
+    }
 }
@@ -0,0 +1,39 @@
+use super::{variables::Variables, WitnessCell, WitnessCtx};
+use ark_ff::Field;
+
+/// Witness cell assigned from one of two variables, depending on a boolean selector variable
+/// See [Variables] for more details
+pub struct MuxCell<'a> {
+    selector: &'a str,
+    if_true: &'a str,
+    if_false: &'a str,
+}
+
+impl<'a> MuxCell<'a> {
+    /// Create witness cell assigned from the variable `if_true` if the variable `selector`
+    /// is one, and from the variable `if_false` if it is zero
+    pub fn create(selector: &'a str, if_true: &'a str, if_false: &'a str) -> Box<MuxCell<'a>> {
+        Box::new(MuxCell {
+            selector,
+            if_true,
+            if_false,
+        })
+    }
+}
+
+impl<'a, F: Field, const W: usize> WitnessCell<F, F, W> for MuxCell<'a> {
+    fn value(&self, _witness: &mut [Vec<F>; W], variables: &Variables<F>, _ctx: WitnessCtx) -> F {
+        let selector = variables[self.selector];
+        if selector.is_one() {
+            variables[self.if_true]
+        } else if selector.is_zero() {
+            variables[self.if_false]
+        } else {
+            panic!("selector `{}` of `MuxCell` is not boolean", self.selector)
+        }
+    }
+
+    fn variable_names(&self) -> Vec<&str> {
+        vec![self.selector, self.if_true, self.if_false]
+    }
+}
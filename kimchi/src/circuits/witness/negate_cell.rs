@@ -0,0 +1,25 @@
+use super::{variables::Variables, WitnessCell, WitnessCtx};
+use ark_ff::Field;
+
+/// Witness cell assigned from the additive inverse of a variable
+/// See [Variables] for more details
+pub struct NegateCell<'a> {
+    name: &'a str,
+}
+
+impl<'a> NegateCell<'a> {
+    /// Create witness cell assigned from the negation of a variable name
+    pub fn create(name: &'a str) -> Box<NegateCell<'a>> {
+        Box::new(NegateCell { name })
+    }
+}
+
+impl<'a, F: Field, const W: usize> WitnessCell<F, F, W> for NegateCell<'a> {
+    fn value(&self, _witness: &mut [Vec<F>; W], variables: &Variables<F>, _ctx: WitnessCtx) -> F {
+        -variables[self.name]
+    }
+
+    fn variable_names(&self) -> Vec<&str> {
+        vec![self.name]
+    }
+}
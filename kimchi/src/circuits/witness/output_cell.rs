@@ -0,0 +1,46 @@
+use super::{variables::Variables, CellDep, WitnessCell, WitnessCtx};
+use crate::error::WitnessError;
+use ark_ff::Field;
+
+/// Witness cell assigned from another witness cell, whose value is also
+/// collected as an output of the layout, see [super::init_with_outputs]
+pub struct OutputCell<'a, F: Field, T, const W: usize> {
+    inner: Box<dyn WitnessCell<F, T, W> + 'a>,
+}
+
+impl<'a, F: Field, T, const W: usize> OutputCell<'a, F, T, W> {
+    /// Create witness cell assigned from `inner`, marked as an output of the layout
+    pub fn create(inner: Box<dyn WitnessCell<F, T, W> + 'a>) -> Box<OutputCell<'a, F, T, W>> {
+        Box::new(OutputCell { inner })
+    }
+}
+
+impl<'a, F: Field, T, const W: usize> WitnessCell<F, T, W> for OutputCell<'a, F, T, W> {
+    fn value(&self, witness: &mut [Vec<F>; W], variables: &Variables<T>, ctx: WitnessCtx) -> F {
+        self.inner.value(witness, variables, ctx)
+    }
+
+    fn length(&self) -> usize {
+        self.inner.length()
+    }
+
+    fn is_unknown(&self) -> bool {
+        self.inner.is_unknown()
+    }
+
+    fn variable_names(&self) -> Vec<&str> {
+        self.inner.variable_names()
+    }
+
+    fn check_variables(&self, variables: &Variables<T>) -> Result<(), WitnessError> {
+        self.inner.check_variables(variables)
+    }
+
+    fn dependencies(&self) -> Vec<CellDep> {
+        self.inner.dependencies()
+    }
+
+    fn is_output(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,63 @@
+use super::{variables::Variables, WitnessCell, WitnessCtx};
+use crate::error::WitnessError;
+use ark_ff::PrimeField;
+use std::array;
+
+/// Initialize a partial witness based on layout and computed variables, where the
+/// cells created with [super::UnknownCell] are left as `None`.
+/// Note that cells reading an unknown cell of the witness see it as zero.
+pub fn init_partial<F: PrimeField, T, const W: usize>(
+    witness: &mut [Vec<Option<F>>; W],
+    offset: usize,
+    layout: &[Vec<Box<dyn WitnessCell<F, T, W> + '_>>],
+    variables: &Variables<T>,
+) {
+    let mut dense: [Vec<F>; W] = array::from_fn(|col| {
+        witness[col]
+            .iter()
+            .map(|value| value.unwrap_or_else(F::zero))
+            .collect()
+    });
+    for row in 0..layout.len() {
+        let mut col = 0;
+        for cell in layout[row].iter() {
+            // The loop will only run more than once if the cell is an IndexCell
+            for index in 0..cell.length() {
+                let value = cell.value(
+                    &mut dense,
+                    variables,
+                    WitnessCtx {
+                        offset,
+                        row,
+                        col,
+                        index,
+                    },
+                );
+                dense[col][row + offset] = value;
+                witness[col][row + offset] = if cell.is_unknown() { None } else { Some(value) };
+                col += 1;
+            }
+        }
+    }
+}
+
+/// Converts a partial witness into a witness, replacing the unknown cells with `default`.
+/// If no `default` is given, returns an error pointing to the first unknown cell instead.
+pub fn finalize<F: PrimeField, const W: usize>(
+    witness: [Vec<Option<F>>; W],
+    default: Option<F>,
+) -> Result<[Vec<F>; W], WitnessError> {
+    if default.is_none() {
+        for (col, column) in witness.iter().enumerate() {
+            if let Some(row) = column.iter().position(Option::is_none) {
+                return Err(WitnessError::UnknownCell { row, col });
+            }
+        }
+    }
+    Ok(witness.map(|column| {
+        column
+            .into_iter()
+            .map(|value| value.or(default).expect("unknown cells were checked"))
+            .collect()
+    }))
+}
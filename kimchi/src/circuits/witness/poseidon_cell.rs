@@ -0,0 +1,50 @@
+use super::{variables::Variables, CellDep, WitnessCell, WitnessCtx};
+use ark_ff::Field;
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    poseidon::{ArithmeticSponge, ArithmeticSpongeParams, Sponge},
+};
+
+/// Witness cell holding the Poseidon digest of other witness cells
+pub struct PoseidonCell<F: Field> {
+    sources: Vec<(usize, usize)>,
+    params: &'static ArithmeticSpongeParams<F>,
+}
+
+impl<F: Field> PoseidonCell<F> {
+    /// Create a witness cell holding the digest of the kimchi sponge with the given `params`
+    /// after absorbing the witness cells at the `(row, col)` positions, in order.
+    /// Note that the source cells must be initialized before this cell.
+    pub fn create(
+        sources: &[(usize, usize)],
+        params: &'static ArithmeticSpongeParams<F>,
+    ) -> Box<PoseidonCell<F>> {
+        Box::new(PoseidonCell {
+            sources: sources.to_vec(),
+            params,
+        })
+    }
+}
+
+impl<F: Field, T, const W: usize> WitnessCell<F, T, W> for PoseidonCell<F> {
+    fn value(&self, witness: &mut [Vec<F>; W], _variables: &Variables<T>, _ctx: WitnessCtx) -> F {
+        let inputs: Vec<F> = self
+            .sources
+            .iter()
+            .map(|(row, col)| witness[*col][*row])
+            .collect();
+        let mut sponge = ArithmeticSponge::<F, PlonkSpongeConstantsKimchi>::new(self.params);
+        sponge.absorb(&inputs);
+        sponge.squeeze()
+    }
+
+    fn dependencies(&self) -> Vec<CellDep> {
+        self.sources
+            .iter()
+            .map(|(row, col)| CellDep::Cell {
+                row: *row,
+                col: *col,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,38 @@
+use super::{variables::Variables, CellDep, WitnessCell, WitnessCtx};
+use ark_ff::Field;
+
+/// Witness cell holding the running product of another column
+pub struct ProductCell<F: Field> {
+    factor_col: usize,
+    init: F,
+}
+
+impl<F: Field> ProductCell<F> {
+    /// Create witness cell holding the product of the previous row of this cell's column
+    /// and the current row of column `factor_col`. The first row of the layout uses `init`
+    /// as the previous product. Note that `factor_col` must be initialized before this cell.
+    pub fn create(factor_col: usize, init: F) -> Box<ProductCell<F>> {
+        Box::new(ProductCell { factor_col, init })
+    }
+}
+
+impl<F: Field, T, const W: usize> WitnessCell<F, T, W> for ProductCell<F> {
+    fn value(&self, witness: &mut [Vec<F>; W], _variables: &Variables<T>, ctx: WitnessCtx) -> F {
+        let prev = if ctx.row == 0 {
+            self.init
+        } else {
+            witness[ctx.col][ctx.offset + ctx.row - 1]
+        };
+        prev * witness[self.factor_col][ctx.offset + ctx.row]
+    }
+
+    fn dependencies(&self) -> Vec<CellDep> {
+        vec![
+            CellDep::OwnColumn { row_delta: -1 },
+            CellDep::Relative {
+                row_delta: 0,
+                col: self.factor_col,
+            },
+        ]
+    }
+}
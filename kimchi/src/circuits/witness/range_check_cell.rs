@@ -0,0 +1,35 @@
+use super::{variables::Variables, WitnessCell, WitnessCtx};
+use ark_ff::{BigInteger, PrimeField};
+
+/// Witness cell assigned from a variable that must fit in a given number of bits
+/// See [Variables] for more details
+pub struct RangeCheckCell<'a> {
+    name: &'a str,
+    bits: usize,
+}
+
+impl<'a> RangeCheckCell<'a> {
+    /// Create witness cell assigned from a variable name, whose value
+    /// must have no bits set above the `bits` least significant ones
+    pub fn create(name: &'a str, bits: usize) -> Box<RangeCheckCell<'a>> {
+        Box::new(RangeCheckCell { name, bits })
+    }
+}
+
+impl<'a, F: PrimeField, const W: usize> WitnessCell<F, F, W> for RangeCheckCell<'a> {
+    fn value(&self, _witness: &mut [Vec<F>; W], variables: &Variables<F>, _ctx: WitnessCtx) -> F {
+        let value = variables[self.name];
+        assert!(
+            value.into_repr().num_bits() as usize <= self.bits,
+            "variable `{}` does not fit in {} bits: {}",
+            self.name,
+            self.bits,
+            value
+        );
+        value
+    }
+
+    fn variable_names(&self) -> Vec<&str> {
+        vec![self.name]
+    }
+}
@@ -0,0 +1,34 @@
+use super::{variables::Variables, CellDep, WitnessCell, WitnessCtx};
+use ark_ff::Field;
+
+/// Witness cell copied from another witness cell located relatively to the current row
+pub struct RelativeCopyCell {
+    row_delta: i32,
+    col: usize,
+}
+
+impl RelativeCopyCell {
+    /// Create a witness cell copied from the witness cell at column `col`
+    /// and `row_delta` rows away from the row of this cell (e.g. -1 for the previous row)
+    pub fn create(row_delta: i32, col: usize) -> Box<RelativeCopyCell> {
+        Box::new(RelativeCopyCell { row_delta, col })
+    }
+}
+
+impl<F: Field, T, const W: usize> WitnessCell<F, T, W> for RelativeCopyCell {
+    fn value(&self, witness: &mut [Vec<F>; W], _variables: &Variables<T>, ctx: WitnessCtx) -> F {
+        let source = (ctx.row + ctx.offset) as i64 + self.row_delta as i64;
+        assert!(
+            source >= 0 && (source as usize) < witness[self.col].len(),
+            "row {source} out of bounds of `RelativeCopyCell`"
+        );
+        witness[self.col][source as usize]
+    }
+
+    fn dependencies(&self) -> Vec<CellDep> {
+        vec![CellDep::Relative {
+            row_delta: self.row_delta,
+            col: self.col,
+        }]
+    }
+}
@@ -0,0 +1,199 @@
+use super::{
+    check_layout, layout_dependencies, variables::Variables, CellDep, WitnessCell, WitnessCtx,
+};
+use ark_ff::PrimeField;
+use std::array;
+
+/// Iterator producing the rows of a witness one at a time, see [WitnessRows::new]
+pub struct WitnessRows<'a, F: PrimeField, T, const W: usize> {
+    layout: &'a [Vec<Box<dyn WitnessCell<F, T, W> + 'a>>],
+    variables: &'a Variables<'a, T>,
+    // Whether each cell of the layout only reads variables, in which case it never reads the buffer
+    variable_only: Vec<Vec<bool>>,
+    // Rows computed so far that cells may still read, only for the columns read by some cell
+    buffer: [Vec<F>; W],
+    // Whether some cell of the layout reads the column
+    read: [bool; W],
+    // Number of rows of the column needed by the cells reading given positions of it
+    reach: [usize; W],
+    // Number of rows before and after their own row read by the cells
+    before: usize,
+    after: usize,
+    // Whether the cells only read the witness relatively to their own row,
+    // in which case the buffer only holds a window of rows around the current row
+    windowed: bool,
+    // First row of the layout held by the buffer
+    start: usize,
+    row: usize,
+}
+
+impl<'a, F: PrimeField, T, const W: usize> WitnessRows<'a, F, T, W> {
+    /// Create an iterator yielding the rows of the witness described by `layout` and `variables`,
+    /// as [super::init] would compute them. Each row is only computed when it is requested.
+    ///
+    /// Only the columns read by some cell are buffered. When the cells only read the witness
+    /// relatively to their own row (e.g. [super::RelativeCopyCell] or [super::ProductCell]),
+    /// the buffer is a window of rows around the current row, and the cells reading it are
+    /// evaluated at their position in the window. Otherwise, the buffered columns hold all the
+    /// rows up to the current one and the ones read at given positions (e.g. by [super::CopyCell]).
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the layout is malformed, see [super::try_init].
+    pub fn new(
+        layout: &'a [Vec<Box<dyn WitnessCell<F, T, W> + 'a>>],
+        variables: &'a Variables<'a, T>,
+    ) -> Self {
+        check_layout(&[layout.len(); W], 0, layout, variables)
+            .expect("failed to initialize witness rows");
+
+        let mut read = [false; W];
+        let mut reach = [0; W];
+        let (mut before, mut after) = (0, 0);
+        let mut windowed = true;
+        for ((_, col), dependency) in layout_dependencies(layout) {
+            let row_delta = match dependency {
+                CellDep::Variable(_) => continue,
+                CellDep::Relative { row_delta, col } => {
+                    read[col] = true;
+                    row_delta
+                }
+                CellDep::OwnColumn { row_delta } => {
+                    read[col] = true;
+                    row_delta
+                }
+                CellDep::Cell { row, col } => {
+                    read[col] = true;
+                    reach[col] = reach[col].max(row + 1);
+                    windowed = false;
+                    continue;
+                }
+                CellDep::Column(col) => {
+                    read[col] = true;
+                    reach[col] = layout.len();
+                    windowed = false;
+                    continue;
+                }
+            };
+            before = before.max(row_delta.min(0).unsigned_abs() as usize);
+            after = after.max(row_delta.max(0) as usize);
+        }
+
+        let variable_only = layout
+            .iter()
+            .map(|cells| {
+                cells
+                    .iter()
+                    .map(|cell| cell.dependencies().iter().all(CellDep::is_variable))
+                    .collect()
+            })
+            .collect();
+        let window = if windowed { before + 1 + after } else { 0 };
+        let buffer = array::from_fn(|col| {
+            if read[col] {
+                vec![F::zero(); window]
+            } else {
+                vec![]
+            }
+        });
+
+        WitnessRows {
+            layout,
+            variables,
+            variable_only,
+            buffer,
+            read,
+            reach,
+            before,
+            after,
+            windowed,
+            start: 0,
+            row: 0,
+        }
+    }
+
+    /// Number of rows currently held by the buffer of each column read by the cells
+    pub fn buffered_rows(&self) -> usize {
+        self.buffer.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    /// Slides the window of buffered rows so that it holds the rows read from the current row,
+    /// returning the position of the current row in the window
+    fn slide(&mut self) -> usize {
+        let start = self.row.saturating_sub(self.before);
+        let row = self.row - start;
+        for (column, read) in self.buffer.iter_mut().zip(self.read) {
+            if read {
+                column.rotate_left(start - self.start);
+                // The current and later rows are not computed yet
+                column[row..].fill(F::zero());
+            }
+        }
+        self.start = start;
+        row
+    }
+
+    /// Grows the buffered columns so that they hold the current row and the rows read from it
+    fn grow(&mut self) {
+        let len = (self.row + 1 + self.after).min(self.layout.len());
+        for ((column, read), reach) in self.buffer.iter_mut().zip(self.read).zip(self.reach) {
+            let len = len.max(reach);
+            if read && column.len() < len {
+                column.resize(len, F::zero());
+            }
+        }
+    }
+}
+
+impl<'a, F: PrimeField, T, const W: usize> Iterator for WitnessRows<'a, F, T, W> {
+    type Item = [F; W];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.row >= self.layout.len() {
+            return None;
+        }
+        // Position of the current row in the buffer
+        let buffer_row = if self.windowed {
+            self.slide()
+        } else {
+            self.grow();
+            self.row
+        };
+
+        let mut values = [F::zero(); W];
+        let mut col = 0;
+        for (cell, &variable_only) in self.layout[self.row]
+            .iter()
+            .zip(self.variable_only[self.row].iter())
+        {
+            let row = if variable_only { self.row } else { buffer_row };
+            // The loop will only run more than once if the cell is an IndexCell
+            for index in 0..cell.length() {
+                let value = cell.value(
+                    &mut self.buffer,
+                    self.variables,
+                    WitnessCtx {
+                        offset: 0,
+                        row,
+                        col,
+                        index,
+                    },
+                );
+                if self.read[col] {
+                    self.buffer[col][buffer_row] = value;
+                }
+                values[col] = value;
+                col += 1;
+            }
+        }
+        self.row += 1;
+        Some(values)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.layout.len() - self.row;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, F: PrimeField, T, const W: usize> ExactSizeIterator for WitnessRows<'a, F, T, W> {}
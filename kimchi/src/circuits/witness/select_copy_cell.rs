@@ -0,0 +1,49 @@
+use ark_ff::Field;
+
+use super::{variables::Variables, CellDep, WitnessCell, WitnessCtx};
+
+/// Witness cell copied from one of two witness cells, depending on a boolean witness cell
+pub struct SelectCopyCell {
+    cond: (usize, usize),
+    a: (usize, usize),
+    b: (usize, usize),
+}
+
+impl SelectCopyCell {
+    /// Create a witness cell copied from the witness cell at position `a` if the witness cell
+    /// at position (cond_row, cond_col) is one, and from the witness cell at position `b` if it is zero
+    pub fn create(
+        cond_row: usize,
+        cond_col: usize,
+        a: (usize, usize),
+        b: (usize, usize),
+    ) -> Box<SelectCopyCell> {
+        Box::new(SelectCopyCell {
+            cond: (cond_row, cond_col),
+            a,
+            b,
+        })
+    }
+}
+
+impl<F: Field, T, const W: usize> WitnessCell<F, T, W> for SelectCopyCell {
+    fn value(&self, witness: &mut [Vec<F>; W], _variables: &Variables<T>, _ctx: WitnessCtx) -> F {
+        let (cond_row, cond_col) = self.cond;
+        let cond = witness[cond_col][cond_row];
+        let (row, col) = if cond.is_one() {
+            self.a
+        } else if cond.is_zero() {
+            self.b
+        } else {
+            panic!("condition at ({cond_row}, {cond_col}) of `SelectCopyCell` is not boolean")
+        };
+        witness[col][row]
+    }
+
+    fn dependencies(&self) -> Vec<CellDep> {
+        [self.cond, self.a, self.b]
+            .into_iter()
+            .map(|(row, col)| CellDep::Cell { row, col })
+            .collect()
+    }
+}
@@ -0,0 +1,36 @@
+use super::{variables::Variables, WitnessCell, WitnessCtx};
+use ark_ff::SquareRootField;
+
+/// Witness cell spanning two columns, holding a square root of a variable
+/// followed by a boolean flag telling whether that square root exists
+/// See [Variables] for more details
+pub struct SqrtCell<'a> {
+    name: &'a str,
+}
+
+impl<'a> SqrtCell<'a> {
+    /// Create witness cell assigned from the square root of a variable name,
+    /// or zero if the variable is not a quadratic residue
+    pub fn create(name: &'a str) -> Box<SqrtCell<'a>> {
+        Box::new(SqrtCell { name })
+    }
+}
+
+impl<'a, F: SquareRootField, const W: usize> WitnessCell<F, F, W> for SqrtCell<'a> {
+    fn value(&self, _witness: &mut [Vec<F>; W], variables: &Variables<F>, ctx: WitnessCtx) -> F {
+        let root = variables[self.name].sqrt();
+        match ctx.index {
+            0 => root.unwrap_or_else(F::zero),
+            1 => F::from(root.is_some()),
+            _ => panic!("index out of bounds of `SqrtCell`"),
+        }
+    }
+
+    fn length(&self) -> usize {
+        2
+    }
+
+    fn variable_names(&self) -> Vec<&str> {
+        vec![self.name]
+    }
+}
@@ -0,0 +1,40 @@
+use super::{variables::Variables, CellDep, WitnessCell, WitnessCtx};
+use ark_ff::PrimeField;
+use num_bigint::BigUint;
+use o1_utils::FieldHelpers;
+
+/// Witness cell holding the two's complement negation of another witness cell
+pub struct TwosComplementCell {
+    row: usize,
+    col: usize,
+    bits: usize,
+}
+
+impl TwosComplementCell {
+    /// Create witness cell holding `(2^bits - x) mod 2^bits`, where `x` is the value of
+    /// the witness cell at position (row, col), which must fit in `bits` bits
+    pub fn create(row: usize, col: usize, bits: usize) -> Box<TwosComplementCell> {
+        Box::new(TwosComplementCell { row, col, bits })
+    }
+}
+
+impl<F: PrimeField, T, const W: usize> WitnessCell<F, T, W> for TwosComplementCell {
+    fn value(&self, witness: &mut [Vec<F>; W], _variables: &Variables<T>, _ctx: WitnessCtx) -> F {
+        let source = witness[self.col][self.row].to_biguint();
+        assert!(
+            source.bits() <= self.bits as u64,
+            "source of `TwosComplementCell` does not fit in {} bits",
+            self.bits
+        );
+        let modulus = BigUint::from(1u32) << self.bits;
+        F::from_biguint(&((&modulus - source) % modulus))
+            .expect("failed to deserialize two's complement for twos complement cell")
+    }
+
+    fn dependencies(&self) -> Vec<CellDep> {
+        vec![CellDep::Cell {
+            row: self.row,
+            col: self.col,
+        }]
+    }
+}
@@ -0,0 +1,23 @@
+use super::{variables::Variables, WitnessCell, WitnessCtx};
+use ark_ff::Field;
+
+/// Witness cell intentionally left unknown, to be filled later on.
+/// See [super::init_partial] for more details
+pub struct UnknownCell {}
+
+impl UnknownCell {
+    /// Create witness cell that is left unknown
+    pub fn create() -> Box<UnknownCell> {
+        Box::new(UnknownCell {})
+    }
+}
+
+impl<F: Field, T, const W: usize> WitnessCell<F, T, W> for UnknownCell {
+    fn value(&self, _witness: &mut [Vec<F>; W], _variables: &Variables<T>, _ctx: WitnessCtx) -> F {
+        F::zero()
+    }
+
+    fn is_unknown(&self) -> bool {
+        true
+    }
+}
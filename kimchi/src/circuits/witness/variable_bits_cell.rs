@@ -1,4 +1,4 @@
-use super::{variables::Variables, WitnessCell};
+use super::{variables::Variables, WitnessCell, WitnessCtx};
 use ark_ff::Field;
 use o1_utils::FieldHelpers;
 
@@ -19,7 +19,7 @@ impl<'a> VariableBitsCell<'a> {
 }
 
 impl<'a, F: Field, const W: usize> WitnessCell<F, F, W> for VariableBitsCell<'a> {
-    fn value(&self, _witness: &mut [Vec<F>; W], variables: &Variables<F>, _index: usize) -> F {
+    fn value(&self, _witness: &mut [Vec<F>; W], variables: &Variables<F>, _ctx: WitnessCtx) -> F {
         let bits = if let Some(end) = self.end {
             F::from_bits(&variables[self.name].to_bits()[self.start..end])
         } else {
@@ -27,4 +27,8 @@ impl<'a, F: Field, const W: usize> WitnessCell<F, F, W> for VariableBitsCell<'a>
         };
         bits.expect("failed to deserialize field bits for variable bits cell")
     }
+
+    fn variable_names(&self) -> Vec<&str> {
+        vec![self.name]
+    }
 }
@@ -1,4 +1,4 @@
-use super::{variables::Variables, WitnessCell};
+use super::{variables::Variables, WitnessCell, WitnessCtx};
 use ark_ff::Field;
 
 /// Witness cell assigned from a variable
@@ -15,7 +15,11 @@ impl<'a> VariableCell<'a> {
 }
 
 impl<'a, F: Field, const W: usize> WitnessCell<F, F, W> for VariableCell<'a> {
-    fn value(&self, _witness: &mut [Vec<F>; W], variables: &Variables<F>, _index: usize) -> F {
+    fn value(&self, _witness: &mut [Vec<F>; W], variables: &Variables<F>, _ctx: WitnessCtx) -> F {
         variables[self.name]
     }
+
+    fn variable_names(&self) -> Vec<&str> {
+        vec![self.name]
+    }
 }
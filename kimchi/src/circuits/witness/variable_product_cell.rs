@@ -0,0 +1,30 @@
+use super::{variables::Variables, WitnessCell, WitnessCtx};
+use ark_ff::Field;
+
+/// Witness cell holding the product of several variables
+/// See [Variables] for more details
+pub struct VariableProductCell<'a> {
+    names: Vec<&'a str>,
+}
+
+impl<'a> VariableProductCell<'a> {
+    /// Create witness cell holding the product of `variables[name]` over the given names.
+    /// An empty product is one.
+    pub fn create(names: &[&'a str]) -> Box<VariableProductCell<'a>> {
+        Box::new(VariableProductCell {
+            names: names.to_vec(),
+        })
+    }
+}
+
+impl<'a, F: Field, const W: usize> WitnessCell<F, F, W> for VariableProductCell<'a> {
+    fn value(&self, _witness: &mut [Vec<F>; W], variables: &Variables<F>, _ctx: WitnessCtx) -> F {
+        self.names
+            .iter()
+            .fold(F::one(), |acc, name| acc * variables[*name])
+    }
+
+    fn variable_names(&self) -> Vec<&str> {
+        self.names.clone()
+    }
+}
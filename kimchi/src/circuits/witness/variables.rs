@@ -28,7 +28,8 @@
 ///
 ///   Third, when you're ready to generate the witness, you pass those
 ///   variables to the witness creation functions using variables!(foo, bar)
-///   or variable_map!("foo" => 12, "bar" => blah).
+///   or variable_map!("foo" => 12, "bar" => blah). Values are converted
+///   with `Into`, so any type convertible into a field element can be used.
 ///```ignore
 ///     Ex.
 ///
@@ -61,6 +62,16 @@ impl<'a, T> Variables<'a, T> {
     pub fn insert(&mut self, name: &'a str, value: T) {
         self.0.insert(name, value);
     }
+
+    /// Returns whether the variable map contains a variable with the given name
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// Returns an iterator over the names and values of the variables, in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, &T)> {
+        self.0.iter().map(|(name, value)| (*name, value))
+    }
 }
 
 impl<'a, T> Index<&'a str> for Variables<'a, T> {
@@ -77,6 +88,7 @@ impl<'a, T> IndexMut<&'a str> for Variables<'a, T> {
 }
 
 /// Macro to simplify mapping of layout variable
+/// The values are converted into the variable type with `Into`
 #[macro_export]
 macro_rules! variables {
     () => {
@@ -84,17 +96,18 @@ macro_rules! variables {
     };
     ($( $var: ident ),*) => {{
          let mut vars = Variables::create();
-         $( vars.insert(stringify!{$var}, $var); )*
+         $( vars.insert(stringify!{$var}, $var.into()); )*
          vars
     }}
 }
 
 /// Macro to simplify creation of layout map
+/// The values are converted into the variable type with `Into`
 #[macro_export]
 macro_rules! variable_map {
     [$( $name: expr => $value: expr ),*] => {{
         let mut vars = Variables::create();
-        $( vars.insert($name, $value); )*
+        $( vars.insert($name, $value.into()); )*
         vars
     }}
 }
@@ -108,3 +108,69 @@ pub enum VerifierIndexError {
     #[error("srs has already been set")]
     SRSHasBeenSet,
 }
+
+/// Errors that can arise when generating a witness from a layout
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum WitnessError {
+    #[error("the witness cell at row {row} and column {col} is unknown")]
+    UnknownCell { row: usize, col: usize },
+
+    #[error("the layout has no cell {cell} in row {row}")]
+    LayoutOutOfBounds { row: usize, cell: usize },
+
+    #[error("the column {col} is out of bounds of the witness (width {width})")]
+    ColumnOutOfBounds { col: usize, width: usize },
+
+    #[error("the row {row} is out of bounds of the witness column {col} (length {len})")]
+    RowOutOfBounds { row: usize, col: usize, len: usize },
+
+    #[error(
+        "the cell {cell} in row {row} of the layout spans the columns {start}..{end}, but the witness has {width}"
+    )]
+    CellOutOfBounds {
+        row: usize,
+        cell: usize,
+        start: usize,
+        end: usize,
+        width: usize,
+    },
+
+    #[error("the witness has {have} rows, but the layout needs {need}")]
+    InsufficientRows { have: usize, need: usize },
+
+    #[error("the variable {0} is missing")]
+    MissingVariable(String),
+
+    #[error("the variable {name} has {len} elements, but the layout reads {need}")]
+    VariableTooShort {
+        name: String,
+        len: usize,
+        need: usize,
+    },
+
+    #[error(
+        "the cell at row {row} and column {col} reads row {dep_row} of column {dep_col}, which is out of bounds of the witness"
+    )]
+    DependencyOutOfBounds {
+        row: usize,
+        col: usize,
+        dep_row: i64,
+        dep_col: usize,
+    },
+}
+
+/// Errors that can arise when writing or reading a linearization or a witness fixture
+#[derive(Error, Debug)]
+pub enum EncodingError {
+    #[error("the file could not be accessed: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("a field element could not be encoded or decoded: {0}")]
+    Field(#[from] ark_serialize::SerializationError),
+
+    #[error("the content could not be encoded: {0}")]
+    Encode(#[from] rmp_serde::encode::Error),
+
+    #[error("the content could not be decoded: {0}")]
+    Decode(#[from] rmp_serde::decode::Error),
+}
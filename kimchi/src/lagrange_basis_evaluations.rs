@@ -1,5 +1,7 @@
 use ark_ff::{batch_inversion_and_mul, FftField};
 use ark_poly::{EvaluationDomain, Evaluations, Radix2EvaluationDomain as D};
+use ark_std::cfg_into_iter;
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
 /// The evaluations of all normalized lagrange basis polynomials at a given
@@ -14,11 +16,9 @@ impl<F: FftField> LagrangeBasisEvaluations<F> {
         assert_eq!(p.evals.len() % self.evals[0].len(), 0);
         let stride = p.evals.len() / self.evals[0].len();
         let p_evals = &p.evals;
-        (&self.evals)
-            .into_par_iter()
+        cfg_into_iter!(&self.evals)
             .map(|evals| {
-                evals
-                    .into_par_iter()
+                cfg_into_iter!(evals)
                     .enumerate()
                     .map(|(i, e)| p_evals[stride * i] * e)
                     .sum()
@@ -95,8 +95,7 @@ impl<F: FftField> LagrangeBasisEvaluations<F> {
             let omegas: Vec<F> = domain.elements().collect();
             let omega_invs: Vec<F> = (0..n).map(|i| omegas[(n - i) % n]).collect();
 
-            omegas
-                .into_par_iter()
+            cfg_into_iter!(omegas)
                 .zip(omega_invs)
                 .map(|(omega_i, omega_i_inv)| omega_i_inv * t_0 * (x - omega_i))
                 .collect()
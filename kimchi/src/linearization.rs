@@ -6,7 +6,7 @@ use crate::circuits::expr;
 use crate::circuits::lookup;
 use crate::circuits::lookup::{
     constraints::LookupConfiguration,
-    lookups::{LookupFeatures, LookupInfo, LookupPattern, LookupPatterns},
+    lookups::{LookupInfo, LookupPattern, LookupPatterns},
 };
 use crate::circuits::polynomials::{
     complete_add::CompleteAdd,
@@ -25,21 +25,164 @@ use crate::circuits::polynomials::{
 use crate::circuits::{
     berkeley_columns::Column,
     constraints::FeatureFlags,
-    expr::{ConstantExpr, Expr, FeatureFlag, Linearization, PolishToken},
-    gate::GateType,
+    expr::{
+        Challenges, ColumnEvaluations, ConstantExpr, ConstantTerm, Constants, Expr, ExprError,
+        FeatureFlag, Linearization, PolishToken,
+    },
+    gate::{CircuitGate, GateType},
     wires::COLUMNS,
 };
-use ark_ff::{FftField, PrimeField, SquareRootField, Zero};
+use crate::error::EncodingError;
+use crate::proof::PointEvaluations;
+use ark_ff::{FftField, Field, PrimeField, SquareRootField, Zero};
+use ark_poly::{EvaluationDomain, Radix2EvaluationDomain as D};
+use once_cell::sync::{Lazy, OnceCell};
+use std::any::{Any, TypeId};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex, PoisonError};
+use thiserror::Error;
 
-/// Get the expresion of constraints.
+/// An argument whose constraints are combined by [constraints_expr]
+struct ArgumentConstraints<F: PrimeField> {
+    /// Name of the argument, as shown by [describe_constraints]
+    name: &'static str,
+    /// Feature flag enabling the argument, or `None` if it is always enabled
+    flag: Option<FeatureFlag>,
+    argument_type: ArgumentType,
+    /// Number of constraints of the argument
+    constraints: u32,
+    /// Combines the constraints of the argument, see [Argument::combined_constraints]
+    combined_constraints: fn(&Alphas<F>, &mut expr::Cache) -> Expr<ConstantExpr<F>, Column>,
+}
+
+impl<F: PrimeField> ArgumentConstraints<F> {
+    fn new<A: Argument<F>>(name: &'static str, flag: Option<FeatureFlag>) -> Self {
+        ArgumentConstraints {
+            name,
+            flag,
+            argument_type: A::ARGUMENT_TYPE,
+            constraints: A::CONSTRAINTS,
+            combined_constraints: A::combined_constraints,
+        }
+    }
+
+    /// Whether the argument is enabled by `feature_flags`, or may be if they are `None`
+    fn is_enabled(&self, feature_flags: Option<&FeatureFlags>) -> bool {
+        match (&self.flag, feature_flags) {
+            (Some(flag), Some(feature_flags)) => flag.is_enabled_in(feature_flags),
+            _ => true,
+        }
+    }
+}
+
+/// The gates whose constraints are combined by [constraints_expr],
+/// in the order in which their constraints are added up.
 ///
-/// # Panics
+/// The Keccak gates are not part of this list: their constraints read the
+/// `KECCAK_COLS` witness columns of the zkVM layout, while only `COLUMNS` are evaluated here.
+fn gate_constraints<F: PrimeField + SquareRootField>() -> [ArgumentConstraints<F>; 12] {
+    [
+        ArgumentConstraints::new::<Poseidon<F>>("Poseidon", None),
+        ArgumentConstraints::new::<VarbaseMul<F>>("VarbaseMul", None),
+        ArgumentConstraints::new::<CompleteAdd<F>>("CompleteAdd", None),
+        ArgumentConstraints::new::<EndosclMul<F>>("EndosclMul", None),
+        ArgumentConstraints::new::<EndomulScalar<F>>("EndomulScalar", None),
+        ArgumentConstraints::new::<RangeCheck0<F>>("RangeCheck0", Some(FeatureFlag::RangeCheck0)),
+        ArgumentConstraints::new::<RangeCheck1<F>>("RangeCheck1", Some(FeatureFlag::RangeCheck1)),
+        ArgumentConstraints::new::<ForeignFieldAdd<F>>(
+            "ForeignFieldAdd",
+            Some(FeatureFlag::ForeignFieldAdd),
+        ),
+        ArgumentConstraints::new::<ForeignFieldMul<F>>(
+            "ForeignFieldMul",
+            Some(FeatureFlag::ForeignFieldMul),
+        ),
+        ArgumentConstraints::new::<xor::Xor16<F>>("Xor16", Some(FeatureFlag::Xor)),
+        ArgumentConstraints::new::<rot::Rot64<F>>("Rot64", Some(FeatureFlag::Rot)),
+        ArgumentConstraints::new::<generic::Generic<F>>("Generic", None),
+    ]
+}
+
+/// Gate types whose constraints are combined by [constraints_expr]
+fn constraints_expr_gates<F: PrimeField + SquareRootField>() -> HashSet<GateType> {
+    gate_constraints::<F>()
+        .into_iter()
+        .filter_map(|gate| match gate.argument_type {
+            ArgumentType::Gate(gate_type) => Some(gate_type),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the lookup constraints combined by [constraints_expr],
+/// or `None` if `feature_flags` do not enable any lookup pattern.
 ///
-/// Will panic if `generic_gate` is not associate with `alpha^0`.
-pub fn constraints_expr<F: PrimeField + SquareRootField>(
+/// If the `feature_flags` argument is `None`, the constraints are generated for all
+/// the lookup features, each of them gated by its own feature flag.
+fn lookup_constraints<F: PrimeField + SquareRootField>(
+    feature_flags: Option<&FeatureFlags>,
+) -> Option<Vec<Expr<ConstantExpr<F>, Column>>> {
+    let Some(feature_flags) = feature_flags else {
+        let all_features = FeatureFlags::all().lookup_features;
+        let lookup_configuration = LookupConfiguration::<F>::new(LookupInfo::create(all_features));
+        return Some(lookup::constraints::constraints(
+            &lookup_configuration,
+            true,
+        ));
+    };
+    if feature_flags.lookup_features.patterns == LookupPatterns::default() {
+        return None;
+    }
+
+    let lookup_configuration =
+        LookupConfiguration::<F>::new(LookupInfo::create(feature_flags.lookup_features));
+    let constraints = lookup::constraints::constraints(&lookup_configuration, false);
+
+    // The runtime table constraints come last, so that toggling them
+    // does not shift the powers of alpha of the other lookup constraints
+    if cfg!(debug_assertions) {
+        let uses_runtime_tables = feature_flags.lookup_features.uses_runtime_tables;
+        let toggled = LookupConfiguration::<F>::new(LookupInfo::create(
+            feature_flags
+                .lookup_features
+                .with_runtime_tables(!uses_runtime_tables),
+        ));
+        let toggled_len = lookup::constraints::constraints(&toggled, false).len();
+        let (with_runtime_tables, without_runtime_tables) = if uses_runtime_tables {
+            (constraints.len(), toggled_len)
+        } else {
+            (toggled_len, constraints.len())
+        };
+        assert_eq!(
+            with_runtime_tables,
+            without_runtime_tables + lookup::runtime_tables::constraints::<F>().len(),
+            "runtime tables shift the powers of alpha of the lookup constraints"
+        );
+    }
+
+    Some(constraints)
+}
+
+/// The combined constraints of an argument, see [combined_arguments]
+struct CombinedArgument<F: PrimeField> {
+    name: &'static str,
+    flag: Option<FeatureFlag>,
+    argument_type: ArgumentType,
+    constraints: u32,
+    expr: Expr<ConstantExpr<F>, Column>,
+}
+
+/// Combines the constraints of the given gate types and of the lookup argument,
+/// registering their powers of alpha as [constraints_expr] does.
+/// The arguments disabled by `feature_flags` are left out, and the generic gate is only
+/// combined if `generic` is set. If the `feature_flags` argument is `None`, every argument
+/// is combined, and the lookup constraints are generated for all the lookup features.
+fn combined_arguments<F: PrimeField + SquareRootField>(
+    gates: &HashSet<GateType>,
     feature_flags: Option<&FeatureFlags>,
     generic: bool,
-) -> (Expr<ConstantExpr<F>, Column>, Alphas<F>) {
+) -> (Vec<CombinedArgument<F>>, Alphas<F>) {
     // register powers of alpha so that we don't reuse them across mutually inclusive constraints
     let mut powers_of_alpha = Alphas::<F>::default();
 
@@ -52,185 +195,442 @@ pub fn constraints_expr<F: PrimeField + SquareRootField>(
 
     let mut cache = expr::Cache::default();
 
-    let mut expr = Poseidon::combined_constraints(&powers_of_alpha, &mut cache);
-    expr += VarbaseMul::combined_constraints(&powers_of_alpha, &mut cache);
-    expr += CompleteAdd::combined_constraints(&powers_of_alpha, &mut cache);
-    expr += EndosclMul::combined_constraints(&powers_of_alpha, &mut cache);
-    expr += EndomulScalar::combined_constraints(&powers_of_alpha, &mut cache);
+    let mut arguments: Vec<_> = gate_constraints::<F>()
+        .into_iter()
+        .filter(|gate| match gate.argument_type {
+            ArgumentType::Gate(GateType::Generic) => generic && gates.contains(&GateType::Generic),
+            ArgumentType::Gate(gate_type) => gates.contains(&gate_type),
+            _ => false,
+        })
+        .filter(|gate| gate.is_enabled(feature_flags))
+        .map(|gate| CombinedArgument {
+            name: gate.name,
+            flag: gate.flag,
+            argument_type: gate.argument_type,
+            constraints: gate.constraints,
+            expr: (gate.combined_constraints)(&powers_of_alpha, &mut cache),
+        })
+        .collect();
 
-    {
-        let mut range_check0_expr =
-            || RangeCheck0::combined_constraints(&powers_of_alpha, &mut cache);
+    // permutation
+    powers_of_alpha.register(ArgumentType::Permutation, permutation::CONSTRAINTS);
 
-        if let Some(feature_flags) = feature_flags {
-            if feature_flags.range_check0 {
-                expr += range_check0_expr();
-            }
-        } else {
-            expr += Expr::IfFeature(
-                FeatureFlag::RangeCheck0,
-                Box::new(range_check0_expr()),
-                Box::new(Expr::zero()),
-            );
-        }
+    // lookup
+    if let Some(constraints) = lookup_constraints::<F>(feature_flags) {
+        // note: the number of constraints depends on the lookup configuration,
+        // specifically the presence of runtime tables.
+        let constraints_len = u32::try_from(constraints.len())
+            .expect("we always expect a relatively low amount of constraints");
+
+        powers_of_alpha.register(ArgumentType::Lookup, constraints_len);
+
+        let alphas = powers_of_alpha.get_exponents(ArgumentType::Lookup, constraints_len);
+        arguments.push(CombinedArgument {
+            name: "Lookup",
+            flag: Some(FeatureFlag::LookupTables),
+            argument_type: ArgumentType::Lookup,
+            constraints: constraints_len,
+            expr: Expr::combine_constraints(alphas, constraints),
+        });
     }
 
-    {
-        let mut range_check1_expr =
-            || RangeCheck1::combined_constraints(&powers_of_alpha, &mut cache);
+    (arguments, powers_of_alpha)
+}
 
-        if let Some(feature_flags) = feature_flags {
-            if feature_flags.range_check1 {
-                expr += range_check1_expr();
-            }
-        } else {
-            expr += Expr::IfFeature(
-                FeatureFlag::RangeCheck1,
-                Box::new(range_check1_expr()),
-                Box::new(Expr::zero()),
-            );
-        }
+/// Represents an error found when building the expression of the constraints
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum LinearizationError {
+    #[error("The generic gate is combined with alpha^{0} instead of alpha^0, which breaks the addition of the public input")]
+    GenericGateNotAlphaZero(u32),
+    #[error("No power of alpha is registered for the generic gate")]
+    GenericGateNotRegistered,
+}
+
+/// Checks that the generic gate is associated with `alpha^0`,
+/// as required to later add the public input to its constraints.
+///
+/// # Errors
+///
+/// Will give error if the gates are not registered, or not registered first.
+pub fn assert_generic_alpha_zero<F: Field>(
+    powers_of_alpha: &Alphas<F>,
+) -> Result<(), LinearizationError> {
+    match powers_of_alpha.registered_exponents(ArgumentType::Gate(GateType::Generic)) {
+        Some(exponents) if exponents.start == 0 && !exponents.is_empty() => Ok(()),
+        Some(exponents) => Err(LinearizationError::GenericGateNotAlphaZero(exponents.start)),
+        None => Err(LinearizationError::GenericGateNotRegistered),
     }
+}
 
-    {
-        let mut foreign_field_add_expr =
-            || ForeignFieldAdd::combined_constraints(&powers_of_alpha, &mut cache);
-        if let Some(feature_flags) = feature_flags {
-            if feature_flags.foreign_field_add {
-                expr += foreign_field_add_expr();
-            }
-        } else {
-            expr += Expr::IfFeature(
-                FeatureFlag::ForeignFieldAdd,
-                Box::new(foreign_field_add_expr()),
-                Box::new(Expr::zero()),
-            );
-        }
+/// Get the expresion of constraints.
+///
+/// # Panics
+///
+/// Will panic if `generic_gate` is not associate with `alpha^0`.
+pub fn constraints_expr<F: PrimeField + SquareRootField>(
+    feature_flags: Option<&FeatureFlags>,
+    generic: bool,
+) -> (Expr<ConstantExpr<F>, Column>, Alphas<F>) {
+    constraints_expr_checked(feature_flags, generic).unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Get the expresion of constraints, as [constraints_expr].
+///
+/// # Errors
+///
+/// Will give error if `generic_gate` is not associate with `alpha^0`,
+/// see [assert_generic_alpha_zero].
+#[allow(clippy::type_complexity)]
+pub fn constraints_expr_checked<F: PrimeField + SquareRootField>(
+    feature_flags: Option<&FeatureFlags>,
+    generic: bool,
+) -> Result<(Expr<ConstantExpr<F>, Column>, Alphas<F>), LinearizationError> {
+    let (expr, powers_of_alpha) =
+        unchecked_constraints_expr(&constraints_expr_gates::<F>(), feature_flags, generic);
+    assert_generic_alpha_zero(&powers_of_alpha)?;
+    Ok((expr, powers_of_alpha))
+}
+
+/// Get the expression of the constraints of the given gate types only, as in [constraints_expr].
+/// The powers of alpha are registered as for all the gates, so that the constraints of a gate
+/// are combined with the same powers of alpha in both expressions.
+/// The generic gate is included if it is part of `gates` and `generic` is set.
+///
+/// # Panics
+///
+/// Will panic if `generic_gate` is not associate with `alpha^0`.
+pub fn constraints_expr_subset<F: PrimeField + SquareRootField>(
+    gates: &HashSet<GateType>,
+    feature_flags: Option<&FeatureFlags>,
+    generic: bool,
+) -> (Expr<ConstantExpr<F>, Column>, Alphas<F>) {
+    let (expr, powers_of_alpha) = unchecked_constraints_expr(gates, feature_flags, generic);
+    if let Err(err) = assert_generic_alpha_zero(&powers_of_alpha) {
+        panic!("{err}");
     }
+    (expr, powers_of_alpha)
+}
 
-    {
-        let mut foreign_field_mul_expr =
-            || ForeignFieldMul::combined_constraints(&powers_of_alpha, &mut cache);
-        if let Some(feature_flags) = feature_flags {
-            if feature_flags.foreign_field_mul {
-                expr += foreign_field_mul_expr();
+/// Combines the constraints of the given gate types, without checking the powers of alpha
+fn unchecked_constraints_expr<F: PrimeField + SquareRootField>(
+    gates: &HashSet<GateType>,
+    feature_flags: Option<&FeatureFlags>,
+    generic: bool,
+) -> (Expr<ConstantExpr<F>, Column>, Alphas<F>) {
+    let (arguments, powers_of_alpha) = combined_arguments::<F>(gates, feature_flags, generic);
+
+    // Without feature flags, the optional arguments are gated on their flag
+    let mut expr = Expr::zero();
+    for argument in arguments {
+        match (argument.flag, feature_flags) {
+            (Some(flag), None) => {
+                expr += Expr::IfFeature(flag, Box::new(argument.expr), Box::new(Expr::zero()));
             }
-        } else {
-            expr += Expr::IfFeature(
-                FeatureFlag::ForeignFieldMul,
-                Box::new(foreign_field_mul_expr()),
-                Box::new(Expr::zero()),
-            );
+            _ => expr += argument.expr,
         }
     }
 
-    {
-        let mut xor_expr = || xor::Xor16::combined_constraints(&powers_of_alpha, &mut cache);
+    // Check that the feature flags correctly turn on or off the constraints generated by the given
+    // flags.
+    if cfg!(feature = "check_feature_flags") {
         if let Some(feature_flags) = feature_flags {
-            if feature_flags.xor {
-                expr += xor_expr();
-            }
-        } else {
-            expr += Expr::IfFeature(
-                FeatureFlag::Xor,
-                Box::new(xor_expr()),
-                Box::new(Expr::zero()),
-            );
+            let (feature_flagged_expr, _) = constraints_expr_subset(gates, None, generic);
+            let feature_flagged_expr = feature_flagged_expr.apply_feature_flags(feature_flags);
+            assert_eq!(expr, feature_flagged_expr);
         }
     }
 
-    {
-        let mut rot_expr = || rot::Rot64::combined_constraints(&powers_of_alpha, &mut cache);
-        if let Some(feature_flags) = feature_flags {
-            if feature_flags.rot {
-                expr += rot_expr();
-            }
-        } else {
-            expr += Expr::IfFeature(
-                FeatureFlag::Rot,
-                Box::new(rot_expr()),
-                Box::new(Expr::zero()),
-            );
-        }
+    // return the expression
+    (expr, powers_of_alpha)
+}
+
+/// Get the constraints as a list of expressions, each of them tagged with the feature flag
+/// that gates it, or `None` if the expression is always enabled.
+///
+/// Unlike [constraints_expr] with no `feature_flags`, the expressions of the gadgets are
+/// not wrapped in `Expr::IfFeature`, so the list can be filtered directly by a set of
+/// flags. Note that the lookup expression may still contain nested `Expr::IfFeature`
+/// variants depending on the lookup configuration.
+///
+/// # Panics
+///
+/// Will panic if `generic_gate` is not associate with `alpha^0`.
+#[allow(clippy::type_complexity)]
+pub fn constraints_expr_by_feature_flag<F: PrimeField + SquareRootField>(
+    generic: bool,
+) -> (
+    Vec<(Option<FeatureFlag>, Expr<ConstantExpr<F>, Column>)>,
+    Alphas<F>,
+) {
+    let (arguments, powers_of_alpha) =
+        combined_arguments::<F>(&constraints_expr_gates::<F>(), None, generic);
+    let exprs = arguments
+        .into_iter()
+        .map(|argument| (argument.flag, argument.expr))
+        .collect();
+
+    // the generic gate must be associated with alpha^0
+    // to make the later addition with the public input work
+    if let Err(err) = assert_generic_alpha_zero(&powers_of_alpha) {
+        panic!("{err}");
     }
 
-    if generic {
-        expr += generic::Generic::combined_constraints(&powers_of_alpha, &mut cache);
+    (exprs, powers_of_alpha)
+}
+
+/// Returns the maximum degree of the constraints of [constraints_expr], see
+/// [Expr::constraint_degree], which bounds the size of the domain needed by the prover.
+pub fn max_degree<F: PrimeField + SquareRootField>(
+    feature_flags: Option<&FeatureFlags>,
+    generic: bool,
+) -> u64 {
+    let (expr, _) = constraints_expr::<F>(feature_flags, generic);
+    expr.constraint_degree()
+}
+
+/// Checks that the constraints of every argument of [constraints_expr] stay within a
+/// degree budget, see [Expr::constraint_degree]. There is no budget if `max_degree` is `None`.
+///
+/// # Errors
+///
+/// Will give error with the first argument type exceeding `max_degree`, along with its degree.
+pub fn check_degree_budget<F: PrimeField + SquareRootField>(
+    feature_flags: Option<&FeatureFlags>,
+    generic: bool,
+    max_degree: Option<usize>,
+) -> Result<(), (ArgumentType, u64)> {
+    let Some(max_degree) = max_degree else {
+        return Ok(());
+    };
+    let (arguments, _) =
+        combined_arguments::<F>(&constraints_expr_gates::<F>(), feature_flags, generic);
+    for argument in arguments {
+        let degree = argument.expr.constraint_degree();
+        if degree > max_degree as u64 {
+            return Err((argument.argument_type, degree));
+        }
     }
+    Ok(())
+}
+
+/// Returns the number of constraints of each argument, registered as in [constraints_expr].
+/// Gates are reported separately, even though they share the same powers of alpha.
+///
+/// If the `feature_flags` argument is `None`, every optional argument is reported,
+/// with the lookup constraints generated for all the lookup features.
+pub fn constraint_counts<F: PrimeField + SquareRootField>(
+    feature_flags: Option<&FeatureFlags>,
+) -> BTreeMap<ArgumentType, usize> {
+    let mut counts: BTreeMap<_, _> = gate_constraints::<F>()
+        .into_iter()
+        .filter(|gate| gate.is_enabled(feature_flags))
+        .map(|gate| (gate.argument_type, gate.constraints as usize))
+        .collect();
 
     // permutation
-    powers_of_alpha.register(ArgumentType::Permutation, permutation::CONSTRAINTS);
+    counts.insert(ArgumentType::Permutation, permutation::CONSTRAINTS as usize);
 
     // lookup
-    if let Some(feature_flags) = feature_flags {
-        if feature_flags.lookup_features.patterns != LookupPatterns::default() {
-            let lookup_configuration =
-                LookupConfiguration::new(LookupInfo::create(feature_flags.lookup_features));
-            let constraints = lookup::constraints::constraints(&lookup_configuration, false);
+    if let Some(constraints) = lookup_constraints::<F>(feature_flags) {
+        counts.insert(ArgumentType::Lookup, constraints.len());
+    }
 
-            // note: the number of constraints depends on the lookup configuration,
-            // specifically the presence of runtime tables.
-            let constraints_len = u32::try_from(constraints.len())
-                .expect("we always expect a relatively low amount of constraints");
+    counts
+}
 
-            powers_of_alpha.register(ArgumentType::Lookup, constraints_len);
+/// Describes the constraints of each argument registered as in [constraints_expr]:
+/// its powers of alpha, its degree and its combined constraint, see [Expr::text_str].
+/// Gates are described separately, even though they share the same powers of alpha.
+///
+/// If the `feature_flags` argument is `None`, every optional argument is described,
+/// along with the feature flag it depends on.
+pub fn describe_constraints<F: PrimeField + SquareRootField>(
+    feature_flags: Option<&FeatureFlags>,
+) -> String {
+    let (arguments, powers_of_alpha) =
+        combined_arguments::<F>(&constraints_expr_gates::<F>(), feature_flags, true);
 
-            let alphas = powers_of_alpha.get_exponents(ArgumentType::Lookup, constraints_len);
-            let combined = Expr::combine_constraints(alphas, constraints);
+    let alphas = |argument_type, constraints| {
+        let exponents: Vec<_> = powers_of_alpha
+            .get_exponents(argument_type, constraints)
+            .collect();
+        format!(
+            "alpha^{}..=alpha^{}",
+            exponents[0],
+            exponents[exponents.len() - 1]
+        )
+    };
 
-            expr += combined;
-        }
-    } else {
-        let all_features = LookupFeatures {
-            patterns: LookupPatterns {
-                xor: true,
-                lookup: true,
-                range_check: true,
-                foreign_field_mul: true,
-            },
-            uses_runtime_tables: true,
-            joint_lookup_used: true,
+    let mut description = String::new();
+    for argument in arguments {
+        let header = match (argument.flag, feature_flags) {
+            (Some(flag), None) => format!("{} (if {flag:?})", argument.name),
+            _ => argument.name.to_string(),
         };
-        let lookup_configuration = LookupConfiguration::new(LookupInfo::create(all_features));
-        let constraints = lookup::constraints::constraints(&lookup_configuration, true);
+        description.push_str(&format!(
+            "{header}: {} constraints, {}, degree {}\n  {}\n",
+            argument.constraints,
+            alphas(argument.argument_type, argument.constraints),
+            argument.expr.constraint_degree(),
+            argument.expr.text_str()
+        ));
+    }
+    description.push_str(&format!(
+        "Permutation: {} constraints, {}\n",
+        permutation::CONSTRAINTS,
+        alphas(ArgumentType::Permutation, permutation::CONSTRAINTS)
+    ));
 
-        // note: the number of constraints depends on the lookup configuration,
-        // specifically the presence of runtime tables.
-        let constraints_len = u32::try_from(constraints.len())
-            .expect("we always expect a relatively low amount of constraints");
+    description
+}
 
-        powers_of_alpha.register(ArgumentType::Lookup, constraints_len);
+/// Curves for which kimchi constraint systems are built, see [constraint_system_stats]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurveName {
+    Pallas,
+    Vesta,
+}
 
-        let alphas = powers_of_alpha.get_exponents(ArgumentType::Lookup, constraints_len);
-        let combined = Expr::IfFeature(
-            FeatureFlag::LookupTables,
-            Box::new(Expr::combine_constraints(alphas, constraints)),
-            Box::new(Expr::zero()),
-        );
+/// Size of the constraints of a constraint system, see [constraint_system_stats]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConstraintSystemStats {
+    /// Number of powers of alpha combining the constraints
+    pub constraints: u32,
+    /// Number of nodes of the combined constraint expression
+    pub nodes: usize,
+    /// Degree of the combined constraint expression, see [Expr::constraint_degree]
+    pub max_degree: u64,
+    /// Number of columns evaluated as part of the proof
+    pub evaluated_columns: usize,
+}
 
-        expr += combined;
+impl ConstraintSystemStats {
+    /// Computes the statistics of the constraints over the field `F` with the given feature flags
+    pub fn new<F: PrimeField + SquareRootField>(
+        feature_flags: &FeatureFlags,
+        generic: bool,
+    ) -> ConstraintSystemStats {
+        let (expr, powers_of_alpha) = constraints_expr::<F>(Some(feature_flags), generic);
+        ConstraintSystemStats {
+            constraints: powers_of_alpha.num_powers(),
+            nodes: expr.num_nodes(),
+            max_degree: expr.constraint_degree(),
+            evaluated_columns: linearization_columns::<F>(Some(feature_flags)).len(),
+        }
     }
+}
 
-    // the generic gate must be associated with alpha^0
-    // to make the later addition with the public input work
-    if cfg!(debug_assertions) {
-        let mut generic_alphas =
-            powers_of_alpha.get_exponents(ArgumentType::Gate(GateType::Generic), 1);
-        assert_eq!(generic_alphas.next(), Some(0));
+/// Reports the size of the kimchi constraint system with all the features enabled,
+/// for circuits over the scalar field of each of the Pasta curves.
+pub fn constraint_system_stats() -> Vec<(CurveName, ConstraintSystemStats)> {
+    let feature_flags = FeatureFlags::all();
+    vec![
+        (
+            CurveName::Pallas,
+            ConstraintSystemStats::new::<mina_curves::pasta::Fq>(&feature_flags, true),
+        ),
+        (
+            CurveName::Vesta,
+            ConstraintSystemStats::new::<mina_curves::pasta::Fp>(&feature_flags, true),
+        ),
+    ]
+}
+
+/// Evaluations of the columns of a concrete circuit at a single row, where the only
+/// selector switched on is the one of `selected`, see [evaluate_constraints_at_row]
+struct RowEvaluations<'a, F: PrimeField> {
+    gates: &'a [CircuitGate<F>],
+    witness: &'a [Vec<F>; COLUMNS],
+    row: usize,
+    selected: GateType,
+}
+
+impl<'a, F: PrimeField> RowEvaluations<'a, F> {
+    fn next_row(&self) -> usize {
+        (self.row + 1) % self.witness[0].len()
     }
+}
 
-    // Check that the feature flags correctly turn on or off the constraints generated by the given
-    // flags.
-    if cfg!(feature = "check_feature_flags") {
-        if let Some(feature_flags) = feature_flags {
-            let (feature_flagged_expr, _) = constraints_expr(None, generic);
-            let feature_flagged_expr = feature_flagged_expr.apply_feature_flags(feature_flags);
-            assert_eq!(expr, feature_flagged_expr);
+impl<'a, F: PrimeField> ColumnEvaluations<F> for RowEvaluations<'a, F> {
+    type Column = Column;
+
+    fn evaluate(&self, col: Column) -> Result<PointEvaluations<F>, ExprError<Column>> {
+        let next_row = self.next_row();
+        match col {
+            Column::Witness(i) => Ok(PointEvaluations {
+                zeta: self.witness[i][self.row],
+                zeta_omega: self.witness[i][next_row],
+            }),
+            Column::Coefficient(i) => {
+                let coeff = |row: usize| {
+                    self.gates
+                        .get(row)
+                        .and_then(|gate| gate.coeffs.get(i).copied())
+                        .unwrap_or_else(F::zero)
+                };
+                Ok(PointEvaluations {
+                    zeta: coeff(self.row),
+                    zeta_omega: coeff(next_row),
+                })
+            }
+            Column::Index(typ) => {
+                let selector = |row: usize| match self.gates.get(row) {
+                    Some(gate) if gate.typ == typ && typ == self.selected => F::one(),
+                    _ => F::zero(),
+                };
+                Ok(PointEvaluations {
+                    zeta: selector(self.row),
+                    zeta_omega: selector(next_row),
+                })
+            }
+            _ => Err(ExprError::MissingIndexEvaluation(col)),
         }
     }
+}
 
-    // return the expression
-    (expr, powers_of_alpha)
+/// Evaluates the combined constraint expression `expr` (e.g. the output of
+/// [constraints_expr]) at the given row of a concrete circuit and witness,
+/// and breaks the result down by gate: the entry of each gate type of the circuit
+/// is the value of `expr` when only the selector of that gate type is switched on.
+/// For a satisfied witness all the entries are zero, so a nonzero entry pinpoints
+/// the gate whose constraints fail at `row`.
+///
+/// # Errors
+///
+/// Will give error if `expr` reads a column other than the witness, the coefficients
+/// or the gate selectors (e.g. if it contains the lookup argument).
+///
+/// # Panics
+///
+/// Will panic if the witness is empty or too large for an evaluation domain.
+pub fn evaluate_constraints_at_row<F: PrimeField>(
+    expr: &Expr<ConstantExpr<F>, Column>,
+    gates: &[CircuitGate<F>],
+    witness: &[Vec<F>; COLUMNS],
+    row: usize,
+    constants: &Constants<F>,
+    challenges: &Challenges<F>,
+) -> Result<BTreeMap<ArgumentType, F>, ExprError<Column>> {
+    let domain = D::<F>::new(witness[0].len()).expect("witness too large for a domain");
+    let pt = domain.element(row);
+
+    let mut contributions = BTreeMap::new();
+    for gate in gates {
+        let argument_type = ArgumentType::Gate(gate.typ);
+        if contributions.contains_key(&argument_type) {
+            continue;
+        }
+        let evals = RowEvaluations {
+            gates,
+            witness,
+            row,
+            selected: gate.typ,
+        };
+        let value = expr.evaluate_(domain, pt, &evals, constants, challenges)?;
+        contributions.insert(argument_type, value);
+    }
+
+    Ok(contributions)
 }
 
 /// Adds the polynomials that are evaluated as part of the proof
@@ -243,28 +643,8 @@ pub fn linearization_columns<F: FftField + SquareRootField>(
 
     let feature_flags = match feature_flags {
         Some(feature_flags) => *feature_flags,
-        None =>
         // Generating using `IfFeature`, turn on all feature flags.
-        {
-            FeatureFlags {
-                range_check0: true,
-                range_check1: true,
-                foreign_field_add: true,
-                foreign_field_mul: true,
-                xor: true,
-                rot: true,
-                lookup_features: LookupFeatures {
-                    patterns: LookupPatterns {
-                        xor: true,
-                        lookup: true,
-                        range_check: true,
-                        foreign_field_mul: true,
-                    },
-                    joint_lookup_used: true,
-                    uses_runtime_tables: true,
-                },
-            }
-        }
+        None => FeatureFlags::all(),
     };
 
     // the witness polynomials
@@ -329,11 +709,145 @@ pub fn linearization_columns<F: FftField + SquareRootField>(
     h
 }
 
+/// Replaces the field elements of a [PolishToken] using `f`, see [write_linearization]
+fn map_literals<F, G, E, Column: Copy>(
+    token: &PolishToken<F, Column>,
+    f: &impl Fn(&F) -> Result<G, E>,
+) -> Result<PolishToken<G, Column>, E> {
+    use PolishToken::*;
+    Ok(match token {
+        Constant(ConstantTerm::Literal(x)) => Constant(ConstantTerm::Literal(f(x)?)),
+        Constant(ConstantTerm::EndoCoefficient) => Constant(ConstantTerm::EndoCoefficient),
+        Constant(ConstantTerm::Mds { row, col }) => Constant(ConstantTerm::Mds {
+            row: *row,
+            col: *col,
+        }),
+        Challenge(challenge) => Challenge(*challenge),
+        Cell(variable) => Cell(*variable),
+        Dup => Dup,
+        Pow(n) => Pow(*n),
+        Add => Add,
+        Mul => Mul,
+        Sub => Sub,
+        VanishesOnZeroKnowledgeAndPreviousRows => VanishesOnZeroKnowledgeAndPreviousRows,
+        UnnormalizedLagrangeBasis(offset) => UnnormalizedLagrangeBasis(*offset),
+        Store => Store,
+        Load(i) => Load(*i),
+        SkipIf(flag, n) => SkipIf(*flag, *n),
+        SkipIfNot(flag, n) => SkipIfNot(*flag, *n),
+    })
+}
+
+/// Replaces the field elements of every token of a linearization using `f`
+#[allow(clippy::type_complexity)]
+fn map_linearization_literals<F, G, E>(
+    linearization: &Linearization<Vec<PolishToken<F, Column>>, Column>,
+    f: impl Fn(&F) -> Result<G, E>,
+) -> Result<Linearization<Vec<PolishToken<G, Column>>, Column>, E> {
+    let map_tokens = |tokens: &Vec<PolishToken<F, Column>>| {
+        tokens
+            .iter()
+            .map(|token| map_literals(token, &f))
+            .collect::<Result<Vec<_>, _>>()
+    };
+    Ok(Linearization {
+        constant_term: map_tokens(&linearization.constant_term)?,
+        index_terms: linearization
+            .index_terms
+            .iter()
+            .map(|(col, tokens)| Ok((*col, map_tokens(tokens)?)))
+            .collect::<Result<Vec<_>, E>>()?,
+    })
+}
+
+/// Writes a linearization, e.g. computed by [expr_linearization], so that it can be loaded
+/// with [read_linearization] instead of being computed again.
+/// The field elements are encoded with `ark_serialize`, and the tokens with `rmp_serde`.
+///
+/// # Errors
+///
+/// Will give error if a field element cannot be encoded, or if writing fails.
+pub fn write_linearization<F: PrimeField>(
+    linearization: &Linearization<Vec<PolishToken<F, Column>>, Column>,
+    mut writer: impl Write,
+) -> Result<(), EncodingError> {
+    let encoded = map_linearization_literals(linearization, |x| {
+        let mut bytes = vec![];
+        x.serialize(&mut bytes)?;
+        Ok::<_, EncodingError>(bytes)
+    })?;
+    Ok(rmp_serde::encode::write(&mut writer, &encoded)?)
+}
+
+/// Reads a linearization written by [write_linearization]
+///
+/// # Errors
+///
+/// Will give error if reading fails, or if the content is not a linearization over `F`.
+#[allow(clippy::type_complexity)]
+pub fn read_linearization<F: PrimeField>(
+    reader: impl Read,
+) -> Result<Linearization<Vec<PolishToken<F, Column>>, Column>, EncodingError> {
+    let encoded: Linearization<Vec<PolishToken<Vec<u8>, Column>>, Column> =
+        rmp_serde::from_read(reader)?;
+    map_linearization_literals(&encoded, |bytes| Ok(F::deserialize(&bytes[..])?))
+}
+
+/// Key of [LINEARIZATION_CACHE], one per field and set of arguments of [expr_linearization]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct LinearizationKey {
+    /// The field the linearization is over
+    field: TypeId,
+    /// The feature flags passed to [expr_linearization]
+    feature_flags: Option<FeatureFlags>,
+    /// Whether the generic gate is included
+    generic: bool,
+}
+
+impl LinearizationKey {
+    fn new<F: 'static>(feature_flags: Option<&FeatureFlags>, generic: bool) -> Self {
+        LinearizationKey {
+            field: TypeId::of::<F>(),
+            feature_flags: feature_flags.copied(),
+            generic,
+        }
+    }
+}
+
+/// Linearization over `F` and its powers of alpha, filled once by [expr_linearization]
+#[allow(clippy::type_complexity)]
+type LinearizationEntry<F> = OnceCell<(
+    Linearization<Vec<PolishToken<F, Column>>, Column>,
+    Alphas<F>,
+)>;
+
+/// Linearizations computed by [expr_linearization], where each entry is a [LinearizationEntry]
+/// over the field of its key
+static LINEARIZATION_CACHE: Lazy<Mutex<HashMap<LinearizationKey, Arc<dyn Any + Send + Sync>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Returns the entry of [LINEARIZATION_CACHE] for `key`, inserting an empty one if needed
+fn linearization_entry<F: PrimeField>(key: LinearizationKey) -> Arc<LinearizationEntry<F>> {
+    debug_assert_eq!(key.field, TypeId::of::<F>());
+    // The lock is only held to access the map, which is thus consistent even if poisoned
+    let entry = LINEARIZATION_CACHE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(key)
+        .or_insert_with(|| Arc::new(LinearizationEntry::<F>::new()))
+        .clone();
+    entry
+        .downcast()
+        .expect("linearization cache entries are over the field of their key")
+}
+
 /// Linearize the `expr`.
 ///
 /// If the `feature_flags` argument is `None`, this will generate an expression using the
 /// `Expr::IfFeature` variant for each of the flags.
 ///
+/// The result is computed once per field and set of arguments, and then cloned from a cache.
+///
 /// # Panics
 ///
 /// Will panic if the `linearization` process fails.
@@ -344,6 +858,20 @@ pub fn expr_linearization<F: PrimeField + SquareRootField>(
 ) -> (
     Linearization<Vec<PolishToken<F, Column>>, Column>,
     Alphas<F>,
+) {
+    // Concurrent callers with the same key wait for the first one to linearize
+    linearization_entry::<F>(LinearizationKey::new::<F>(feature_flags, generic))
+        .get_or_init(|| compute_expr_linearization(feature_flags, generic))
+        .clone()
+}
+
+#[allow(clippy::type_complexity)]
+fn compute_expr_linearization<F: PrimeField + SquareRootField>(
+    feature_flags: Option<&FeatureFlags>,
+    generic: bool,
+) -> (
+    Linearization<Vec<PolishToken<F, Column>>, Column>,
+    Alphas<F>,
 ) {
     let evaluated_cols = linearization_columns::<F>(feature_flags);
 
@@ -358,3 +886,423 @@ pub fn expr_linearization<F: PrimeField + SquareRootField>(
 
     (linearization, powers_of_alpha)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::One;
+    use mina_curves::pasta::Fp;
+
+    #[test]
+    fn test_constraints_expr_by_feature_flag() {
+        let feature_flags = FeatureFlags::all();
+        let (exprs, _) = constraints_expr_by_feature_flag::<Fp>(true);
+        let folded = exprs
+            .into_iter()
+            .filter(|(flag, _)| match flag {
+                Some(flag) => flag.is_enabled_in(&feature_flags),
+                None => true,
+            })
+            .fold(Expr::zero(), |mut acc, (_, expr)| {
+                acc += expr;
+                acc
+            })
+            .apply_feature_flags(&feature_flags);
+
+        let (expr, _) = constraints_expr::<Fp>(Some(&feature_flags), true);
+        assert_eq!(folded, expr);
+    }
+
+    #[test]
+    fn test_referenced_feature_flags() {
+        let (expr, _) = constraints_expr::<Fp>(None, true);
+        let flags = expr.referenced_feature_flags();
+
+        // All the optional gadgets are gated on their flag
+        for flag in [
+            FeatureFlag::RangeCheck0,
+            FeatureFlag::RangeCheck1,
+            FeatureFlag::ForeignFieldAdd,
+            FeatureFlag::ForeignFieldMul,
+            FeatureFlag::Xor,
+            FeatureFlag::Rot,
+            FeatureFlag::LookupTables,
+            FeatureFlag::RuntimeLookupTables,
+        ] {
+            assert!(flags.contains(&flag), "missing {flag:?}");
+        }
+        // The remaining flags can only come from the lookup argument
+        for flag in flags.iter() {
+            assert!(
+                matches!(
+                    flag,
+                    FeatureFlag::RangeCheck0
+                        | FeatureFlag::RangeCheck1
+                        | FeatureFlag::ForeignFieldAdd
+                        | FeatureFlag::ForeignFieldMul
+                        | FeatureFlag::Xor
+                        | FeatureFlag::Rot
+                        | FeatureFlag::LookupTables
+                        | FeatureFlag::RuntimeLookupTables
+                        | FeatureFlag::LookupPattern(_)
+                        | FeatureFlag::TableWidth(_)
+                        | FeatureFlag::LookupsPerRow(_)
+                ),
+                "unexpected {flag:?}"
+            );
+        }
+
+        // Once specialized, no flag is referenced anymore
+        let (specialized, _) = constraints_expr::<Fp>(Some(&FeatureFlags::all()), true);
+        assert!(specialized.referenced_feature_flags().is_empty());
+    }
+
+    #[test]
+    fn test_max_degree() {
+        // The core gates are bounded by the Poseidon s-box x^7 times its selector,
+        // hence the quotient being computed over the domain d8
+        assert_eq!(max_degree::<Fp>(Some(&FeatureFlags::none()), true), 8);
+
+        let all = FeatureFlags::all();
+        let (expr, _) = constraints_expr::<Fp>(Some(&all), true);
+        assert_eq!(max_degree::<Fp>(Some(&all), true), expr.constraint_degree());
+    }
+
+    #[test]
+    fn test_check_degree_budget() {
+        // The whole expression is as large as its largest argument
+        let (expr, _) = constraints_expr::<Fp>(None, true);
+        let max_degree = expr.constraint_degree();
+        check_degree_budget::<Fp>(None, true, Some(max_degree as usize)).unwrap();
+        let (_, degree) =
+            check_degree_budget::<Fp>(None, true, Some(max_degree as usize - 1)).unwrap_err();
+        assert_eq!(degree, max_degree);
+
+        // Without a budget, any degree is accepted
+        check_degree_budget::<Fp>(None, true, None).unwrap();
+
+        // Poseidon is checked first and its s-box alone exceeds a low budget
+        let (argument_type, degree) = check_degree_budget::<Fp>(None, true, Some(3)).unwrap_err();
+        assert_eq!(argument_type, ArgumentType::Gate(GateType::Poseidon));
+        assert!(degree > 3);
+    }
+
+    #[test]
+    fn test_constraint_system_stats() {
+        let stats = constraint_system_stats();
+        assert_eq!(stats.len(), 2);
+        let (pallas, pallas_stats) = stats[0];
+        let (vesta, vesta_stats) = stats[1];
+        assert_eq!(pallas, CurveName::Pallas);
+        assert_eq!(vesta, CurveName::Vesta);
+
+        // Both curves share the same constraints
+        assert_eq!(pallas_stats, vesta_stats);
+
+        // The generic gate uses alpha^0 and VarbaseMul has the most gate constraints
+        assert!(
+            pallas_stats.constraints >= VarbaseMul::<Fp>::CONSTRAINTS + permutation::CONSTRAINTS
+        );
+        assert!(pallas_stats.nodes > 0);
+        assert!((7..=16).contains(&pallas_stats.max_degree));
+        assert!(pallas_stats.evaluated_columns >= COLUMNS);
+    }
+
+    #[test]
+    fn test_evaluate_constraints_at_row() {
+        use crate::circuits::{polynomials::generic::GenericGateSpec, wires::Wire};
+        use crate::curve::KimchiCurve;
+        use mina_curves::pasta::Vesta;
+
+        // A 16-bit xor (one Xor16 row and a generic row constraining its output to zero)
+        // followed by a generic multiplication
+        let (_, mut gates) = CircuitGate::<Fp>::create_xor_gadget(0, 16);
+        gates.push(CircuitGate::create_generic_gadget(
+            Wire::for_row(gates.len()),
+            GenericGateSpec::Mul {
+                output_coeff: None,
+                mul_coeff: None,
+            },
+            None,
+        ));
+        let mut witness = xor::create_xor_witness(Fp::from(0x1234u64), Fp::from(0xabcdu64), 16);
+        for (col, value) in [3u64, 4, 12].into_iter().enumerate() {
+            witness[col].push(Fp::from(value));
+        }
+        for col in witness.iter_mut().skip(3) {
+            col.push(Fp::zero());
+        }
+
+        // Only the xor gate, no lookups
+        let feature_flags = FeatureFlags::builder().xor(true).build();
+        let (expr, _) = constraints_expr::<Fp>(Some(&feature_flags), true);
+        let constants = Constants {
+            endo_coefficient: Fp::one(),
+            mds: &Vesta::sponge_params().mds,
+            zk_rows: 3,
+        };
+        let challenges = Challenges {
+            alpha: Fp::from(7u64),
+            beta: Fp::one(),
+            gamma: Fp::one(),
+            joint_combiner: None,
+        };
+        let evaluate = |witness: &[Vec<Fp>; COLUMNS], row| {
+            evaluate_constraints_at_row(&expr, &gates, witness, row, &constants, &challenges)
+                .unwrap()
+        };
+
+        // A valid witness satisfies every gate at every row
+        for row in 0..gates.len() {
+            let contributions = evaluate(&witness, row);
+            assert_eq!(contributions.len(), 2);
+            assert!(contributions.values().all(|value| value.is_zero()));
+        }
+
+        // Break the output of the xor
+        witness[2][0] += Fp::one();
+        let contributions = evaluate(&witness, 0);
+        assert!(!contributions[&ArgumentType::Gate(GateType::Xor16)].is_zero());
+        assert!(contributions[&ArgumentType::Gate(GateType::Generic)].is_zero());
+
+        // The other rows are unaffected
+        for row in 1..gates.len() {
+            assert!(evaluate(&witness, row)
+                .values()
+                .all(|value| value.is_zero()));
+        }
+    }
+
+    #[test]
+    fn test_expr_linearization_cache() {
+        let feature_flags = FeatureFlags::builder().rot(true).build();
+        let key = LinearizationKey::new::<Fp>(Some(&feature_flags), true);
+
+        // The first call fills the entry of its key
+        let (first, first_alphas) = expr_linearization::<Fp>(Some(&feature_flags), true);
+        let entry = linearization_entry::<Fp>(key);
+        assert!(entry.get().is_some());
+
+        // The second call is served from that same entry
+        let (second, second_alphas) = expr_linearization::<Fp>(Some(&feature_flags), true);
+        assert!(Arc::ptr_eq(&entry, &linearization_entry::<Fp>(key)));
+        assert_eq!(first.constant_term, second.constant_term);
+        assert_eq!(first.index_terms, second.index_terms);
+        assert_eq!(first_alphas.num_powers(), second_alphas.num_powers());
+
+        // And it matches a fresh linearization
+        let (fresh, fresh_alphas) = compute_expr_linearization::<Fp>(Some(&feature_flags), true);
+        assert_eq!(fresh.constant_term, second.constant_term);
+        assert_eq!(fresh.index_terms, second.index_terms);
+        assert_eq!(fresh_alphas.num_powers(), second_alphas.num_powers());
+    }
+
+    #[test]
+    fn test_feature_flags_all_and_none() {
+        let (exprs, _) = constraints_expr_by_feature_flag::<Fp>(true);
+        let fold = |feature_flags: &FeatureFlags, gated: bool| {
+            exprs
+                .iter()
+                .filter(|(flag, _)| match flag {
+                    Some(flag) => gated && flag.is_enabled_in(feature_flags),
+                    None => true,
+                })
+                .fold(Expr::zero(), |mut acc, (_, expr)| {
+                    acc += expr.clone();
+                    acc
+                })
+                .apply_feature_flags(feature_flags)
+        };
+
+        // Every gated constraint is part of the expression of `all()`
+        let all = FeatureFlags::all();
+        let (expr, _) = constraints_expr::<Fp>(Some(&all), true);
+        assert_eq!(expr, fold(&all, true));
+        for flag in exprs.iter().filter_map(|(flag, _)| flag.as_ref()) {
+            assert!(flag.is_enabled_in(&all), "{flag:?} is not enabled");
+        }
+
+        // Only the constraints that are always on are part of the expression of `none()`
+        let none = FeatureFlags::none();
+        let (expr, _) = constraints_expr::<Fp>(Some(&none), true);
+        assert_eq!(expr, fold(&none, false));
+        assert_eq!(FeatureFlags::builder().build(), none);
+        assert_eq!(
+            FeatureFlags::builder()
+                .range_check(true)
+                .foreign_field_add(true)
+                .foreign_field_mul(true)
+                .xor(true)
+                .rot(true)
+                .lookup_pattern(LookupPattern::Xor, true)
+                .lookup_pattern(LookupPattern::Lookup, true)
+                .lookup_pattern(LookupPattern::RangeCheck, true)
+                .lookup_pattern(LookupPattern::ForeignFieldMul, true)
+                .joint_lookup_used(true)
+                .uses_runtime_tables(true)
+                .build(),
+            all
+        );
+    }
+
+    #[test]
+    fn test_constraint_counts() {
+        let counts = constraint_counts::<Fp>(None);
+        assert_eq!(
+            counts[&ArgumentType::Gate(GateType::Generic)],
+            generic::CONSTRAINTS as usize
+        );
+        assert_eq!(
+            counts[&ArgumentType::Permutation],
+            permutation::CONSTRAINTS as usize
+        );
+        assert_eq!(
+            counts[&ArgumentType::Gate(GateType::VarBaseMul)],
+            VarbaseMul::<Fp>::CONSTRAINTS as usize
+        );
+        assert!(counts.contains_key(&ArgumentType::Gate(GateType::Rot64)));
+
+        // Gates share their powers of alpha, the permutation and lookup arguments do not
+        let all = FeatureFlags::all();
+        let counts = constraint_counts::<Fp>(Some(&all));
+        let (_, powers_of_alpha) = constraints_expr::<Fp>(Some(&all), true);
+        let max_gate = counts
+            .iter()
+            .filter(|(argument_type, _)| matches!(argument_type, ArgumentType::Gate(_)))
+            .map(|(_, count)| *count)
+            .max()
+            .unwrap();
+        assert_eq!(
+            powers_of_alpha.num_powers() as usize,
+            max_gate + counts[&ArgumentType::Permutation] + counts[&ArgumentType::Lookup]
+        );
+
+        // Without features, neither the optional gates nor the lookup argument are counted
+        let counts = constraint_counts::<Fp>(Some(&FeatureFlags::none()));
+        assert!(!counts.contains_key(&ArgumentType::Gate(GateType::Xor16)));
+        assert!(!counts.contains_key(&ArgumentType::Lookup));
+        assert_eq!(
+            counts[&ArgumentType::Gate(GateType::Generic)],
+            generic::CONSTRAINTS as usize
+        );
+    }
+
+    #[test]
+    fn test_linearization_roundtrip() {
+        // Without feature flags, the linearization also contains the tokens skipping features
+        let (linearization, _) = expr_linearization::<Fp>(None, true);
+        let mut bytes = vec![];
+        write_linearization(&linearization, &mut bytes).unwrap();
+
+        let read = read_linearization::<Fp>(&bytes[..]).unwrap();
+        assert_eq!(read.constant_term, linearization.constant_term);
+        assert_eq!(read.index_terms, linearization.index_terms);
+
+        // A truncated file is rejected
+        assert!(matches!(
+            read_linearization::<Fp>(&bytes[..bytes.len() / 2]),
+            Err(EncodingError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn test_describe_constraints() {
+        let description = describe_constraints::<Fp>(Some(&FeatureFlags::none()));
+        assert!(description.contains("Poseidon: 15 constraints, alpha^0..=alpha^14"));
+        assert!(description.contains("VarbaseMul: 21 constraints, alpha^0..=alpha^20"));
+        assert!(description.contains("Permutation: 3 constraints, alpha^21..=alpha^23"));
+        assert!(!description.contains("Xor"));
+        assert!(!description.contains("Lookup"));
+
+        let description =
+            describe_constraints::<Fp>(Some(&FeatureFlags::builder().xor(true).build()));
+        assert!(description.contains("Xor16: 3 constraints, alpha^0..=alpha^2"));
+
+        // Without feature flags, every argument is described along with its flag
+        let description = describe_constraints::<Fp>(None);
+        assert!(description.contains("Xor16 (if Xor)"));
+        assert!(description.contains("Lookup (if LookupTables)"));
+    }
+
+    #[test]
+    fn test_constraints_expr_subset() {
+        let feature_flags = FeatureFlags::none();
+        let (full, full_alphas) = constraints_expr::<Fp>(Some(&feature_flags), true);
+
+        // Only the generic gate, with the same powers of alpha as the full expression
+        let gates = HashSet::from([GateType::Generic]);
+        let (subset, subset_alphas) =
+            constraints_expr_subset::<Fp>(&gates, Some(&feature_flags), true);
+        assert_eq!(subset_alphas.num_powers(), full_alphas.num_powers());
+        assert!(subset.num_nodes() * 10 < full.num_nodes());
+        assert_eq!(
+            subset,
+            generic::Generic::combined_constraints(&full_alphas, &mut expr::Cache::default())
+        );
+
+        // Every gate is the full expression
+        let gates = constraints_expr_gates::<Fp>();
+        let (subset, _) = constraints_expr_subset::<Fp>(&gates, None, true);
+        assert_eq!(subset, constraints_expr::<Fp>(None, true).0);
+    }
+
+    #[test]
+    fn test_lookup_constraints_with_runtime_tables() {
+        let runtime_constraints = lookup::runtime_tables::constraints::<Fp>().len();
+        let feature_flags = FeatureFlags::builder()
+            .xor(true)
+            .lookup_pattern(LookupPattern::Xor, true)
+            .build();
+        let lookup_features = feature_flags.lookup_features.with_runtime_tables(true);
+        assert!(lookup_features.uses_runtime_tables);
+        assert_eq!(
+            lookup_features.patterns,
+            feature_flags.lookup_features.patterns
+        );
+        let with_runtime_tables = FeatureFlags {
+            lookup_features,
+            ..feature_flags
+        };
+
+        // Runtime tables only add their own constraints to the lookup argument
+        let without = constraint_counts::<Fp>(Some(&feature_flags))[&ArgumentType::Lookup];
+        let with = constraint_counts::<Fp>(Some(&with_runtime_tables))[&ArgumentType::Lookup];
+        assert_eq!(with, without + runtime_constraints);
+
+        // and the lookup constraints are registered last
+        let (_, alphas_without) = constraints_expr::<Fp>(Some(&feature_flags), true);
+        let (_, alphas_with) = constraints_expr::<Fp>(Some(&with_runtime_tables), true);
+        assert_eq!(
+            alphas_with.num_powers(),
+            alphas_without.num_powers() + runtime_constraints as u32
+        );
+    }
+
+    #[test]
+    fn test_assert_generic_alpha_zero() {
+        let (_, powers_of_alpha) = constraints_expr_checked::<Fp>(None, true).unwrap();
+        assert_eq!(assert_generic_alpha_zero(&powers_of_alpha), Ok(()));
+
+        // Registering another argument before the gates shifts their powers of alpha
+        let mut powers_of_alpha = Alphas::<Fp>::default();
+        powers_of_alpha.register(ArgumentType::Permutation, permutation::CONSTRAINTS);
+        powers_of_alpha.register(
+            ArgumentType::Gate(GateType::Zero),
+            VarbaseMul::<Fp>::CONSTRAINTS,
+        );
+        assert_eq!(
+            assert_generic_alpha_zero(&powers_of_alpha),
+            Err(LinearizationError::GenericGateNotAlphaZero(
+                permutation::CONSTRAINTS
+            ))
+        );
+
+        // And forgetting to register them is detected as well
+        let mut powers_of_alpha = Alphas::<Fp>::default();
+        powers_of_alpha.register(ArgumentType::Permutation, permutation::CONSTRAINTS);
+        assert_eq!(
+            assert_generic_alpha_zero(&powers_of_alpha),
+            Err(LinearizationError::GenericGateNotRegistered)
+        );
+    }
+}
@@ -38,6 +38,7 @@ use ark_poly::{
     univariate::DensePolynomial, EvaluationDomain, Evaluations, Polynomial,
     Radix2EvaluationDomain as D, UVPolynomial,
 };
+use ark_std::cfg_iter_mut;
 use itertools::Itertools;
 use mina_poseidon::{sponge::ScalarChallenge, FqSponge};
 use o1_utils::ExtendedDensePolynomial as _;
@@ -48,6 +49,7 @@ use poly_commitment::{
     evaluation_proof::DensePolynomialOrEvaluations,
     OpenProof, SRS as _,
 };
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use std::array;
 use std::collections::HashMap;
@@ -400,9 +402,7 @@ where
 
                 // pre-compute the updated second column of the lookup table
                 let mut second_column_d8 = runtime_table_contribution_d8.clone();
-                second_column_d8
-                    .evals
-                    .par_iter_mut()
+                cfg_iter_mut!(second_column_d8.evals)
                     .enumerate()
                     .for_each(|(row, e)| {
                         *e += lcs.lookup_table8[1][row];
@@ -832,7 +832,7 @@ where
                         constraints.into_iter().zip_eq(lookup_alphas).enumerate()
                     {
                         let mut eval = constraint.evaluations(&env);
-                        eval.evals.par_iter_mut().for_each(|x| *x *= alpha_pow);
+                        cfg_iter_mut!(eval.evals).for_each(|x| *x *= alpha_pow);
 
                         if eval.domain().size == t4.domain().size {
                             t4 += &eval;
@@ -173,6 +173,20 @@ fn test_rot_random() {
     test_rot::<Pallas>(word, rot, RotMode::Right);
 }
 
+#[test]
+// Test the witness of several words rotated by the smallest, a middle and the largest offsets
+fn test_rot_offsets() {
+    let rng = &mut StdRng::from_seed(RNG_SEED);
+    let mut words = vec![0, 1, u64::MAX, 0x8000_0000_0000_0001];
+    words.extend((0..4).map(|_| rng.gen_range(0..2u128.pow(64)) as u64));
+    for word in words {
+        for rot in [1, 31, 63] {
+            test_rot::<Vesta>(word, rot, RotMode::Left);
+            test_rot::<Vesta>(word, rot, RotMode::Right);
+        }
+    }
+}
+
 #[test]
 // Test that a bad rotation fails as expected
 fn test_zero_rot() {
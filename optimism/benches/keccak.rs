@@ -0,0 +1,27 @@
+use ark_bn254::Fr as Fp;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, SamplingMode, Throughput};
+use kimchi::circuits::polynomials::keccak::Keccak;
+use kimchi_optimism::keccak::{environment::KeccakEnv, interpreter::KeccakInterpreter};
+
+pub fn bench_keccak_witness(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Keccak witness");
+    group.sample_size(10).sampling_mode(SamplingMode::Flat); // for slow benchmarks
+
+    for size in [32, 1 << 10, 1 << 16, 1 << 20] {
+        let preimage = vec![0xab; size];
+        // Throughput is measured in absorbed blocks per second
+        group.throughput(Throughput::Elements(Keccak::num_blocks(size) as u64));
+
+        // The same environment is reused across hashes, reset before each of them
+        let mut env = KeccakEnv::<Fp>::default();
+        group.bench_function(format!("hash ({size} bytes)"), |b| {
+            b.iter(|| {
+                env.reset();
+                env.hash(black_box(preimage.clone()))
+            })
+        });
+    }
+}
+
+criterion_group!(benches, bench_keccak_witness);
+criterion_main!(benches);
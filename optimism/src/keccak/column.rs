@@ -5,7 +5,7 @@ use kimchi::circuits::polynomials::keccak::constants::{
     CHI_SHIFTS_B_LEN, CHI_SHIFTS_B_OFF, CHI_SHIFTS_SUM_LEN, CHI_SHIFTS_SUM_OFF, PIRHO_DENSE_E_LEN,
     PIRHO_DENSE_E_OFF, PIRHO_DENSE_ROT_E_LEN, PIRHO_DENSE_ROT_E_OFF, PIRHO_EXPAND_ROT_E_LEN,
     PIRHO_EXPAND_ROT_E_OFF, PIRHO_QUOTIENT_E_LEN, PIRHO_QUOTIENT_E_OFF, PIRHO_REMAINDER_E_LEN,
-    PIRHO_REMAINDER_E_OFF, PIRHO_SHIFTS_E_LEN, PIRHO_SHIFTS_E_OFF, SPONGE_BYTES_OFF,
+    PIRHO_REMAINDER_E_OFF, PIRHO_SHIFTS_E_LEN, PIRHO_SHIFTS_E_OFF, RATE_IN_BYTES, SPONGE_BYTES_OFF,
     SPONGE_NEW_STATE_OFF, SPONGE_OLD_STATE_OFF, SPONGE_SHIFTS_OFF, STATE_LEN, THETA_DENSE_C_LEN,
     THETA_DENSE_C_OFF, THETA_DENSE_ROT_C_LEN, THETA_DENSE_ROT_C_OFF, THETA_EXPAND_ROT_C_LEN,
     THETA_EXPAND_ROT_C_OFF, THETA_QUOTIENT_C_LEN, THETA_QUOTIENT_C_OFF, THETA_REMAINDER_C_LEN,
@@ -14,7 +14,10 @@ use kimchi::circuits::polynomials::keccak::constants::{
 };
 use serde::{Deserialize, Serialize};
 
-use super::{grid_index, ZKVM_KECCAK_COLS_CURR, ZKVM_KECCAK_COLS_NEXT};
+use super::{
+    grid_coords, grid_index, PAD_SUFFIX_LEN, ZKVM_KECCAK_COLS, ZKVM_KECCAK_COLS_CURR,
+    ZKVM_KECCAK_COLS_MODE, ZKVM_KECCAK_COLS_NEXT,
+};
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub enum KeccakColumn {
@@ -27,6 +30,8 @@ pub enum KeccakColumn {
     FlagLength,                               // Coeff Length 0 | 1 .. 136
     TwoToPad,                                 // 2^PadLength
     InverseRound,                             // Round^-1
+    BlockAddress,                             // Address of the first byte of the block
+    FlagPermute,                              // Coeff Permute = 0 | 1
     FlagsBytes(usize),                        // 136 boolean values
     PadSuffix(usize),                         // 5 values with padding suffix
     RoundConstants(usize),                    // Round constants
@@ -53,6 +58,264 @@ pub enum KeccakColumn {
     SpongeXorState(usize),                    // Absorb Next[0..100)
 }
 
+/// Families of columns of the Keccak witness, grouped by the step or algorithm they belong to
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub enum KeccakFamily {
+    Flag,
+    Sponge,
+    Theta,
+    PiRho,
+    Chi,
+    Iota,
+}
+
+impl KeccakColumn {
+    /// Returns the family of columns this column belongs to
+    pub fn family(&self) -> KeccakFamily {
+        match self {
+            KeccakColumn::StepCounter
+            | KeccakColumn::FlagRound
+            | KeccakColumn::FlagAbsorb
+            | KeccakColumn::FlagSqueeze
+            | KeccakColumn::FlagRoot
+            | KeccakColumn::FlagPad
+            | KeccakColumn::FlagLength
+            | KeccakColumn::TwoToPad
+            | KeccakColumn::InverseRound
+            | KeccakColumn::BlockAddress
+            | KeccakColumn::FlagPermute
+            | KeccakColumn::FlagsBytes(_)
+            | KeccakColumn::PadSuffix(_) => KeccakFamily::Flag,
+            KeccakColumn::ThetaStateA(..)
+            | KeccakColumn::ThetaShiftsC(..)
+            | KeccakColumn::ThetaDenseC(..)
+            | KeccakColumn::ThetaQuotientC(_)
+            | KeccakColumn::ThetaRemainderC(..)
+            | KeccakColumn::ThetaDenseRotC(..)
+            | KeccakColumn::ThetaExpandRotC(..) => KeccakFamily::Theta,
+            KeccakColumn::PiRhoShiftsE(..)
+            | KeccakColumn::PiRhoDenseE(..)
+            | KeccakColumn::PiRhoQuotientE(..)
+            | KeccakColumn::PiRhoRemainderE(..)
+            | KeccakColumn::PiRhoDenseRotE(..)
+            | KeccakColumn::PiRhoExpandRotE(..) => KeccakFamily::PiRho,
+            KeccakColumn::ChiShiftsB(..) | KeccakColumn::ChiShiftsSum(..) => KeccakFamily::Chi,
+            KeccakColumn::RoundConstants(_) | KeccakColumn::IotaStateG(_) => KeccakFamily::Iota,
+            KeccakColumn::SpongeOldState(_)
+            | KeccakColumn::SpongeNewState(_)
+            | KeccakColumn::SpongeBytes(_)
+            | KeccakColumn::SpongeShifts(_)
+            | KeccakColumn::SpongeXorState(_) => KeccakFamily::Sponge,
+        }
+    }
+
+    /// Returns the number of index parameters carried by the column
+    pub fn arity(&self) -> usize {
+        match self {
+            KeccakColumn::StepCounter
+            | KeccakColumn::FlagRound
+            | KeccakColumn::FlagAbsorb
+            | KeccakColumn::FlagSqueeze
+            | KeccakColumn::FlagRoot
+            | KeccakColumn::FlagPad
+            | KeccakColumn::FlagLength
+            | KeccakColumn::TwoToPad
+            | KeccakColumn::InverseRound
+            | KeccakColumn::BlockAddress
+            | KeccakColumn::FlagPermute => 0,
+            KeccakColumn::FlagsBytes(_)
+            | KeccakColumn::PadSuffix(_)
+            | KeccakColumn::RoundConstants(_)
+            | KeccakColumn::ThetaQuotientC(_)
+            | KeccakColumn::IotaStateG(_)
+            | KeccakColumn::SpongeOldState(_)
+            | KeccakColumn::SpongeNewState(_)
+            | KeccakColumn::SpongeBytes(_)
+            | KeccakColumn::SpongeShifts(_)
+            | KeccakColumn::SpongeXorState(_) => 1,
+            KeccakColumn::ThetaDenseC(..)
+            | KeccakColumn::ThetaRemainderC(..)
+            | KeccakColumn::ThetaDenseRotC(..)
+            | KeccakColumn::ThetaExpandRotC(..) => 2,
+            KeccakColumn::ThetaStateA(..)
+            | KeccakColumn::ThetaShiftsC(..)
+            | KeccakColumn::PiRhoDenseE(..)
+            | KeccakColumn::PiRhoQuotientE(..)
+            | KeccakColumn::PiRhoRemainderE(..)
+            | KeccakColumn::PiRhoDenseRotE(..)
+            | KeccakColumn::PiRhoExpandRotE(..) => 3,
+            KeccakColumn::PiRhoShiftsE(..)
+            | KeccakColumn::ChiShiftsB(..)
+            | KeccakColumn::ChiShiftsSum(..) => 4,
+        }
+    }
+
+    /// Returns the index of the column in the flat witness matrix of the Keccak environment.
+    /// The first [ZKVM_KECCAK_COLS_CURR] columns follow the layout of the Keccak gate of kimchi,
+    /// followed by the [ZKVM_KECCAK_COLS_NEXT] columns of the next state, and the flags.
+    /// Note that sponge and round columns share the same indices.
+    pub fn to_index(&self) -> usize {
+        let next = ZKVM_KECCAK_COLS_CURR;
+        let mode = next + ZKVM_KECCAK_COLS_NEXT;
+        let flags_bytes = mode + ZKVM_KECCAK_COLS_MODE;
+        let pad_suffix = flags_bytes + RATE_IN_BYTES;
+        let round_constants = pad_suffix + PAD_SUFFIX_LEN;
+        match *self {
+            KeccakColumn::StepCounter => mode,
+            KeccakColumn::FlagRound => mode + 1,
+            KeccakColumn::FlagAbsorb => mode + 2,
+            KeccakColumn::FlagSqueeze => mode + 3,
+            KeccakColumn::FlagRoot => mode + 4,
+            KeccakColumn::FlagPad => mode + 5,
+            KeccakColumn::FlagLength => mode + 6,
+            KeccakColumn::TwoToPad => mode + 7,
+            KeccakColumn::InverseRound => mode + 8,
+            KeccakColumn::BlockAddress => mode + 9,
+            KeccakColumn::FlagPermute => mode + 10,
+            KeccakColumn::FlagsBytes(i) => flags_bytes + i,
+            KeccakColumn::PadSuffix(i) => pad_suffix + i,
+            KeccakColumn::RoundConstants(q) => round_constants + q,
+            KeccakColumn::ThetaStateA(y, x, q) => {
+                THETA_STATE_A_OFF + grid_index(THETA_STATE_A_LEN, 0, y, x, q)
+            }
+            KeccakColumn::ThetaShiftsC(i, x, q) => {
+                THETA_SHIFTS_C_OFF + grid_index(THETA_SHIFTS_C_LEN, i, 0, x, q)
+            }
+            KeccakColumn::ThetaDenseC(x, q) => {
+                THETA_DENSE_C_OFF + grid_index(THETA_DENSE_C_LEN, 0, 0, x, q)
+            }
+            KeccakColumn::ThetaQuotientC(x) => {
+                THETA_QUOTIENT_C_OFF + grid_index(THETA_QUOTIENT_C_LEN, 0, 0, x, 0)
+            }
+            KeccakColumn::ThetaRemainderC(x, q) => {
+                THETA_REMAINDER_C_OFF + grid_index(THETA_REMAINDER_C_LEN, 0, 0, x, q)
+            }
+            KeccakColumn::ThetaDenseRotC(x, q) => {
+                THETA_DENSE_ROT_C_OFF + grid_index(THETA_DENSE_ROT_C_LEN, 0, 0, x, q)
+            }
+            KeccakColumn::ThetaExpandRotC(x, q) => {
+                THETA_EXPAND_ROT_C_OFF + grid_index(THETA_EXPAND_ROT_C_LEN, 0, 0, x, q)
+            }
+            KeccakColumn::PiRhoShiftsE(i, y, x, q) => {
+                PIRHO_SHIFTS_E_OFF + grid_index(PIRHO_SHIFTS_E_LEN, i, y, x, q)
+            }
+            KeccakColumn::PiRhoDenseE(y, x, q) => {
+                PIRHO_DENSE_E_OFF + grid_index(PIRHO_DENSE_E_LEN, 0, y, x, q)
+            }
+            KeccakColumn::PiRhoQuotientE(y, x, q) => {
+                PIRHO_QUOTIENT_E_OFF + grid_index(PIRHO_QUOTIENT_E_LEN, 0, y, x, q)
+            }
+            KeccakColumn::PiRhoRemainderE(y, x, q) => {
+                PIRHO_REMAINDER_E_OFF + grid_index(PIRHO_REMAINDER_E_LEN, 0, y, x, q)
+            }
+            KeccakColumn::PiRhoDenseRotE(y, x, q) => {
+                PIRHO_DENSE_ROT_E_OFF + grid_index(PIRHO_DENSE_ROT_E_LEN, 0, y, x, q)
+            }
+            KeccakColumn::PiRhoExpandRotE(y, x, q) => {
+                PIRHO_EXPAND_ROT_E_OFF + grid_index(PIRHO_EXPAND_ROT_E_LEN, 0, y, x, q)
+            }
+            KeccakColumn::ChiShiftsB(i, y, x, q) => {
+                CHI_SHIFTS_B_OFF + grid_index(CHI_SHIFTS_B_LEN, i, y, x, q)
+            }
+            KeccakColumn::ChiShiftsSum(i, y, x, q) => {
+                CHI_SHIFTS_SUM_OFF + grid_index(CHI_SHIFTS_SUM_LEN, i, y, x, q)
+            }
+            KeccakColumn::IotaStateG(i) => next + i,
+            KeccakColumn::SpongeOldState(i) => SPONGE_OLD_STATE_OFF + i,
+            KeccakColumn::SpongeNewState(i) => SPONGE_NEW_STATE_OFF + i,
+            KeccakColumn::SpongeBytes(i) => SPONGE_BYTES_OFF + i,
+            KeccakColumn::SpongeShifts(i) => SPONGE_SHIFTS_OFF + i,
+            KeccakColumn::SpongeXorState(i) => next + i,
+        }
+    }
+
+    /// Returns the column located at the given index of the flat witness matrix of the
+    /// Keccak environment. Indices shared by sponge and round columns are mapped to the
+    /// round columns, so `from_index(col.to_index())` addresses the same cell as `col`.
+    pub fn from_index(index: usize) -> Self {
+        assert!(index < ZKVM_KECCAK_COLS, "Invalid Keccak column index");
+        let next = ZKVM_KECCAK_COLS_CURR;
+        let mode = next + ZKVM_KECCAK_COLS_NEXT;
+        let flags_bytes = mode + ZKVM_KECCAK_COLS_MODE;
+        let pad_suffix = flags_bytes + RATE_IN_BYTES;
+        let round_constants = pad_suffix + PAD_SUFFIX_LEN;
+        if index >= round_constants {
+            return KeccakColumn::RoundConstants(index - round_constants);
+        }
+        if index >= pad_suffix {
+            return KeccakColumn::PadSuffix(index - pad_suffix);
+        }
+        if index >= flags_bytes {
+            return KeccakColumn::FlagsBytes(index - flags_bytes);
+        }
+        if index >= mode {
+            return [
+                KeccakColumn::StepCounter,
+                KeccakColumn::FlagRound,
+                KeccakColumn::FlagAbsorb,
+                KeccakColumn::FlagSqueeze,
+                KeccakColumn::FlagRoot,
+                KeccakColumn::FlagPad,
+                KeccakColumn::FlagLength,
+                KeccakColumn::TwoToPad,
+                KeccakColumn::InverseRound,
+                KeccakColumn::BlockAddress,
+                KeccakColumn::FlagPermute,
+            ][index - mode];
+        }
+        if index >= next {
+            return KeccakColumn::IotaStateG(index - next);
+        }
+        let coords = |offset: usize, length: usize| grid_coords(length, index - offset);
+        if index >= CHI_SHIFTS_SUM_OFF {
+            let (i, y, x, q) = coords(CHI_SHIFTS_SUM_OFF, CHI_SHIFTS_SUM_LEN);
+            KeccakColumn::ChiShiftsSum(i, y, x, q)
+        } else if index >= CHI_SHIFTS_B_OFF {
+            let (i, y, x, q) = coords(CHI_SHIFTS_B_OFF, CHI_SHIFTS_B_LEN);
+            KeccakColumn::ChiShiftsB(i, y, x, q)
+        } else if index >= PIRHO_EXPAND_ROT_E_OFF {
+            let (_, y, x, q) = coords(PIRHO_EXPAND_ROT_E_OFF, PIRHO_EXPAND_ROT_E_LEN);
+            KeccakColumn::PiRhoExpandRotE(y, x, q)
+        } else if index >= PIRHO_DENSE_ROT_E_OFF {
+            let (_, y, x, q) = coords(PIRHO_DENSE_ROT_E_OFF, PIRHO_DENSE_ROT_E_LEN);
+            KeccakColumn::PiRhoDenseRotE(y, x, q)
+        } else if index >= PIRHO_REMAINDER_E_OFF {
+            let (_, y, x, q) = coords(PIRHO_REMAINDER_E_OFF, PIRHO_REMAINDER_E_LEN);
+            KeccakColumn::PiRhoRemainderE(y, x, q)
+        } else if index >= PIRHO_QUOTIENT_E_OFF {
+            let (_, y, x, q) = coords(PIRHO_QUOTIENT_E_OFF, PIRHO_QUOTIENT_E_LEN);
+            KeccakColumn::PiRhoQuotientE(y, x, q)
+        } else if index >= PIRHO_DENSE_E_OFF {
+            let (_, y, x, q) = coords(PIRHO_DENSE_E_OFF, PIRHO_DENSE_E_LEN);
+            KeccakColumn::PiRhoDenseE(y, x, q)
+        } else if index >= PIRHO_SHIFTS_E_OFF {
+            let (i, y, x, q) = coords(PIRHO_SHIFTS_E_OFF, PIRHO_SHIFTS_E_LEN);
+            KeccakColumn::PiRhoShiftsE(i, y, x, q)
+        } else if index >= THETA_EXPAND_ROT_C_OFF {
+            let (_, _, x, q) = coords(THETA_EXPAND_ROT_C_OFF, THETA_EXPAND_ROT_C_LEN);
+            KeccakColumn::ThetaExpandRotC(x, q)
+        } else if index >= THETA_DENSE_ROT_C_OFF {
+            let (_, _, x, q) = coords(THETA_DENSE_ROT_C_OFF, THETA_DENSE_ROT_C_LEN);
+            KeccakColumn::ThetaDenseRotC(x, q)
+        } else if index >= THETA_REMAINDER_C_OFF {
+            let (_, _, x, q) = coords(THETA_REMAINDER_C_OFF, THETA_REMAINDER_C_LEN);
+            KeccakColumn::ThetaRemainderC(x, q)
+        } else if index >= THETA_QUOTIENT_C_OFF {
+            let (_, _, x, _) = coords(THETA_QUOTIENT_C_OFF, THETA_QUOTIENT_C_LEN);
+            KeccakColumn::ThetaQuotientC(x)
+        } else if index >= THETA_DENSE_C_OFF {
+            let (_, _, x, q) = coords(THETA_DENSE_C_OFF, THETA_DENSE_C_LEN);
+            KeccakColumn::ThetaDenseC(x, q)
+        } else if index >= THETA_SHIFTS_C_OFF {
+            let (i, _, x, q) = coords(THETA_SHIFTS_C_OFF, THETA_SHIFTS_C_LEN);
+            KeccakColumn::ThetaShiftsC(i, x, q)
+        } else {
+            let (_, y, x, q) = coords(THETA_STATE_A_OFF, THETA_STATE_A_LEN);
+            KeccakColumn::ThetaStateA(y, x, q)
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub struct KeccakColumns<T> {
     pub step_counter: T,
@@ -64,6 +327,8 @@ pub struct KeccakColumns<T> {
     pub flag_length: T,          // Coeff Length 0 | 1 .. 136
     pub two_to_pad: T,           // 2^PadLength
     pub inverse_round: T,        // Round^-1
+    pub block_address: T,        // Address of the first byte of the block
+    pub flag_permute: T,         // Coeff Permute = 0 | 1
     pub flags_bytes: Vec<T>,     // 136 boolean values
     pub pad_suffix: Vec<T>,      // 5 values with padding suffix
     pub round_constants: Vec<T>, // Round constants
@@ -111,6 +376,8 @@ impl<T: Zero + One + Clone> Default for KeccakColumns<T> {
             flag_length: T::zero(),
             two_to_pad: T::one(), // So that default 2^0 is in the table
             inverse_round: T::zero(),
+            block_address: T::zero(),
+            flag_permute: T::zero(),
             flags_bytes: vec![T::zero(); 136],
             pad_suffix: vec![T::zero(); 5],
             round_constants: vec![T::zero(); 4], // RC[0] is set to be all zeros
@@ -134,6 +401,8 @@ impl<T: Clone> Index<KeccakColumn> for KeccakColumns<T> {
             KeccakColumn::FlagLength => &self.flag_length,
             KeccakColumn::TwoToPad => &self.two_to_pad,
             KeccakColumn::InverseRound => &self.inverse_round,
+            KeccakColumn::BlockAddress => &self.block_address,
+            KeccakColumn::FlagPermute => &self.flag_permute,
             KeccakColumn::FlagsBytes(i) => &self.flags_bytes[i],
             KeccakColumn::PadSuffix(i) => &self.pad_suffix[i],
             KeccakColumn::RoundConstants(q) => &self.round_constants[q],
@@ -204,6 +473,8 @@ impl<T: Clone> IndexMut<KeccakColumn> for KeccakColumns<T> {
             KeccakColumn::FlagLength => &mut self.flag_length,
             KeccakColumn::TwoToPad => &mut self.two_to_pad,
             KeccakColumn::InverseRound => &mut self.inverse_round,
+            KeccakColumn::BlockAddress => &mut self.block_address,
+            KeccakColumn::FlagPermute => &mut self.flag_permute,
             KeccakColumn::FlagsBytes(i) => &mut self.flags_bytes[i],
             KeccakColumn::PadSuffix(i) => &mut self.pad_suffix[i],
             KeccakColumn::RoundConstants(q) => &mut self.round_constants[q],
@@ -261,3 +532,30 @@ impl<T: Clone> IndexMut<KeccakColumn> for KeccakColumns<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_column_family_and_arity() {
+        assert_eq!(
+            KeccakColumn::ThetaShiftsC(1, 2, 3).family(),
+            KeccakFamily::Theta
+        );
+        assert_eq!(KeccakColumn::ThetaShiftsC(1, 2, 3).arity(), 3);
+        assert_eq!(KeccakColumn::StepCounter.family(), KeccakFamily::Flag);
+        assert_eq!(KeccakColumn::StepCounter.arity(), 0);
+        assert_eq!(
+            KeccakColumn::ChiShiftsSum(0, 1, 2, 3).family(),
+            KeccakFamily::Chi
+        );
+        assert_eq!(KeccakColumn::ChiShiftsSum(0, 1, 2, 3).arity(), 4);
+        assert_eq!(KeccakColumn::IotaStateG(7).family(), KeccakFamily::Iota);
+        assert_eq!(
+            KeccakColumn::SpongeXorState(7).family(),
+            KeccakFamily::Sponge
+        );
+    }
+}
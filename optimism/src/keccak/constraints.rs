@@ -47,6 +47,8 @@ impl<Fp: Field> Constraints for KeccakEnv<Fp> {
                 self.constrain(Self::is_boolean(self.is_root()));
                 // Pad is either true or false
                 self.constrain(Self::is_boolean(self.is_pad()));
+                // Permute is either true or false
+                self.constrain(Self::is_boolean(self.is_permute()));
                 for i in 0..RATE_IN_BYTES {
                     // Bytes are either involved on padding or not
                     self.constrain(Self::is_boolean(self.in_padding(i)));
@@ -64,6 +66,8 @@ impl<Fp: Field> Constraints for KeccakEnv<Fp> {
                 self.constrain(Self::either_false(self.is_round(), self.is_root()));
                 // Absorb and Squeeze cannot happen at the same time
                 self.constrain(Self::either_false(self.is_absorb(), self.is_squeeze()));
+                // Only a squeeze can be followed by a permutation
+                self.constrain(self.is_permute() * Self::not(self.is_squeeze()));
                 // Only one of Round and Sponge can be zero
                 // This means either Sponge is true or Round is nonzero -> has an inverse
                 self.constrain(self.is_sponge() * self.round());
@@ -81,6 +85,10 @@ impl<Fp: Field> Constraints for KeccakEnv<Fp> {
             for i in 0..QUARTERS * DIM * DIM {
                 // In first absorb, root state is all zeros
                 self.constrain(self.is_root() * self.old_state(i).clone());
+                // A squeeze followed by a permutation passes its state on unchanged
+                self.constrain(
+                    self.is_permute() * (self.xor_state(i).clone() - self.old_state(i).clone()),
+                );
                 // Absorbs the new block by performing XOR with the old state
                 self.constrain(
                     self.is_absorb()
@@ -115,10 +123,7 @@ impl<Fp: Field> Constraints for KeccakEnv<Fp> {
                 );
             }
             // Check that the padding is located at the end of the message
-            let pad_at_end = (0..RATE_IN_BYTES).fold(Self::zero(), |acc, i| {
-                acc * Self::two() + self.sponge_bytes(i).clone()
-            });
-            self.constrain(self.is_pad() * (self.two_to_pad() - Self::one() - pad_at_end));
+            self.constrain(self.is_pad() * (self.two_to_pad() - Self::one() - self.pad_at_end()));
             // Check that the padding value is correct
             for i in 0..5 {
                 self.constrain(self.is_pad() * (self.block_in_padding(i) - self.pad_suffix(i)));
@@ -1,15 +1,132 @@
 use super::{
     column::{KeccakColumn, KeccakColumns},
+    constraints::Constraints,
     interpreter::{Absorb, KeccakStep, Sponge},
-    ArithOps, BoolOps, DIM, E, QUARTERS,
+    ArithOps, BoolOps, DIM, E, HASH_BYTELENGTH, QUARTERS, WORD_LENGTH_IN_BITS, ZKVM_KECCAK_COLS,
 };
 use crate::mips::interpreter::Lookup;
-use ark_ff::{Field, One};
-use kimchi::circuits::expr::Operations;
+use ark_ff::{Field, One, PrimeField};
+use kimchi::circuits::expr::{Literal as _, Operations};
 use kimchi::{
-    auto_clone_array, circuits::expr::ConstantTerm::Literal,
-    circuits::polynomials::keccak::constants::*, grid, o1_utils::Two,
+    auto_clone_array,
+    circuits::expr::ConstantTerm::Literal,
+    circuits::polynomials::keccak::{constants::*, Endianness, Keccak},
+    grid,
+    o1_utils::{FieldHelpers, Two},
 };
+use std::io::Write;
+use thiserror::Error;
+
+/// Represents an error found when running the steps of a Keccak hash
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum KeccakError {
+    #[error("The step counter overflows the limit of {max_steps} steps after step {step}")]
+    TooManySteps { step: u64, max_steps: u64 },
+}
+
+/// Columns of the witness trace that are exported by [KeccakEnv::to_csv]
+const CSV_COLUMNS: [KeccakColumn; 13] = [
+    KeccakColumn::StepCounter,
+    KeccakColumn::FlagRound,
+    KeccakColumn::FlagAbsorb,
+    KeccakColumn::FlagSqueeze,
+    KeccakColumn::FlagRoot,
+    KeccakColumn::FlagPad,
+    KeccakColumn::FlagLength,
+    KeccakColumn::TwoToPad,
+    KeccakColumn::InverseRound,
+    // First lane of the output state of the round
+    KeccakColumn::IotaStateG(0),
+    KeccakColumn::IotaStateG(1),
+    KeccakColumn::IotaStateG(2),
+    KeccakColumn::IotaStateG(3),
+];
+
+/// Variants of the hash function computed by [KeccakEnv], differing in their padding
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HashVariant {
+    /// Keccak-256, with the `0x01` domain separation byte
+    #[default]
+    Keccak256,
+    /// SHA3-256, with the `0x06` domain separation byte
+    Sha3_256,
+    /// SHAKE256, with the `0x1F` domain separation byte, see [KeccakEnv::xof]
+    Shake256,
+}
+
+impl HashVariant {
+    /// Returns the first byte of the padding of the variant
+    pub fn domain_byte(&self) -> u8 {
+        match self {
+            HashVariant::Keccak256 => 0x01,
+            HashVariant::Sha3_256 => 0x06,
+            HashVariant::Shake256 => 0x1F,
+        }
+    }
+}
+
+/// Parameters of the Keccak sponge computed by [KeccakEnv], differing in their rate and
+/// output length while sharing the same permutation. They are independent of the padding
+/// chosen with [HashVariant], e.g. SHA3-512 is [HashVariant::Sha3_256] with [KeccakParams::Keccak512].
+/// Note that the constraints are still laid out for the rate of Keccak-256, so the witness
+/// of the other parameters is rejected by [KeccakEnv::to_kimchi_witness].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeccakParams {
+    /// 256-bit output, with a rate of 136 bytes
+    #[default]
+    Keccak256,
+    /// 384-bit output, with a rate of 104 bytes
+    Keccak384,
+    /// 512-bit output, with a rate of 72 bytes
+    Keccak512,
+}
+
+impl KeccakParams {
+    /// Returns the byte-length of the hash output
+    pub fn hash_bytelength(&self) -> usize {
+        match self {
+            KeccakParams::Keccak256 => HASH_BYTELENGTH,
+            KeccakParams::Keccak384 => 48,
+            KeccakParams::Keccak512 => 64,
+        }
+    }
+
+    /// Returns the number of 64-bit words of the hash output
+    pub fn words_in_hash(&self) -> usize {
+        self.hash_bytelength() * 8 / WORD_LENGTH_IN_BITS
+    }
+
+    /// Returns the byte-length of the capacity, twice the length of the hash output
+    pub fn capacity_in_bytes(&self) -> usize {
+        2 * self.hash_bytelength()
+    }
+
+    /// Returns the byte-length of the rate, the part of the state absorbing each block
+    pub fn rate_in_bytes(&self) -> usize {
+        RATE_IN_BYTES + CAPACITY_IN_BYTES - self.capacity_in_bytes()
+    }
+
+    /// Number of blocks absorbed on input a preimage of the given bytelength,
+    /// including the padding
+    pub fn num_blocks(&self, bytelength: usize) -> usize {
+        bytelength / self.rate_in_bytes() + 1
+    }
+}
+
+/// Intermediate states of a round, as 100 expanded quarters each, see [KeccakEnv::round_trace]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoundTrace {
+    /// Round number, between 1 and 24
+    pub round: u64,
+    /// Output of Theta
+    pub state_e: Vec<u64>,
+    /// Output of PiRho
+    pub state_b: Vec<u64>,
+    /// Output of Chi
+    pub state_f: Vec<u64>,
+    /// Output of Iota, that is, of the round
+    pub state_g: Vec<u64>,
+}
 
 #[derive(Clone, Debug)]
 pub struct KeccakEnv<Fp> {
@@ -17,12 +134,20 @@ pub struct KeccakEnv<Fp> {
     pub(crate) constraints: Vec<E<Fp>>,
     /// Values that are looked up in the circuit
     pub(crate) lookups: Vec<Lookup<E<Fp>>>,
-    /// Expanded block of previous step
+    /// State passed from the previous step to the current one, as 100 expanded quarters.
+    /// Every step begins from this state and replaces it with its own output, exactly as
+    /// written in the output columns of the step, so that the step lookups match. Note that
+    /// the output of an absorb is the sparse sum of two states, only reset by the next round,
+    /// see [Self::dense_state] for the canonical dense value. The state is deliberately not
+    /// stored in dense form, as collapsing it between steps would break the equality between
+    /// the output columns of a step and the input columns of the next one.
     pub(crate) prev_block: Vec<u64>,
-    /// Padded preimage data
-    pub(crate) padded: Vec<u8>,
+    /// Blocks of the padded preimage data, of the rate of [Self::params] each
+    pub(crate) blocks: Vec<Vec<u8>>,
     /// Current block of preimage data
     pub(crate) block_idx: usize,
+    /// Memory address of the first byte of the preimage
+    pub(crate) preimage_address: u64,
     /// The full state of the Keccak gate (witness)
     pub(crate) keccak_state: KeccakColumns<E<Fp>>,
     /// Byte-length of the 10*1 pad (<=136)
@@ -33,9 +158,89 @@ pub struct KeccakEnv<Fp> {
     pub(crate) keccak_step: Option<KeccakStep>,
     /// Step counter of the total number of steps executed so far (starts with 0)
     pub(crate) step_counter: u64,
+    /// Maximum number of steps of the witness, see [Self::set_max_steps]
+    pub(crate) max_steps: u64,
+    /// Output columns of the last round executed, expected to be the input columns of the squeeze
+    #[cfg(debug_assertions)]
+    pub(crate) last_round_state: Vec<E<Fp>>,
+    /// Whether the row of each step is recorded in [Self::trace], see [Self::set_record_trace]
+    pub(crate) record_trace: bool,
+    /// Witness of all the steps executed so far, one row per step, if recorded
+    pub(crate) trace: Vec<KeccakColumns<E<Fp>>>,
+    /// Byte order in which the hash output is exposed
+    pub(crate) endianness: Endianness,
+    /// Variant of the hash, determining the padding of the preimage
+    pub(crate) hash_variant: HashVariant,
+    /// Parameters of the sponge, determining the rate and the length of the hash output
+    pub(crate) params: KeccakParams,
+    /// Bytes of the hash output produced by the squeeze, in the chosen byte order
+    pub(crate) hash_output: Vec<u8>,
+    /// Syscall channel where the final squeeze of each hash writes its digest
+    pub(crate) syscall_channel: Vec<Vec<u8>>,
+    /// Rate bytes of the state exposed by each squeeze of the current hash, in order
+    pub(crate) xof_output: Vec<u8>,
+    /// Bytes of extendable output requested by [Self::xof] for the current hash, if any
+    pub(crate) xof_len: Option<usize>,
+    /// Whether the intermediate states of each round are recorded in [Self::round_trace]
+    pub(crate) record_intermediate: bool,
+    /// Intermediate states of the rounds of the current hash, if recorded
+    pub(crate) round_trace: Vec<RoundTrace>,
+    /// Bytes of the preimage streamed by [Self::absorb_chunk] that do not fill a block yet,
+    /// if a stream is in progress
+    pub(crate) stream_buffer: Option<Vec<u8>>,
+    /// Block streamed to the next absorb by [Self::absorb_chunk] or [Self::finalize],
+    /// which is not kept in [Self::blocks] so that streams run in constant memory
+    pub(crate) stream_block: Option<Vec<u8>>,
+    /// Hook run at the end of each step update, used to inject faults in tests
+    #[cfg(test)]
+    pub(crate) update_step_hook: Option<fn(&mut Self)>,
+}
+
+impl<Fp: Field> Default for KeccakEnv<Fp> {
+    fn default() -> Self {
+        Self {
+            constraints: vec![],
+            lookups: vec![],
+            prev_block: vec![],
+            blocks: vec![],
+            block_idx: 0,
+            preimage_address: 0,
+            keccak_state: KeccakColumns::default(),
+            pad_len: 0,
+            blocks_left_to_absorb: 0,
+            keccak_step: None,
+            step_counter: 0,
+            max_steps: u64::MAX,
+            #[cfg(debug_assertions)]
+            last_round_state: vec![],
+            record_trace: false,
+            trace: vec![],
+            endianness: Endianness::default(),
+            hash_variant: HashVariant::default(),
+            params: KeccakParams::default(),
+            hash_output: vec![],
+            syscall_channel: vec![],
+            xof_output: vec![],
+            xof_len: None,
+            record_intermediate: false,
+            round_trace: vec![],
+            stream_buffer: None,
+            stream_block: None,
+            #[cfg(test)]
+            update_step_hook: None,
+        }
+    }
 }
 
 impl<Fp: Field> KeccakEnv<Fp> {
+    /// Creates an environment computing the Keccak sponge with the given parameters
+    pub fn with_params(params: KeccakParams) -> Self {
+        Self {
+            params,
+            ..Self::default()
+        }
+    }
+
     pub fn write_column(&mut self, column: KeccakColumn, value: u64) {
         self.keccak_state[column] = Self::constant(value);
     }
@@ -44,39 +249,339 @@ impl<Fp: Field> KeccakEnv<Fp> {
         self.keccak_state[column] = Self::constant_field(value);
     }
 
+    /// Splits the padded `preimage` into the blocks read by each absorb step,
+    /// and returns the byte-length of the padding
+    pub(crate) fn set_preimage(&mut self, preimage: &[u8]) -> u64 {
+        let padded = Keccak::pad_with_rate(
+            preimage,
+            self.hash_variant.domain_byte(),
+            self.params.rate_in_bytes(),
+        );
+        self.blocks = padded
+            .chunks(self.params.rate_in_bytes())
+            .map(|block| block.to_vec())
+            .collect();
+        (padded.len() - preimage.len()) as u64
+    }
+
+    /// Sets the memory address from which the preimage bytes are read
+    pub fn set_preimage_address(&mut self, address: u64) {
+        self.preimage_address = address;
+    }
+
+    /// Returns the state passed to the current step as 100 dense quarters,
+    /// resetting the expanded representation of [Self::prev_block]
+    pub(crate) fn dense_state(&self) -> Vec<u64> {
+        Keccak::collapse(&Keccak::reset(&Keccak::shift(&self.prev_block)))
+    }
+
+    /// Reads the block of preimage data absorbed by the current absorb step,
+    /// either streamed to it or split from the whole preimage
+    pub(crate) fn read_block(&mut self) -> Vec<u8> {
+        self.stream_block
+            .take()
+            .unwrap_or_else(|| self.blocks[self.block_idx].clone())
+    }
+
+    /// Sets the byte order in which the squeeze exposes the hash output.
+    /// The witness columns are always filled in little endian order.
+    pub fn set_endianness(&mut self, endianness: Endianness) {
+        self.endianness = endianness;
+    }
+
+    /// Sets the variant of the hash computed by the next calls to [KeccakInterpreter::hash]
+    pub fn set_hash_variant(&mut self, hash_variant: HashVariant) {
+        self.hash_variant = hash_variant;
+    }
+
+    /// Sets whether the next hashes record the intermediate states of each round,
+    /// see [Self::round_trace]. Recording is disabled by default.
+    pub fn set_record_intermediate(&mut self, record_intermediate: bool) {
+        self.record_intermediate = record_intermediate;
+    }
+
+    /// Sets whether the next steps record their row in the trace exported by
+    /// [Self::to_kimchi_witness] and [Self::to_csv] and checked by [Self::check_constraints].
+    /// Recording is disabled by default, as the rows of large preimages take a lot of memory.
+    /// The lookups of the steps are always recorded.
+    pub fn set_record_trace(&mut self, record_trace: bool) {
+        self.record_trace = record_trace;
+    }
+
+    /// Sets the maximum number of steps of the witness, e.g. the size of the domain of the
+    /// circuit it is proven in. A step with no row left fails, see [Self::update_step].
+    /// There is no limit by default, other than the range of the step counter.
+    pub fn set_max_steps(&mut self, max_steps: u64) {
+        self.max_steps = max_steps;
+    }
+
+    /// Returns the intermediate states of the rounds executed by the current hash, in order,
+    /// or nothing if they are not recorded, see [Self::set_record_intermediate]
+    pub fn round_trace(&self) -> &[RoundTrace] {
+        &self.round_trace
+    }
+
+    /// Returns the bytes of the hash output computed by the last squeeze, in the chosen byte order
+    pub fn hash_output(&self) -> Vec<u8> {
+        self.hash_output.clone()
+    }
+
+    /// Returns the first `len` bytes of the hash output, see [Self::hash_output].
+    /// Panics if `len` is larger than the length of the digest.
+    pub fn hash_output_truncated(&self, len: usize) -> Vec<u8> {
+        let hash_bytelength = self.params.hash_bytelength();
+        assert!(
+            len <= hash_bytelength,
+            "cannot truncate a {hash_bytelength}-byte hash output to {len} bytes"
+        );
+        self.hash_output[..len].to_vec()
+    }
+
+    /// Returns the digest bytes written in the `SpongeBytes` columns of the current squeeze step
+    pub fn hash_of_step(&self) -> Vec<u8> {
+        (0..self.params.hash_bytelength())
+            .map(|i| {
+                self.keccak_state[KeccakColumn::SpongeBytes(i)]
+                    .to_literal_ref()
+                    .expect("Keccak witness values must be constants")
+                    .to_bytes()[0]
+            })
+            .collect()
+    }
+
+    /// Returns the Keccak-256 digest of the last hash, as written to the syscall channel.
+    /// Unlike [Self::hash_output], it does not depend on the chosen byte order.
+    /// Panics with other [KeccakParams], whose digests are not 32 bytes long.
+    pub fn digest(&self) -> [u8; HASH_BYTELENGTH] {
+        self.syscall_channel
+            .last()
+            .expect("no Keccak hash has been computed")
+            .as_slice()
+            .try_into()
+            .expect("only Keccak-256 digests are 32 bytes long")
+    }
+
+    /// Returns the public inputs binding the last hash to its private preimage: the digest,
+    /// as the `SpongeBytes` columns of the squeeze step (the last step executed) in little
+    /// endian order, and the byte-length of the preimage. All the other columns are private.
+    pub fn public_inputs(&self) -> (Vec<Fp>, Fp) {
+        assert_eq!(
+            self.keccak_state[KeccakColumn::FlagSqueeze].to_literal_ref(),
+            Some(&Fp::one()),
+            "no Keccak hash has been computed"
+        );
+        let digest = (0..self.params.hash_bytelength())
+            .map(|i| {
+                *self.keccak_state[KeccakColumn::SpongeBytes(i)]
+                    .to_literal_ref()
+                    .expect("Keccak witness values must be constants")
+            })
+            .collect();
+        let length = (self.block_idx * self.params.rate_in_bytes()) as u64 - self.pad_len;
+        (digest, Fp::from(length))
+    }
+
+    /// Checks that the `SpongeXorState` columns of the current step are the sum of the
+    /// `SpongeOldState` and `SpongeNewState` columns, as required by the absorb constraints.
+    /// Returns the index of the first mismatching entry, if any.
+    pub fn check_sponge_xor_state(&self) -> Result<(), usize> {
+        let value = |column| {
+            *self.keccak_state[column]
+                .to_literal_ref()
+                .expect("Keccak witness values must be constants")
+        };
+        match (0..STATE_LEN).find(|&i| {
+            value(KeccakColumn::SpongeOldState(i)) + value(KeccakColumn::SpongeNewState(i))
+                != value(KeccakColumn::SpongeXorState(i))
+        }) {
+            Some(i) => Err(i),
+            None => Ok(()),
+        }
+    }
+
+    /// Panics unless the hash is Keccak-256, the only one the constraints are laid out for
+    fn assert_constrained_params(&self) {
+        assert_eq!(
+            self.params,
+            KeccakParams::Keccak256,
+            "the Keccak constraints only support Keccak-256"
+        );
+    }
+
+    /// Evaluates the constraints of [Constraints::constraints] over the witness of each step
+    /// recorded so far (see [Self::set_record_trace]), and returns a description of every
+    /// unsatisfied constraint, identified by its step and by its index in the list of constraints.
+    /// Only [KeccakParams::Keccak256] is supported.
+    pub fn check_constraints(&self) -> Result<(), Vec<String>> {
+        self.assert_constrained_params();
+        let mut errors = vec![];
+        for (step, columns) in self.trace.iter().enumerate() {
+            let mut env = KeccakEnv::<Fp> {
+                keccak_state: columns.clone(),
+                ..KeccakEnv::default()
+            };
+            env.constraints();
+            for (index, constraint) in env.constraints.iter().enumerate() {
+                match constraint.to_literal_ref() {
+                    Some(value) if value.is_zero() => (),
+                    Some(value) => errors.push(format!(
+                        "step {step}: constraint {index} evaluates to {value} instead of zero"
+                    )),
+                    None => errors.push(format!(
+                        "step {step}: constraint {index} does not evaluate to a constant"
+                    )),
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Returns the witness of all the steps recorded so far as a matrix of columns,
+    /// where each column is located at the index given by [KeccakColumn::to_index]
+    /// and each row corresponds to one step of the hash.
+    /// Only [KeccakParams::Keccak256] is supported.
+    pub fn to_kimchi_witness(&self) -> Vec<Vec<Fp>> {
+        self.assert_constrained_params();
+        let mut witness = vec![vec![Fp::zero(); self.trace.len()]; ZKVM_KECCAK_COLS];
+        for (row, columns) in self.trace.iter().enumerate() {
+            for (index, column) in witness.iter_mut().enumerate() {
+                column[row] = *columns[KeccakColumn::from_index(index)]
+                    .to_literal_ref()
+                    .expect("Keccak witness values must be constants");
+            }
+        }
+        witness
+    }
+
+    /// Writes the witness of all the steps recorded so far as CSV, with a header line
+    /// followed by one line per step containing the step counter, the flags,
+    /// and the first lane of the round output, in decimal
+    pub fn to_csv(&self, mut w: impl Write) -> std::io::Result<()>
+    where
+        Fp: PrimeField,
+    {
+        let header = CSV_COLUMNS
+            .iter()
+            .map(|column| format!("{:?}", column))
+            .collect::<Vec<_>>();
+        writeln!(w, "{}", header.join(","))?;
+        for columns in self.trace.iter() {
+            let values = CSV_COLUMNS
+                .iter()
+                .map(|column| {
+                    columns[*column]
+                        .to_literal_ref()
+                        .expect("Keccak witness values must be constants")
+                        .to_biguint()
+                        .to_string()
+                })
+                .collect::<Vec<_>>();
+            writeln!(w, "{}", values.join(","))?;
+        }
+        Ok(())
+    }
+
     pub fn null_state(&mut self) {
         self.keccak_state = KeccakColumns::default();
     }
-    pub fn update_step(&mut self) {
-        match self.keccak_step {
+
+    /// Clears the steps executed so far, with their trace, their lookups and the digests
+    /// written to the syscall channel, along with the state of the last hash, keeping the
+    /// allocated buffers, so that the environment can be reused for a new witness.
+    /// The configuration (byte order, hash variant, parameters, preimage address, step limit
+    /// and recording options) is kept.
+    pub fn reset(&mut self) {
+        self.step_counter = 0;
+        self.trace.clear();
+        self.lookups.clear();
+        self.syscall_channel.clear();
+        self.start_hash();
+    }
+
+    /// Whether the current squeeze is followed by another permutation of the state,
+    /// because fewer bytes than requested by [Self::xof] have been squeezed so far
+    pub(crate) fn permutes_after_squeeze(&self) -> bool {
+        matches!(self.xof_len, Some(len) if self.xof_output.len() < len)
+    }
+
+    /// Starts a new hash from the zero root state, clearing the state left by the previous one.
+    /// The steps executed so far are kept, so that the steps of the new hash follow them.
+    pub(crate) fn start_hash(&mut self) {
+        self.prev_block.clear();
+        self.prev_block.resize(STATE_LEN, 0);
+        self.blocks.clear();
+        self.block_idx = 0;
+        self.pad_len = 0;
+        self.blocks_left_to_absorb = 0;
+        self.keccak_step = None;
+        #[cfg(debug_assertions)]
+        self.last_round_state.clear();
+        self.xof_output.clear();
+        self.xof_len = None;
+        self.round_trace.clear();
+        self.stream_buffer = None;
+        self.stream_block = None;
+        self.null_state();
+    }
+
+    /// Moves on to the next step of the hash and increments the step counter.
+    /// Fails without updating anything if the step counter would overflow,
+    /// or if there is a next step and no row is left for it, see [Self::set_max_steps].
+    pub fn update_step(&mut self) -> Result<(), KeccakError> {
+        let too_many_steps = KeccakError::TooManySteps {
+            step: self.step_counter,
+            max_steps: self.max_steps,
+        };
+        let step_counter = self
+            .step_counter
+            .checked_add(1)
+            .ok_or(too_many_steps.clone())?;
+        let mut blocks_left_to_absorb = self.blocks_left_to_absorb;
+        let keccak_step = match self.keccak_step {
             Some(step) => match step {
                 KeccakStep::Sponge(sponge) => match sponge {
-                    Sponge::Absorb(_) => self.keccak_step = Some(KeccakStep::Round(1)),
-
-                    Sponge::Squeeze => self.keccak_step = None,
+                    Sponge::Absorb(_) => Some(KeccakStep::Round(1)),
+
+                    Sponge::Squeeze => {
+                        if self.permutes_after_squeeze() {
+                            // The permutation is followed by a squeeze, as after the last absorb
+                            blocks_left_to_absorb = 1;
+                            Some(KeccakStep::Round(1))
+                        } else {
+                            None
+                        }
+                    }
                 },
                 KeccakStep::Round(round) => {
                     if round < ROUNDS as u64 {
-                        self.keccak_step = Some(KeccakStep::Round(round + 1));
+                        Some(KeccakStep::Round(round + 1))
                     } else {
-                        self.blocks_left_to_absorb -= 1;
-                        match self.blocks_left_to_absorb {
-                            0 => self.keccak_step = Some(KeccakStep::Sponge(Sponge::Squeeze)),
-                            1 => {
-                                self.keccak_step =
-                                    Some(KeccakStep::Sponge(Sponge::Absorb(Absorb::Last)))
-                            }
-                            _ => {
-                                self.keccak_step =
-                                    Some(KeccakStep::Sponge(Sponge::Absorb(Absorb::Middle)))
-                            }
+                        blocks_left_to_absorb -= 1;
+                        match blocks_left_to_absorb {
+                            0 => Some(KeccakStep::Sponge(Sponge::Squeeze)),
+                            1 => Some(KeccakStep::Sponge(Sponge::Absorb(Absorb::Last))),
+                            _ => Some(KeccakStep::Sponge(Sponge::Absorb(Absorb::Middle))),
                         }
                     }
                 }
             },
             None => panic!("No step to update"),
+        };
+        if keccak_step.is_some() && step_counter >= self.max_steps {
+            return Err(too_many_steps);
+        }
+        self.keccak_step = keccak_step;
+        self.blocks_left_to_absorb = blocks_left_to_absorb;
+        self.step_counter = step_counter;
+        #[cfg(test)]
+        if let Some(hook) = self.update_step_hook {
+            hook(self);
         }
-        self.step_counter += 1;
+        Ok(())
     }
 }
 
@@ -101,10 +606,6 @@ impl<Fp: Field> BoolOps for KeccakEnv<Fp> {
         x * x_inv - Self::Variable::one()
     }
 
-    fn xor(x: Self::Variable, y: Self::Variable) -> Self::Variable {
-        Self::is_one(x + y)
-    }
-
     fn or(x: Self::Variable, y: Self::Variable) -> Self::Variable {
         x.clone() + y.clone() - x * y
     }
@@ -166,12 +667,16 @@ pub(crate) trait KeccakEnvironment {
 
     fn is_pad(&self) -> Self::Variable;
 
+    fn is_permute(&self) -> Self::Variable;
+
     fn is_round(&self) -> Self::Variable;
 
     fn round(&self) -> Self::Variable;
 
     fn inverse_round(&self) -> Self::Variable;
 
+    fn block_address(&self) -> Self::Variable;
+
     fn length(&self) -> Self::Variable;
 
     fn two_to_pad(&self) -> Self::Variable;
@@ -186,6 +691,10 @@ pub(crate) trait KeccakEnvironment {
 
     fn block_in_padding(&self, i: usize) -> Self::Variable;
 
+    /// Returns the flags of the bytes in padding read as a binary number, which is
+    /// `2^pad_len - 1` when the padding is located at the end of the block
+    fn pad_at_end(&self) -> Self::Variable;
+
     fn round_constants(&self) -> &[Self::Variable];
 
     fn old_state(&self, i: usize) -> Self::Variable;
@@ -283,8 +792,8 @@ impl<Fp: Field> KeccakEnvironment for KeccakEnv<Fp> {
                         + Self::two_pow(3) * shifts(3, y.unwrap(), x.unwrap(), q.unwrap())
                 }
             }
-            100 => {
-                let shifts = grid!(100, shifts);
+            80 => {
+                let shifts = grid!(80, shifts);
                 shifts(0, x.unwrap(), q.unwrap())
                     + Self::two_pow(1) * shifts(1, x.unwrap(), q.unwrap())
                     + Self::two_pow(2) * shifts(2, x.unwrap(), q.unwrap())
@@ -313,7 +822,8 @@ impl<Fp: Field> KeccakEnvironment for KeccakEnv<Fp> {
     }
 
     fn is_sponge(&self) -> Self::Variable {
-        Self::xor(self.is_absorb().clone(), self.is_squeeze().clone())
+        // Absorb and squeeze are mutually exclusive, so their sum is boolean
+        self.is_absorb() + self.is_squeeze()
     }
 
     fn is_absorb(&self) -> Self::Variable {
@@ -332,6 +842,10 @@ impl<Fp: Field> KeccakEnvironment for KeccakEnv<Fp> {
         self.keccak_state[KeccakColumn::FlagPad].clone()
     }
 
+    fn is_permute(&self) -> Self::Variable {
+        self.keccak_state[KeccakColumn::FlagPermute].clone()
+    }
+
     fn is_round(&self) -> Self::Variable {
         Self::not(self.is_sponge())
     }
@@ -344,6 +858,10 @@ impl<Fp: Field> KeccakEnvironment for KeccakEnv<Fp> {
         self.keccak_state[KeccakColumn::InverseRound].clone()
     }
 
+    fn block_address(&self) -> Self::Variable {
+        self.keccak_state[KeccakColumn::BlockAddress].clone()
+    }
+
     fn length(&self) -> Self::Variable {
         self.keccak_state[KeccakColumn::FlagLength].clone()
     }
@@ -381,14 +899,18 @@ impl<Fp: Field> KeccakEnvironment for KeccakEnv<Fp> {
         let bytes = self.bytes_block(i);
         let flags = self.flags_block(i);
         assert_eq!(bytes.len(), flags.len());
-        let pad = bytes
+        bytes
             .iter()
             .zip(flags)
             .fold(Self::zero(), |acc, (byte, flag)| {
-                acc + byte.clone() * flag.clone() * Self::two_pow(8)
-            });
+                acc * Self::two_pow(8) + byte.clone() * flag.clone()
+            })
+    }
 
-        pad
+    fn pad_at_end(&self) -> Self::Variable {
+        (0..RATE_IN_BYTES).fold(Self::zero(), |acc, i| {
+            acc * Self::two() + self.in_padding(i).clone()
+        })
     }
 
     fn round_constants(&self) -> &[Self::Variable] {
@@ -424,7 +946,7 @@ impl<Fp: Field> KeccakEnvironment for KeccakEnv<Fp> {
         self.keccak_state[KeccakColumn::SpongeShifts(i)].clone()
     }
 
-    fn state_a(&self, x: usize, y: usize, q: usize) -> Self::Variable {
+    fn state_a(&self, y: usize, x: usize, q: usize) -> Self::Variable {
         self.keccak_state[KeccakColumn::ThetaStateA(y, x, q)].clone()
     }
 
@@ -41,6 +41,11 @@ pub(crate) trait Lookups {
     /// Adds the lookups required for the sponge
     fn lookups_sponge(&mut self);
 
+    /// Adds one lookup to the KeccakPreimage table per byte of the block being absorbed,
+    /// reading the pair (address, byte) from memory, unless the byte belongs to the padding.
+    /// The addresses follow the one of the first byte of the block, in [KeccakColumn::BlockAddress].
+    fn lookup_read_bytes(&mut self);
+
     /// Adds the lookups required for Theta in the round
     fn lookups_round_theta(&mut self);
 
@@ -63,7 +68,8 @@ impl<Fp: Field> Lookups for KeccakEnv<Fp> {
     }
 
     fn lookups(&mut self) {
-        // TODO: preimage lookups (somewhere else)
+        // PREIMAGE LOOKUPS
+        self.lookup_read_bytes();
 
         // SPONGE LOOKUPS
         self.lookups_sponge();
@@ -91,9 +97,10 @@ impl<Fp: Field> Lookups for KeccakEnv<Fp> {
             LookupTable::KeccakStepLookup,
             self.input_of_step(),
         ));
-        // (if not a squeeze) Input for next step is output of current step
+        // (if not a squeeze, or a squeeze followed by a permutation)
+        // Input for next step is output of current step
         self.add_lookup(Lookup::write_if(
-            Self::not(self.is_squeeze()),
+            Self::not(self.is_squeeze()) + self.is_permute(),
             LookupTable::KeccakStepLookup,
             self.output_of_step(),
         ));
@@ -167,6 +174,20 @@ impl<Fp: Field> Lookups for KeccakEnv<Fp> {
         }
     }
 
+    fn lookup_read_bytes(&mut self) {
+        let rate_in_bytes = self.params.rate_in_bytes();
+        for i in 0..rate_in_bytes {
+            self.add_lookup(Lookup::read_if(
+                self.is_absorb() * Self::not(self.in_padding(i)),
+                LookupTable::KeccakPreimageLookup,
+                vec![
+                    self.block_address() + Self::constant(i as u64),
+                    self.sponge_bytes(i),
+                ],
+            ));
+        }
+    }
+
     fn lookups_round_theta(&mut self) {
         for q in 0..QUARTERS {
             for x in 0..DIM {
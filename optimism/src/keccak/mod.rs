@@ -1,6 +1,8 @@
 use kimchi::circuits::{
     expr::{ConstantExpr, Expr},
-    polynomials::keccak::constants::{DIM, KECCAK_COLS, QUARTERS, STATE_LEN},
+    polynomials::keccak::constants::{
+        DIM, KECCAK_COLS, QUARTERS, RATE_IN_BYTES, ROUNDS, STATE_LEN,
+    },
 };
 
 use self::column::KeccakColumn;
@@ -17,10 +19,25 @@ pub(crate) const HASH_BYTELENGTH: usize = HASH_BITLENGTH / 8;
 pub(crate) const WORD_LENGTH_IN_BITS: usize = 64;
 pub(crate) const ZKVM_KECCAK_COLS_CURR: usize = KECCAK_COLS;
 pub(crate) const ZKVM_KECCAK_COLS_NEXT: usize = STATE_LEN;
+// StepCounter, FlagRound, FlagAbsorb, FlagSqueeze, FlagRoot, FlagPad, FlagLength, TwoToPad, InverseRound,
+// BlockAddress, FlagPermute
+pub(crate) const ZKVM_KECCAK_COLS_MODE: usize = 11;
+// FlagsBytes, PadSuffix, RoundConstants
+pub(crate) const ZKVM_KECCAK_COLS_FLAGS: usize =
+    ZKVM_KECCAK_COLS_MODE + RATE_IN_BYTES + PAD_SUFFIX_LEN + QUARTERS;
+pub(crate) const ZKVM_KECCAK_COLS: usize =
+    ZKVM_KECCAK_COLS_CURR + ZKVM_KECCAK_COLS_NEXT + ZKVM_KECCAK_COLS_FLAGS;
+pub(crate) const PAD_SUFFIX_LEN: usize = 5;
 pub(crate) const WORDS_IN_HASH: usize = HASH_BITLENGTH / WORD_LENGTH_IN_BITS;
 
 pub(crate) type E<F> = Expr<ConstantExpr<F>, KeccakColumn>;
 
+/// Number of steps of the hash of a preimage absorbed in the given number of blocks:
+/// one absorb and all the rounds per block, plus the final squeeze
+pub(crate) fn num_steps(num_blocks: usize) -> usize {
+    num_blocks * (ROUNDS + 1) + 1
+}
+
 fn grid_index(length: usize, i: usize, y: usize, x: usize, q: usize) -> usize {
     match length {
         5 => x,
@@ -32,6 +49,25 @@ fn grid_index(length: usize, i: usize, y: usize, x: usize, q: usize) -> usize {
     }
 }
 
+/// Inverse of [grid_index], returns the coordinates (i, y, x, q) of the given index
+fn grid_coords(length: usize, index: usize) -> (usize, usize, usize, usize) {
+    let q = index % QUARTERS;
+    let x = (index / QUARTERS) % DIM;
+    match length {
+        5 => (0, 0, index, 0),
+        20 => (0, 0, x, q),
+        80 => (index / (QUARTERS * DIM), 0, x, q),
+        100 => (0, index / (QUARTERS * DIM), x, q),
+        400 => (
+            index / (QUARTERS * DIM * DIM),
+            (index / (QUARTERS * DIM)) % DIM,
+            x,
+            q,
+        ),
+        _ => panic!("Invalid grid size"),
+    }
+}
+
 pub(crate) trait BoolOps {
     type Column;
     type Variable: std::ops::Mul<Self::Variable, Output = Self::Variable>
@@ -48,8 +84,6 @@ pub(crate) trait BoolOps {
 
     fn is_nonzero(x: Self::Variable, x_inv: Self::Variable) -> Self::Variable;
 
-    fn xor(x: Self::Variable, y: Self::Variable) -> Self::Variable;
-
     fn or(x: Self::Variable, y: Self::Variable) -> Self::Variable;
 
     fn either_false(x: Self::Variable, y: Self::Variable) -> Self::Variable;
@@ -1,26 +1,34 @@
 use super::{
     column::KeccakColumn,
-    environment::KeccakEnv,
+    environment::{HashVariant, KeccakEnv, RoundTrace},
     interpreter::{Absorb, KeccakInterpreter, KeccakStep, Sponge},
     lookups::Lookups,
-    DIM, HASH_BYTELENGTH, QUARTERS, WORDS_IN_HASH,
+    num_steps, DIM, HASH_BYTELENGTH, QUARTERS,
 };
 use ark_ff::Field;
+use ark_std::cfg_into_iter;
 use kimchi::{
     circuits::polynomials::keccak::{
-        constants::{CAPACITY_IN_BYTES, RATE_IN_BYTES, ROUNDS, STATE_LEN},
+        constants::{RATE_IN_BYTES, ROUNDS, SHIFTS},
         witness::{Chi, Iota, PiRho, Theta},
         Keccak,
     },
     grid,
 };
+#[cfg(feature = "parallel")]
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
-pub(crate) fn pad_blocks<Fp: Field>(pad_bytelength: usize) -> Vec<Fp> {
+pub(crate) fn pad_blocks<Fp: Field>(
+    pad_bytelength: usize,
+    rate_in_bytes: usize,
+    hash_variant: HashVariant,
+) -> Vec<Fp> {
     // Blocks to store padding. The first one uses at most 12 bytes, and the rest use at most 31 bytes.
+    // The padding ends with the rate, so the bytes after it are zero for rates below RATE_IN_BYTES.
     let mut blocks = vec![Fp::zero(); 5];
     let mut pad = [Fp::zero(); RATE_IN_BYTES];
-    pad[RATE_IN_BYTES - pad_bytelength] = Fp::one();
-    pad[RATE_IN_BYTES - 1] += Fp::from(0x80u8);
+    pad[rate_in_bytes - pad_bytelength] = Fp::from(hash_variant.domain_byte());
+    pad[rate_in_bytes - 1] += Fp::from(0x80u8);
     blocks[0] = pad
         .iter()
         .take(12)
@@ -37,59 +45,131 @@ pub(crate) fn pad_blocks<Fp: Field>(pad_bytelength: usize) -> Vec<Fp> {
     blocks
 }
 
-impl<Fp: Field> KeccakInterpreter for KeccakEnv<Fp> {
-    type Position = KeccakColumn;
-
-    type Variable = Fp;
+/// Whether the columns of the Theta, PiRho and Chi steps of a round are computed in parallel
+const PARALLEL_WITNESS: bool = cfg!(feature = "parallel");
 
-    fn hash(&mut self, preimage: Vec<u8>) {
-        // TODO: Read preimage for each block
+/// Collects the columns written for each of the `n` independent slices of a round step,
+/// spreading the slices across the rayon thread pool if the `parallel` feature is enabled.
+/// Either way, the columns are returned in order.
+fn collect_columns<F>(n: usize, f: F) -> Vec<(KeccakColumn, u64)>
+where
+    F: Fn(usize) -> Vec<(KeccakColumn, u64)> + Send + Sync,
+{
+    cfg_into_iter!(0..n).flat_map(f).collect()
+}
 
-        self.blocks_left_to_absorb = Keccak::num_blocks(preimage.len()) as u64;
+/// Theta-related columns of a round, sliced by `x`
+fn theta_columns(theta: &Theta, state_a: &[u64]) -> Vec<(KeccakColumn, u64)> {
+    collect_columns(DIM, |x| {
+        let state_a = grid!(100, state_a);
+        let mut columns = vec![(KeccakColumn::ThetaQuotientC(x), theta.quotient_c(x))];
+        for q in 0..QUARTERS {
+            columns.push((KeccakColumn::ThetaDenseC(x, q), theta.dense_c(x, q)));
+            columns.push((KeccakColumn::ThetaRemainderC(x, q), theta.remainder_c(x, q)));
+            columns.push((KeccakColumn::ThetaDenseRotC(x, q), theta.dense_rot_c(x, q)));
+            columns.push((
+                KeccakColumn::ThetaExpandRotC(x, q),
+                theta.expand_rot_c(x, q),
+            ));
+            for y in 0..DIM {
+                columns.push((KeccakColumn::ThetaStateA(y, x, q), state_a(y, x, q)));
+            }
+            for i in 0..QUARTERS {
+                columns.push((KeccakColumn::ThetaShiftsC(i, x, q), theta.shifts_c(i, x, q)));
+            }
+        }
+        columns
+    })
+}
 
-        // Configure first step depending on number of blocks remaining
-        self.keccak_step = if self.blocks_left_to_absorb == 1 {
-            Some(KeccakStep::Sponge(Sponge::Absorb(Absorb::FirstAndLast)))
-        } else {
-            Some(KeccakStep::Sponge(Sponge::Absorb(Absorb::First)))
-        };
-        self.step_counter = 0;
+/// PiRho-related columns of a round, sliced by `y`
+fn pirho_columns(pirho: &PiRho) -> Vec<(KeccakColumn, u64)> {
+    collect_columns(DIM, |y| {
+        let mut columns = vec![];
+        for x in 0..DIM {
+            for q in 0..QUARTERS {
+                columns.push((KeccakColumn::PiRhoDenseE(y, x, q), pirho.dense_e(y, x, q)));
+                columns.push((
+                    KeccakColumn::PiRhoQuotientE(y, x, q),
+                    pirho.quotient_e(y, x, q),
+                ));
+                columns.push((
+                    KeccakColumn::PiRhoRemainderE(y, x, q),
+                    pirho.remainder_e(y, x, q),
+                ));
+                columns.push((
+                    KeccakColumn::PiRhoDenseRotE(y, x, q),
+                    pirho.dense_rot_e(y, x, q),
+                ));
+                columns.push((
+                    KeccakColumn::PiRhoExpandRotE(y, x, q),
+                    pirho.expand_rot_e(y, x, q),
+                ));
+                for i in 0..QUARTERS {
+                    columns.push((
+                        KeccakColumn::PiRhoShiftsE(i, y, x, q),
+                        pirho.shifts_e(i, y, x, q),
+                    ));
+                }
+            }
+        }
+        columns
+    })
+}
 
-        // Root state is zero
-        self.prev_block = vec![0u64; STATE_LEN];
+/// Chi-related columns of a round, sliced by `i`
+fn chi_columns(chi: &Chi) -> Vec<(KeccakColumn, u64)> {
+    collect_columns(SHIFTS, |i| {
+        let mut columns = vec![];
+        for y in 0..DIM {
+            for x in 0..DIM {
+                for q in 0..QUARTERS {
+                    columns.push((
+                        KeccakColumn::ChiShiftsB(i, y, x, q),
+                        chi.shifts_b(i, y, x, q),
+                    ));
+                    columns.push((
+                        KeccakColumn::ChiShiftsSum(i, y, x, q),
+                        chi.shifts_sum(i, y, x, q),
+                    ));
+                }
+            }
+        }
+        columns
+    })
+}
 
-        // Pad preimage
-        self.padded = Keccak::pad(&preimage);
-        self.block_idx = 0;
-        self.pad_len = (self.padded.len() - preimage.len()) as u64;
+impl<Fp: Field> KeccakInterpreter for KeccakEnv<Fp> {
+    type Position = KeccakColumn;
 
-        // Run all steps of hash
-        while self.keccak_step.is_some() {
-            self.step();
-        }
+    type Variable = Fp;
 
-        // TODO: create READ lookup tables
-        // TODO: When finish, write hash to Syscall channel using `output_of_step()` on Squeeze step
+    fn hash(&mut self, preimage: Vec<u8>) {
+        self.run_hash(preimage, None);
     }
 
-    // FIXME: read preimage from memory and pad and expand
     fn step(&mut self) {
         // Reset columns to zeros to avoid conflicts between steps
         self.null_state();
 
-        // FIXME sparse notation
+        // The step begins from the expanded state left by the previous one in `prev_block`
 
         match self.keccak_step.unwrap() {
             KeccakStep::Sponge(typ) => self.run_sponge(typ),
             KeccakStep::Round(i) => self.run_round(i),
         }
         self.write_column(KeccakColumn::StepCounter, self.step_counter);
+        if self.record_trace {
+            self.trace.push(self.keccak_state.clone());
+        }
 
         // INTER-STEP CHANNEL
         // Write outputs for next step if not a squeeze and read inputs of curr step if not a root
         self.lookup_steps();
 
-        self.update_step();
+        if let Err(err) = self.update_step() {
+            panic!("{err}");
+        }
     }
 
     fn set_flag_root(&mut self) {
@@ -99,10 +179,16 @@ impl<Fp: Field> KeccakInterpreter for KeccakEnv<Fp> {
     fn set_flag_pad(&mut self) {
         self.write_column(KeccakColumn::FlagPad, 1);
         self.write_column(KeccakColumn::FlagLength, self.pad_len);
-        let pad_range = RATE_IN_BYTES - self.pad_len as usize..RATE_IN_BYTES;
+        self.write_column_field(KeccakColumn::TwoToPad, Fp::from(2u64).pow([self.pad_len]));
+        let rate_in_bytes = self.params.rate_in_bytes();
+        let pad_range = rate_in_bytes - self.pad_len as usize..rate_in_bytes;
         for i in pad_range {
             self.write_column(KeccakColumn::FlagsBytes(i), 1);
         }
+        let pad_blocks = pad_blocks::<Fp>(self.pad_len as usize, rate_in_bytes, self.hash_variant);
+        for (i, value) in pad_blocks.iter().enumerate() {
+            self.write_column_field(KeccakColumn::PadSuffix(i), *value);
+        }
     }
 
     fn set_flag_absorb(&mut self, absorb: Absorb) {
@@ -143,34 +229,70 @@ impl<Fp: Field> KeccakInterpreter for KeccakEnv<Fp> {
         // Compute witness values
         let state = self.prev_block.clone();
         let shifts = Keccak::shift(&state);
-        let dense = Keccak::collapse(&Keccak::reset(&shifts));
+        let dense = self.dense_state();
         let bytes = Keccak::bytestring(&dense);
+        let words_in_hash = self.params.words_in_hash();
 
         // Write squeeze-related columns
         for (i, value) in state.iter().enumerate() {
             self.write_column(KeccakColumn::SpongeOldState(i), *value);
         }
-        for (i, value) in bytes.iter().enumerate().take(HASH_BYTELENGTH) {
+        // The input columns of the squeeze must be the output columns of the last round
+        #[cfg(debug_assertions)]
+        assert_eq!(
+            self.keccak_state.curr_state(),
+            self.last_round_state,
+            "Squeeze input does not match the output of the last round"
+        );
+        for (i, value) in bytes.iter().enumerate().take(self.params.hash_bytelength()) {
             self.write_column(KeccakColumn::SpongeBytes(i), *value);
         }
-        for (i, value) in shifts.iter().enumerate().take(QUARTERS * WORDS_IN_HASH) {
-            self.write_column(KeccakColumn::SpongeShifts(i), *value);
+        // The constraints decompose each quarter of the digest with all of its shifts
+        for shift in 0..SHIFTS {
+            for i in 0..QUARTERS * words_in_hash {
+                let index = shift * QUARTERS * DIM * DIM + i;
+                self.write_column(KeccakColumn::SpongeShifts(index), shifts[index]);
+            }
         }
 
+        // Expose the hash output in the chosen byte order (the witness stays little endian)
+        self.hash_output =
+            Keccak::bytestring_with(&dense[0..QUARTERS * words_in_hash], self.endianness)
+                .iter()
+                .map(|byte| *byte as u8)
+                .collect();
+
         // Rest is zero thanks to null_state
 
-        // TODO: more updates to the env?
+        // Expose the whole rate for extendable output, see [KeccakEnv::xof]
+        self.xof_output.extend(
+            bytes
+                .iter()
+                .take(self.params.rate_in_bytes())
+                .map(|byte| *byte as u8),
+        );
+
+        if self.permutes_after_squeeze() {
+            // Pass the state on to the next permutation, see [KeccakEnv::xof]
+            self.write_column(KeccakColumn::FlagPermute, 1);
+            for (i, value) in state.iter().enumerate() {
+                self.write_column(KeccakColumn::SpongeXorState(i), *value);
+            }
+        } else {
+            // Write the hash to the syscall channel
+            let digest = self.hash_of_step();
+            self.syscall_channel.push(digest);
+        }
     }
 
     fn run_absorb(&mut self, absorb: Absorb) {
         self.set_flag_absorb(absorb);
 
         // Compute witness values
-        let ini_idx = self.block_idx * RATE_IN_BYTES;
-        let mut block = self.padded[ini_idx..ini_idx + RATE_IN_BYTES].to_vec();
+        let mut block = self.read_block();
 
         // Pad with zeros
-        block.append(&mut vec![0; CAPACITY_IN_BYTES]);
+        block.append(&mut vec![0; self.params.capacity_in_bytes()]);
 
         //    Round + Mode of Operation (Sponge)
         //    state -> permutation(state) -> state'
@@ -198,16 +320,24 @@ impl<Fp: Field> KeccakInterpreter for KeccakEnv<Fp> {
             self.write_column(KeccakColumn::SpongeNewState(i), new_state[i]);
             self.write_column(KeccakColumn::SpongeXorState(i), xor_state[i]);
         }
+        debug_assert_eq!(
+            self.check_sponge_xor_state(),
+            Ok(()),
+            "Sponge xor state does not match the sum of the old and new states"
+        );
         for (i, value) in bytes.iter().enumerate() {
             self.write_column(KeccakColumn::SpongeBytes(i), *value);
         }
         for (i, value) in shifts.iter().enumerate() {
             self.write_column(KeccakColumn::SpongeShifts(i), *value);
         }
-        let pad_blocks = pad_blocks::<Fp>(self.pad_len as usize);
-        for (i, value) in pad_blocks.iter().enumerate() {
-            self.write_column_field(KeccakColumn::PadSuffix(i), *value);
-        }
+        // Prove that the bytes of the block were read from memory
+        let rate_in_bytes = self.params.rate_in_bytes();
+        self.write_column(
+            KeccakColumn::BlockAddress,
+            self.preimage_address + (self.block_idx * rate_in_bytes) as u64,
+        );
+        self.lookup_read_bytes();
         // Rest is zero thanks to null_state
 
         // Update environment
@@ -224,14 +354,72 @@ impl<Fp: Field> KeccakInterpreter for KeccakEnv<Fp> {
         let state_f = self.run_chi(&state_b);
         let state_g = self.run_iota(&state_f, round as usize);
 
+        if self.record_intermediate {
+            self.round_trace.push(RoundTrace {
+                round,
+                state_e,
+                state_b,
+                state_f,
+                state_g: state_g.clone(),
+            });
+        }
+
+        // Keep the output columns of the round, to be checked against the input of the squeeze
+        #[cfg(debug_assertions)]
+        {
+            self.last_round_state.clear();
+            self.last_round_state
+                .extend_from_slice(self.keccak_state.next_state());
+        }
+
         // Update block for next step with the output of the round
         self.prev_block = state_g;
     }
 
     fn run_theta(&mut self, state_a: &[u64]) -> Vec<u64> {
         let theta = Theta::create(state_a);
+        self.write_theta(&theta, state_a, PARALLEL_WITNESS);
+        theta.state_e()
+    }
+
+    fn run_pirho(&mut self, state_e: &[u64]) -> Vec<u64> {
+        let pirho = PiRho::create(state_e);
+        self.write_pirho(&pirho, PARALLEL_WITNESS);
+        pirho.state_b()
+    }
+
+    fn run_chi(&mut self, state_b: &[u64]) -> Vec<u64> {
+        let chi = Chi::create(state_b);
+        self.write_chi(&chi, PARALLEL_WITNESS);
+        chi.state_f()
+    }
+
+    fn run_iota(&mut self, state_f: &[u64], round: usize) -> Vec<u64> {
+        let iota = Iota::create(state_f, round);
+        let state_g = iota.state_g();
+
+        // Update columns
+        for (i, g) in state_g.iter().enumerate() {
+            self.write_column(KeccakColumn::IotaStateG(i), *g);
+        }
+        for i in 0..QUARTERS {
+            self.write_column(KeccakColumn::RoundConstants(i), iota.rc(i));
+        }
+
+        state_g
+    }
+}
 
-        // Write Theta-related columns
+impl<Fp: Field> KeccakEnv<Fp> {
+    /// Writes the Theta-related columns of a round, collected in parallel if `parallel` is set
+    /// and the `parallel` feature is enabled
+    fn write_theta(&mut self, theta: &Theta, state_a: &[u64], parallel: bool) {
+        if parallel {
+            for (column, value) in theta_columns(theta, state_a) {
+                self.write_column(column, value);
+            }
+            return;
+        }
         for x in 0..DIM {
             self.write_column(KeccakColumn::ThetaQuotientC(x), theta.quotient_c(x));
             for q in 0..QUARTERS {
@@ -251,13 +439,17 @@ impl<Fp: Field> KeccakInterpreter for KeccakEnv<Fp> {
                 }
             }
         }
-        theta.state_e()
     }
 
-    fn run_pirho(&mut self, state_e: &[u64]) -> Vec<u64> {
-        let pirho = PiRho::create(state_e);
-
-        // Write PiRho-related columns
+    /// Writes the PiRho-related columns of a round, collected in parallel if `parallel` is set
+    /// and the `parallel` feature is enabled
+    fn write_pirho(&mut self, pirho: &PiRho, parallel: bool) {
+        if parallel {
+            for (column, value) in pirho_columns(pirho) {
+                self.write_column(column, value);
+            }
+            return;
+        }
         for y in 0..DIM {
             for x in 0..DIM {
                 for q in 0..QUARTERS {
@@ -287,14 +479,18 @@ impl<Fp: Field> KeccakInterpreter for KeccakEnv<Fp> {
                 }
             }
         }
-        pirho.state_b()
     }
 
-    fn run_chi(&mut self, state_b: &[u64]) -> Vec<u64> {
-        let chi = Chi::create(state_b);
-
-        // Write Chi-related columns
-        for i in 0..DIM {
+    /// Writes the Chi-related columns of a round, collected in parallel if `parallel` is set
+    /// and the `parallel` feature is enabled
+    fn write_chi(&mut self, chi: &Chi, parallel: bool) {
+        if parallel {
+            for (column, value) in chi_columns(chi) {
+                self.write_column(column, value);
+            }
+            return;
+        }
+        for i in 0..SHIFTS {
             for y in 0..DIM {
                 for x in 0..DIM {
                     for q in 0..QUARTERS {
@@ -310,21 +506,1199 @@ impl<Fp: Field> KeccakInterpreter for KeccakEnv<Fp> {
                 }
             }
         }
-        chi.state_f()
     }
 
-    fn run_iota(&mut self, state_f: &[u64], round: usize) -> Vec<u64> {
-        let iota = Iota::create(state_f, round);
-        let state_g = iota.state_g();
+    /// Runs all the steps of the hash of the `preimage`, starting from the zero root state
+    /// after the steps of the previous hashes. If `out_len` is given, the state is permuted
+    /// again after each squeeze until `out_len` bytes of extendable output have been squeezed.
+    fn run_hash(&mut self, preimage: Vec<u8>, out_len: Option<usize>) {
+        self.start_hash();
+        self.xof_len = out_len;
 
-        // Update columns
-        for (i, g) in state_g.iter().enumerate() {
-            self.write_column(KeccakColumn::IotaStateG(i), *g);
+        // Pad preimage and split it into the blocks read by each absorb.
+        // The padding takes at least one byte, so there is always at least one block:
+        // an empty preimage is a single block made only of padding (`pad_len == rate`).
+        self.pad_len = self.set_preimage(&preimage);
+        self.blocks_left_to_absorb = self.blocks.len() as u64;
+        debug_assert_eq!(
+            self.blocks.len(),
+            self.params.num_blocks(preimage.len()),
+            "Unexpected number of blocks for a preimage of {} bytes",
+            preimage.len()
+        );
+
+        // Configure first step depending on number of blocks remaining
+        self.keccak_step = if self.blocks_left_to_absorb == 1 {
+            Some(KeccakStep::Sponge(Sponge::Absorb(Absorb::FirstAndLast)))
+        } else {
+            Some(KeccakStep::Sponge(Sponge::Absorb(Absorb::First)))
+        };
+
+        // Run all steps of hash, guarding against a faulty step update looping forever
+        let rate = self.params.rate_in_bytes();
+        let extra_squeezes = out_len.map_or(0, |len| len.saturating_sub(1) / rate);
+        let max_steps =
+            num_steps(self.params.num_blocks(preimage.len())) + extra_squeezes * (ROUNDS + 1);
+        let mut steps = 0;
+        while self.keccak_step.is_some() {
+            assert!(
+                steps < max_steps,
+                "Keccak hash did not finish after the expected {max_steps} steps"
+            );
+            self.step();
+            steps += 1;
         }
-        for i in 0..QUARTERS {
-            self.write_column(KeccakColumn::RoundConstants(i), iota.rc(i));
+    }
+
+    /// Computes `out_len` bytes of extendable output of the `preimage`, padded according
+    /// to the hash variant (e.g. [HashVariant::Shake256]). After the hash, the state is
+    /// permuted again and squeezed until enough bytes of the rate have been exposed.
+    /// Each squeeze but the last one passes its state on to the rounds of the next
+    /// permutation through the step lookups, and only the last one writes to the
+    /// syscall channel.
+    pub fn xof(&mut self, preimage: Vec<u8>, out_len: usize) -> Vec<u8> {
+        self.run_hash(preimage, Some(out_len));
+        self.xof_output[..out_len].to_vec()
+    }
+
+    /// Appends `chunk` to the preimage being hashed in a streaming fashion, running an absorb
+    /// and its rounds for each full block that becomes available. Note that a full block is
+    /// never the last one, since the padding takes at least one byte. A new stream starts with
+    /// the first chunk after [Self::finalize] or [KeccakInterpreter::hash].
+    pub fn absorb_chunk(&mut self, chunk: &[u8]) {
+        let mut buffer = self.stream_buffer.take().unwrap_or_else(|| {
+            self.start_hash();
+            vec![]
+        });
+        buffer.extend_from_slice(chunk);
+
+        let rate_in_bytes = self.params.rate_in_bytes();
+        let mut start = 0;
+        while buffer.len() - start >= rate_in_bytes {
+            self.absorb_block(buffer[start..start + rate_in_bytes].to_vec(), false);
+            start += rate_in_bytes;
         }
+        buffer.drain(..start);
+        self.stream_buffer = Some(buffer);
+    }
 
-        state_g
+    /// Pads the bytes buffered by [Self::absorb_chunk], absorbs them as the last block
+    /// and squeezes the digest, which is the same as the one of [KeccakInterpreter::hash]
+    /// on the whole preimage
+    pub fn finalize(&mut self) -> [u8; HASH_BYTELENGTH] {
+        let remainder = self.stream_buffer.take().unwrap_or_else(|| {
+            self.start_hash();
+            vec![]
+        });
+        let padded = Keccak::pad_with_rate(
+            &remainder,
+            self.hash_variant.domain_byte(),
+            self.params.rate_in_bytes(),
+        );
+        self.pad_len = (padded.len() - remainder.len()) as u64;
+        self.absorb_block(padded, true);
+        self.digest()
+    }
+
+    /// Runs the absorb of the next block of the preimage and its rounds,
+    /// followed by the squeeze if it is the `last` one
+    fn absorb_block(&mut self, block: Vec<u8>, last: bool) {
+        let absorb = match (self.block_idx == 0, last) {
+            (true, true) => Absorb::FirstAndLast,
+            (true, false) => Absorb::First,
+            (false, true) => Absorb::Last,
+            (false, false) => Absorb::Middle,
+        };
+        self.stream_block = Some(block);
+        self.keccak_step = Some(KeccakStep::Sponge(Sponge::Absorb(absorb)));
+        // The step update after the last round chooses the next step from the blocks left
+        self.blocks_left_to_absorb = if last { 1 } else { 2 };
+        let steps = if last { ROUNDS + 2 } else { ROUNDS + 1 };
+        for _ in 0..steps {
+            self.step();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::keccak::{
+        column::KeccakColumns,
+        environment::{KeccakEnvironment, KeccakError, KeccakParams},
+        ArithOps, E, HASH_BYTELENGTH, PAD_SUFFIX_LEN, ZKVM_KECCAK_COLS,
+    };
+    use crate::mips::interpreter::{Lookup, LookupMode, LookupTable};
+    use ark_bn254::Fr as Fp;
+    use kimchi::circuits::{
+        expr::Literal,
+        polynomials::keccak::{constants::STATE_LEN, Endianness},
+    };
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use sha3::{Digest, Keccak256, Keccak384, Keccak512};
+
+    /// Returns an environment holding the row of the given step of the recorded trace,
+    /// in which the constraints can be evaluated
+    fn trace_env(env: &KeccakEnv<Fp>, step: usize) -> KeccakEnv<Fp> {
+        KeccakEnv {
+            keccak_state: env.trace[step].clone(),
+            ..KeccakEnv::default()
+        }
+    }
+
+    /// Prepares the environment to hash `preimage`, without executing any step
+    fn setup_hash(env: &mut KeccakEnv<Fp>, preimage: &[u8]) {
+        env.blocks_left_to_absorb = Keccak::num_blocks(preimage.len()) as u64;
+        env.keccak_step = if env.blocks_left_to_absorb == 1 {
+            Some(KeccakStep::Sponge(Sponge::Absorb(Absorb::FirstAndLast)))
+        } else {
+            Some(KeccakStep::Sponge(Sponge::Absorb(Absorb::First)))
+        };
+        env.prev_block = vec![0u64; STATE_LEN];
+        env.pad_len = env.set_preimage(preimage);
+        env.block_idx = 0;
+    }
+
+    /// Runs all steps of the hash of `preimage` until reaching the squeeze step, without executing it
+    #[cfg(debug_assertions)]
+    fn run_until_squeeze(env: &mut KeccakEnv<Fp>, preimage: &[u8]) {
+        setup_hash(env, preimage);
+        while env.keccak_step != Some(KeccakStep::Sponge(Sponge::Squeeze)) {
+            env.step();
+        }
+    }
+
+    #[test]
+    fn test_to_kimchi_witness() {
+        let mut env = KeccakEnv::<Fp>::default();
+        // The rows of the steps are not recorded by default
+        env.hash(vec![0x2a; 10]);
+        assert!(env.trace.is_empty());
+
+        env.reset();
+        env.set_record_trace(true);
+        env.hash(vec![0x2a; 10]);
+        let witness = env.to_kimchi_witness();
+        let steps = num_steps(Keccak::num_blocks(10));
+
+        assert_eq!(witness.len(), ZKVM_KECCAK_COLS);
+        for column in witness.iter() {
+            assert_eq!(column.len(), steps);
+        }
+        for index in 0..ZKVM_KECCAK_COLS {
+            assert_eq!(KeccakColumn::from_index(index).to_index(), index);
+        }
+        for (row, columns) in env.trace.iter().enumerate() {
+            assert_eq!(
+                witness[KeccakColumn::StepCounter.to_index()][row],
+                Fp::from(row as u64)
+            );
+            for index in [0, 150, 1000, ZKVM_KECCAK_COLS - 1] {
+                assert_eq!(
+                    Some(&witness[index][row]),
+                    columns[KeccakColumn::from_index(index)].to_literal_ref()
+                );
+            }
+        }
+        assert_eq!(
+            witness[KeccakColumn::FlagSqueeze.to_index()][steps - 1],
+            Fp::from(1u64)
+        );
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let mut env = KeccakEnv::<Fp>::default();
+        env.set_record_trace(true);
+        env.hash(vec![0x2a; 10]);
+        let mut csv = vec![];
+        env.to_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        let steps = num_steps(Keccak::num_blocks(10));
+
+        // Header line plus one line per step
+        assert_eq!(lines.len(), steps + 1);
+        assert!(lines[0].starts_with("StepCounter,FlagRound,"));
+        let columns = lines[0].split(',').count();
+        for (step, line) in lines.iter().skip(1).enumerate() {
+            let values = line.split(',').collect::<Vec<_>>();
+            assert_eq!(values.len(), columns);
+            assert_eq!(values[0], step.to_string());
+        }
+    }
+
+    #[test]
+    fn test_hash_output_endianness() {
+        let mut little = KeccakEnv::<Fp>::default();
+        little.set_record_trace(true);
+        little.hash(vec![0x2a; 10]);
+        let mut big = KeccakEnv::<Fp>::default();
+        big.set_record_trace(true);
+        big.set_endianness(Endianness::Big);
+        big.hash(vec![0x2a; 10]);
+
+        let mut reversed = little.hash_output();
+        reversed.reverse();
+        assert_eq!(little.hash_output().len(), HASH_BYTELENGTH);
+        assert_eq!(big.hash_output(), reversed);
+        // The witness is the same regardless of the byte order of the output
+        assert_eq!(little.to_kimchi_witness(), big.to_kimchi_witness());
+    }
+
+    #[test]
+    fn test_check_sponge_xor_state() {
+        let mut env = KeccakEnv::<Fp>::default();
+        setup_hash(&mut env, &[0x2a; 10]);
+        // Execute the absorb step only
+        env.step();
+        assert_eq!(env.check_sponge_xor_state(), Ok(()));
+
+        env.write_column(KeccakColumn::SpongeXorState(5), 0x1234);
+        assert_eq!(env.check_sponge_xor_state(), Err(5));
+    }
+
+    #[test]
+    #[should_panic(expected = "Keccak hash did not finish after the expected")]
+    fn test_hash_step_guard() {
+        let mut env = KeccakEnv::<Fp>::default();
+        // Simulate a step update that never reaches the end of the hash
+        env.update_step_hook = Some(|env| env.keccak_step = Some(KeccakStep::Round(1)));
+        env.hash(vec![0x2a; 10]);
+    }
+
+    #[test]
+    fn test_squeeze_input_is_last_round_output() {
+        let mut env = KeccakEnv::<Fp>::default();
+        env.set_record_trace(true);
+        env.set_record_intermediate(true);
+        env.hash(vec![0x2a; 200]);
+
+        // The input columns of the squeeze are the output columns of the last round
+        let squeeze = &env.trace[env.trace.len() - 1];
+        let last_round = &env.trace[env.trace.len() - 2];
+        assert_eq!(squeeze.curr_state(), last_round.next_state());
+        let state_g = &env.round_trace().last().unwrap().state_g;
+        for (i, value) in state_g.iter().enumerate() {
+            assert_eq!(
+                squeeze[KeccakColumn::SpongeOldState(i)].to_literal_ref(),
+                Some(&Fp::from(*value))
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Squeeze input does not match the output of the last round")]
+    fn test_squeeze_input_mismatch() {
+        let mut env = KeccakEnv::<Fp>::default();
+        run_until_squeeze(&mut env, &[0x2a; 10]);
+        // Tamper with the state threaded into the squeeze
+        env.prev_block[0] ^= 1;
+        env.step();
+    }
+
+    /// Hashes `inputs` random preimages of up to four blocks with [KeccakEnv::hash] and checks
+    /// the digests against the reference implementation of Keccak-256
+    fn fuzz_against_reference(seed: u64, inputs: usize) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..inputs {
+            let length = rng.gen_range(0..4 * RATE_IN_BYTES);
+            let preimage: Vec<u8> = (0..length).map(|_| rng.gen()).collect();
+
+            let mut env = KeccakEnv::<Fp>::default();
+            env.hash(preimage.clone());
+
+            let expected = Keccak256::digest(&preimage);
+            assert_eq!(
+                env.hash_output(),
+                expected.as_slice(),
+                "digest mismatch for a preimage of {length} bytes (seed {seed})"
+            );
+        }
+    }
+
+    #[test]
+    fn test_fuzz_against_reference() {
+        for seed in 0..4 {
+            fuzz_against_reference(seed, 100);
+        }
+    }
+
+    #[test]
+    fn test_against_reference_block_boundaries() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for length in [
+            0,
+            1,
+            RATE_IN_BYTES - 1,
+            RATE_IN_BYTES,
+            RATE_IN_BYTES + 1,
+            2 * RATE_IN_BYTES,
+            3 * RATE_IN_BYTES + 5,
+        ] {
+            let preimage: Vec<u8> = (0..length).map(|_| rng.gen()).collect();
+            let mut env = KeccakEnv::<Fp>::default();
+            env.hash(preimage.clone());
+            assert_eq!(
+                env.hash_output(),
+                Keccak256::digest(&preimage).as_slice(),
+                "digest mismatch for a preimage of {length} bytes"
+            );
+        }
+    }
+
+    #[test]
+    fn test_hash_reads_each_block() {
+        // Three blocks of preimage data
+        let preimage: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        let mut env = KeccakEnv::<Fp>::default();
+        env.hash(preimage);
+
+        assert_eq!(env.blocks.len(), 3);
+        assert_eq!(env.block_idx, 3);
+        assert_eq!(
+            hex::encode(env.hash_output()),
+            "a679e749a6af300c36e7ff2255d220864eab27b382f9cfdc5aa4d13563ba36ff"
+        );
+    }
+
+    #[test]
+    fn test_lookup_read_bytes() {
+        let preimage: Vec<u8> = (0..200).map(|i| i as u8).collect();
+        let mut env = KeccakEnv::<Fp>::default();
+        env.set_record_trace(true);
+        env.set_preimage_address(0x1000);
+        env.hash(preimage.clone());
+
+        // One lookup per byte of each of the two blocks, padding bytes included
+        let reads = env
+            .lookups
+            .iter()
+            .filter(|lookup| matches!(lookup.table_id, LookupTable::KeccakPreimageLookup))
+            .collect::<Vec<_>>();
+        assert_eq!(reads.len(), 2 * RATE_IN_BYTES);
+        for (i, lookup) in reads.iter().enumerate().take(preimage.len()) {
+            assert_eq!(
+                lookup.value[0].to_literal_ref(),
+                Some(&Fp::from(0x1000 + i as u64))
+            );
+            assert_eq!(
+                lookup.value[1].to_literal_ref(),
+                Some(&Fp::from(preimage[i]))
+            );
+        }
+
+        // The lookups of the constraints read the same bytes from the address column
+        let mut second = KeccakEnv::<Fp> {
+            keccak_state: env.trace[ROUNDS + 1].clone(),
+            ..KeccakEnv::default()
+        };
+        second.lookups();
+        let values = |lookup: &Lookup<E<Fp>>| {
+            lookup
+                .value
+                .iter()
+                .map(|value| value.to_literal_ref().copied())
+                .collect::<Vec<_>>()
+        };
+        let second_reads = second
+            .lookups
+            .iter()
+            .filter(|lookup| matches!(lookup.table_id, LookupTable::KeccakPreimageLookup))
+            .map(values)
+            .collect::<Vec<_>>();
+        let expected = reads[RATE_IN_BYTES..]
+            .iter()
+            .map(|lookup| values(lookup))
+            .collect::<Vec<_>>();
+        assert_eq!(second_reads, expected);
+    }
+
+    #[test]
+    fn test_hash_output_truncated() {
+        let mut env = KeccakEnv::<Fp>::default();
+        env.hash(vec![0x2a; 10]);
+        let output = env.hash_output();
+
+        for len in [0, 1, 20, HASH_BYTELENGTH] {
+            assert_eq!(env.hash_output_truncated(len), output[..len]);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot truncate")]
+    fn test_hash_output_truncated_too_long() {
+        let mut env = KeccakEnv::<Fp>::default();
+        env.hash(vec![0x2a; 10]);
+        env.hash_output_truncated(HASH_BYTELENGTH + 1);
+    }
+
+    #[test]
+    fn test_digest_against_reference() {
+        for preimage in [
+            vec![],
+            vec![0x2a; 10],
+            vec![0xff; RATE_IN_BYTES],
+            (0..300).map(|i| i as u8).collect(),
+        ] {
+            let mut env = KeccakEnv::<Fp>::default();
+            // The digest does not depend on the byte order of the hash output
+            env.set_endianness(Endianness::Big);
+            env.hash(preimage.clone());
+            assert_eq!(
+                env.digest().as_slice(),
+                Keccak256::digest(&preimage).as_slice()
+            );
+            assert_eq!(env.syscall_channel.len(), 1);
+        }
+    }
+
+    const ROUND_CONSTANTS: [u64; ROUNDS] = [
+        0x0000000000000001,
+        0x0000000000008082,
+        0x800000000000808A,
+        0x8000000080008000,
+        0x000000000000808B,
+        0x0000000080000001,
+        0x8000000080008081,
+        0x8000000000008009,
+        0x000000000000008A,
+        0x0000000000000088,
+        0x0000000080008009,
+        0x000000008000000A,
+        0x000000008000808B,
+        0x800000000000008B,
+        0x8000000000008089,
+        0x8000000000008003,
+        0x8000000000008002,
+        0x8000000000000080,
+        0x000000000000800A,
+        0x800000008000000A,
+        0x8000000080008081,
+        0x8000000000008080,
+        0x0000000080000001,
+        0x8000000080008008,
+    ];
+
+    /// Reference Keccak-f round on the 25 lanes of the state, indexed by `x + 5 * y`
+    fn reference_round(lanes: &[u64; 25], round: usize) -> [u64; 25] {
+        const ROTATIONS: [u32; 25] = [
+            0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61,
+            56, 14,
+        ];
+        // Theta
+        let c: Vec<u64> = (0..5)
+            .map(|x| (0..5).fold(0, |acc, y| acc ^ lanes[x + 5 * y]))
+            .collect();
+        let mut a = *lanes;
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                a[x + 5 * y] ^= d;
+            }
+        }
+        // Rho and Pi
+        let mut b = [0u64; 25];
+        for x in 0..5 {
+            for y in 0..5 {
+                b[y + 5 * ((2 * x + 3 * y) % 5)] = a[x + 5 * y].rotate_left(ROTATIONS[x + 5 * y]);
+            }
+        }
+        // Chi
+        let mut f = [0u64; 25];
+        for x in 0..5 {
+            for y in 0..5 {
+                f[x + 5 * y] = b[x + 5 * y] ^ (!b[(x + 1) % 5 + 5 * y] & b[(x + 2) % 5 + 5 * y]);
+            }
+        }
+        // Iota
+        f[0] ^= ROUND_CONSTANTS[round];
+        f
+    }
+
+    #[test]
+    fn test_rounds_against_reference() {
+        let preimage = b"abc".to_vec();
+        let mut env = KeccakEnv::<Fp>::default();
+        setup_hash(&mut env, &preimage);
+
+        // Absorb the single block into the zero state
+        env.step();
+        let block = Keccak::pad(&preimage);
+        let mut lanes: [u64; 25] = std::array::from_fn(|i| {
+            if i < RATE_IN_BYTES / 8 {
+                u64::from_le_bytes(block[8 * i..8 * i + 8].try_into().unwrap())
+            } else {
+                0
+            }
+        });
+
+        // Each round step outputs the same state as the reference round
+        for round in 1..=ROUNDS {
+            assert_eq!(env.keccak_step, Some(KeccakStep::Round(round as u64)));
+            env.step();
+            lanes = reference_round(&lanes, round - 1);
+
+            let dense = env.dense_state();
+            let expected: Vec<u64> = lanes
+                .iter()
+                .flat_map(|lane| (0..QUARTERS).map(move |q| (lane >> (16 * q)) & 0xffff))
+                .collect();
+            assert_eq!(dense, expected, "state mismatch after round {round}");
+        }
+    }
+
+    #[test]
+    fn test_hash_variants() {
+        for (variant, preimage, digest) in [
+            (
+                HashVariant::Keccak256,
+                "",
+                "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+            ),
+            (
+                HashVariant::Keccak256,
+                "abc",
+                "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+            ),
+            (
+                HashVariant::Sha3_256,
+                "",
+                "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a",
+            ),
+            (
+                HashVariant::Sha3_256,
+                "abc",
+                "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+            ),
+        ] {
+            let mut env = KeccakEnv::<Fp>::default();
+            env.set_hash_variant(variant);
+            env.hash(preimage.as_bytes().to_vec());
+            assert_eq!(
+                hex::encode(env.digest()),
+                digest,
+                "{variant:?} of {preimage:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_xof_against_reference() {
+        for (preimage, output) in [
+            (
+                "",
+                "46b9dd2b0ba88d13233b3feb743eeb24",
+            ),
+            (
+                "",
+                "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762fd75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be",
+            ),
+            (
+                "",
+                "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762fd75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be141e96616fb13957692cc7edd0b45ae3dc07223c8e92937bef84bc0eab862853349ec75546f58fb7c2775c38462c5010d846c185c15111e595522a6bcd16cf86f3d122109e3b1fdd943b6aec468a2d621a7c06c6a957c62b54dafc3be87567d677231395f6147293b68ceab7a9e0c58d864e8efde4e1b9a46cbe854713672f5caaae314ed9083dab",
+            ),
+            (
+                "abc",
+                "483366601360a8771c6863080cc4114d",
+            ),
+            (
+                "abc",
+                "483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739d5a15bef186a5386c75744c0527e1faa9f8726e462a12a4feb06bd8801e751e4",
+            ),
+            (
+                "abc",
+                "483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739d5a15bef186a5386c75744c0527e1faa9f8726e462a12a4feb06bd8801e751e41385141204f329979fd3047a13c5657724ada64d2470157b3cdc288620944d78dbcddbd912993f0913f164fb2ce95131a2d09a3e6d51cbfc622720d7a75c6334e8a2d7ec71a7cc29cf0ea610eeff1a588290a53000faa79932becec0bd3cd0b33a7e5d397fed1ada9442b99903f4dcfd8559ed3950faf40fe6f3b5d710ed3b677513771af6bfe119",
+            ),
+        ] {
+            let out_len = output.len() / 2;
+            let mut env = KeccakEnv::<Fp>::default();
+            env.set_hash_variant(HashVariant::Shake256);
+            assert_eq!(
+                hex::encode(env.xof(preimage.as_bytes().to_vec(), out_len)),
+                output,
+                "SHAKE256 of {preimage:?} with {out_len} bytes"
+            );
+            // 200 bytes exceed the rate, so the state is squeezed twice,
+            // but only the last squeeze writes to the syscall channel
+            let squeezes = if out_len > RATE_IN_BYTES { 2 } else { 1 };
+            assert_eq!(env.xof_output.len(), squeezes * RATE_IN_BYTES);
+            assert_eq!(env.syscall_channel.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_xof_steps_are_connected() {
+        let mut env = KeccakEnv::<Fp>::default();
+        env.set_hash_variant(HashVariant::Shake256);
+        env.set_record_trace(true);
+        env.xof(b"abc".to_vec(), 2 * RATE_IN_BYTES + 1);
+        // One absorb, and three permutations each followed by a squeeze
+        assert_eq!(env.trace.len(), 1 + 3 * (ROUNDS + 1));
+        assert_eq!(env.check_constraints(), Ok(()));
+
+        // Only the squeezes followed by a permutation pass their state on
+        let squeezes = env
+            .trace
+            .iter()
+            .filter(|columns| {
+                columns[KeccakColumn::FlagSqueeze].to_literal_ref() == Some(&Fp::from(1u8))
+            })
+            .map(|columns| columns[KeccakColumn::FlagPermute].to_literal_ref().copied())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            squeezes,
+            [
+                Some(Fp::from(1u8)),
+                Some(Fp::from(1u8)),
+                Some(Fp::from(0u8))
+            ]
+        );
+
+        // Each step but the root reads the output written by the step before it
+        let step_lookups = |read: bool| {
+            env.lookups
+                .iter()
+                .filter(|lookup| {
+                    matches!(lookup.table_id, LookupTable::KeccakStepLookup)
+                        && matches!(lookup.mode, LookupMode::Read) == read
+                        && lookup.magnitude.to_literal_ref() != Some(&Fp::from(0u8))
+                })
+                .map(|lookup| {
+                    lookup
+                        .value
+                        .iter()
+                        .map(|value| value.to_literal_ref().copied())
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        let reads = step_lookups(true);
+        assert_eq!(reads.len(), env.trace.len() - 1);
+        assert_eq!(reads, step_lookups(false));
+        assert_eq!(env.syscall_channel.len(), 1);
+    }
+
+    #[test]
+    fn test_public_inputs() {
+        let mut rng = StdRng::seed_from_u64(771);
+        for length in [0, 1, RATE_IN_BYTES - 1, RATE_IN_BYTES, 300] {
+            let preimage: Vec<u8> = (0..length).map(|_| rng.gen()).collect();
+            let mut env = KeccakEnv::<Fp>::default();
+            env.hash(preimage);
+            let (digest, len) = env.public_inputs();
+            let expected: Vec<Fp> = env.hash_output().into_iter().map(Fp::from).collect();
+            assert_eq!(digest, expected);
+            assert_eq!(len, Fp::from(length as u64));
+        }
+    }
+
+    #[test]
+    fn test_check_constraints_reports_corrupted_chi() {
+        let mut env = KeccakEnv::<Fp>::default();
+        env.set_record_trace(true);
+        env.hash(b"abc".to_vec());
+        assert_eq!(env.check_constraints(), Ok(()));
+
+        // The second step is the first round. The shift of index 1 only appears in the
+        // decomposition of the Chi input, so exactly one constraint must break.
+        let step = 1;
+        let column = KeccakColumn::ChiShiftsB(1, 0, 0, 0);
+        let value = *env.trace[step][column].to_literal_ref().unwrap();
+        env.trace[step][column] = Literal::literal(value + Fp::from(1u8));
+
+        let reported = env.check_constraints().unwrap_err();
+        assert_eq!(reported.len(), 1, "{reported:?}");
+        assert!(reported[0].starts_with(&format!("step {step}:")));
+    }
+
+    #[test]
+    fn test_sponge_flag() {
+        let mut env = KeccakEnv::<Fp>::default();
+        env.set_record_trace(true);
+        env.hash(b"abc".to_vec());
+        // The absorb, the first round and the squeeze
+        for (step, sponge) in [(0, 1u8), (1, 0), (ROUNDS + 1, 1)] {
+            let row = trace_env(&env, step);
+            assert_eq!(row.is_sponge().to_literal_ref(), Some(&Fp::from(sponge)));
+            assert_eq!(row.is_round().to_literal_ref(), Some(&Fp::from(1 - sponge)));
+        }
+    }
+
+    #[test]
+    fn test_state_a_coordinates() {
+        let mut env = KeccakEnv::<Fp>::default();
+        env.write_column(KeccakColumn::ThetaStateA(1, 2, 3), 0x2a);
+        // Theta sums the state over the rows y of each column x
+        assert_eq!(
+            env.state_a(1, 2, 3).to_literal_ref(),
+            Some(&Fp::from(0x2au8))
+        );
+        assert_eq!(env.state_a(2, 1, 3).to_literal_ref(), Some(&Fp::from(0u8)));
+    }
+
+    #[test]
+    fn test_from_theta_shifts() {
+        let mut env = KeccakEnv::<Fp>::default();
+        for i in 0..SHIFTS {
+            env.write_column(KeccakColumn::ThetaShiftsC(i, 2, 3), 1 << i);
+        }
+        // The 80 shifts of state C are composed by column and quarter
+        let composed =
+            KeccakEnv::<Fp>::from_shifts(env.vec_shifts_c(), None, None, Some(2), Some(3));
+        assert_eq!(composed.to_literal_ref(), Some(&Fp::from(0x55u8)));
+    }
+
+    #[test]
+    fn test_block_in_padding() {
+        let mut env = KeccakEnv::<Fp>::default();
+        env.set_record_trace(true);
+        env.hash(b"abc".to_vec());
+        // The bytes of the padding of each block, read big endian, are its pad suffix
+        let row = trace_env(&env, 0);
+        for i in 0..PAD_SUFFIX_LEN {
+            assert_eq!(
+                row.block_in_padding(i).to_literal_ref(),
+                row.pad_suffix(i).to_literal_ref(),
+                "block {i}"
+            );
+        }
+        assert_ne!(row.pad_suffix(4).to_literal_ref(), Some(&Fp::from(0u8)));
+    }
+
+    #[test]
+    fn test_two_to_pad() {
+        let mut env = KeccakEnv::<Fp>::default();
+        env.set_record_trace(true);
+        env.hash(b"abc".to_vec());
+        let row = trace_env(&env, 0);
+        assert_eq!(
+            row.two_to_pad().to_literal_ref(),
+            Some(&Fp::from(2u64).pow([env.pad_len]))
+        );
+    }
+
+    #[test]
+    fn test_pad_at_end() {
+        let mut env = KeccakEnv::<Fp>::default();
+        env.set_record_trace(true);
+        env.hash(b"abc".to_vec());
+        let row = trace_env(&env, 0);
+        let value = row.two_to_pad() - KeccakEnv::<Fp>::one() - row.pad_at_end();
+        assert_eq!(value.to_literal_ref(), Some(&Fp::from(0u8)));
+    }
+
+    #[test]
+    fn test_squeeze_shifts() {
+        let mut env = KeccakEnv::<Fp>::default();
+        env.set_record_trace(true);
+        env.hash(b"abc".to_vec());
+        let row = trace_env(&env, env.trace.len() - 1);
+        for i in 0..QUARTERS * 4 {
+            let composed =
+                KeccakEnv::<Fp>::from_shifts(row.vec_sponge_shifts(), Some(i), None, None, None);
+            assert_eq!(composed.to_literal_ref(), row.old_state(i).to_literal_ref());
+        }
+    }
+
+    #[test]
+    fn test_hashes_follow_each_other() {
+        let mut env = KeccakEnv::<Fp>::default();
+        env.set_record_trace(true);
+        env.hash(b"abc".to_vec());
+        let steps = env.step_counter as usize;
+        let lookups = env.lookups.len();
+        env.hash(vec![0x2a; 200]);
+
+        // The steps of the second hash follow the ones of the first one
+        let mut fresh = KeccakEnv::<Fp>::default();
+        fresh.hash(vec![0x2a; 200]);
+        assert_eq!(
+            env.step_counter as usize,
+            steps + fresh.step_counter as usize
+        );
+        assert_eq!(env.trace.len(), env.step_counter as usize);
+        assert_eq!(
+            env.trace[steps][KeccakColumn::StepCounter].to_literal_ref(),
+            Some(&Fp::from(steps as u64))
+        );
+        assert_eq!(env.lookups.len(), lookups + fresh.lookups.len());
+        assert_eq!(
+            env.syscall_channel,
+            vec![
+                Keccak256::digest(b"abc").to_vec(),
+                Keccak256::digest([0x2a; 200]).to_vec()
+            ]
+        );
+        assert_eq!(env.check_constraints(), Ok(()));
+    }
+
+    #[test]
+    fn test_reset_reuses_env() {
+        let mut rng = StdRng::seed_from_u64(772);
+        let mut env = KeccakEnv::<Fp>::default();
+        env.set_record_trace(true);
+        for length in [300, 0, 2 * RATE_IN_BYTES] {
+            let preimage: Vec<u8> = (0..length).map(|_| rng.gen()).collect();
+            env.hash(preimage.clone());
+
+            let mut fresh = KeccakEnv::<Fp>::default();
+            fresh.hash(preimage);
+            assert_eq!(env.digest(), fresh.digest());
+            assert_eq!(env.step_counter, fresh.step_counter);
+            // Only the steps of the last hash are kept
+            assert_eq!(env.trace.len(), env.step_counter as usize);
+            assert_eq!(env.syscall_channel.len(), 1);
+
+            env.reset();
+            assert_eq!(env.prev_block, vec![0; STATE_LEN]);
+            assert!(env.blocks.is_empty());
+            assert_eq!(env.keccak_step, None);
+            assert_eq!(env.step_counter, 0);
+            assert!(env.trace.is_empty());
+            assert!(env.lookups.is_empty());
+            assert!(env.syscall_channel.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_keccak_params() {
+        for (params, preimage, digest) in [
+            (
+                KeccakParams::Keccak384,
+                "",
+                "2c23146a63a29acf99e73b88f8c24eaa7dc60aa771780ccc006afbfa8fe2479b2dd2b21362337441ac12b515911957ff",
+            ),
+            (
+                KeccakParams::Keccak384,
+                "abc",
+                "f7df1165f033337be098e7d288ad6a2f74409d7a60b49c36642218de161b1f99f8c681e4afaf31a34db29fb763e3c28e",
+            ),
+            (
+                KeccakParams::Keccak512,
+                "",
+                "0eab42de4c3ceb9235fc91acffe746b29c29a8c366b7c60e4e67c466f36a4304c00fa9caf9d87976ba469bcbe06713b435f091ef2769fb160cdab33d3670680e",
+            ),
+            (
+                KeccakParams::Keccak512,
+                "abc",
+                "18587dc2ea106b9a1563e32b3312421ca164c7f1f07bc922a9c83d77cea3a1e5d0c69910739025372dc14ac9642629379540c17e2a65b19d77aa511a9d00bb96",
+            ),
+        ] {
+            let mut env = KeccakEnv::<Fp>::with_params(params);
+            env.hash(preimage.as_bytes().to_vec());
+            assert_eq!(
+                hex::encode(env.hash_output()),
+                digest,
+                "{params:?} of {preimage:?}"
+            );
+            assert_eq!(env.hash_output().len(), params.hash_bytelength());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "the Keccak constraints only support Keccak-256")]
+    fn test_keccak_params_witness_rejected() {
+        let mut env = KeccakEnv::<Fp>::with_params(KeccakParams::Keccak512);
+        env.set_record_trace(true);
+        env.hash(b"abc".to_vec());
+        env.to_kimchi_witness();
+    }
+
+    #[test]
+    fn test_keccak_params_block_boundaries() {
+        let mut rng = StdRng::seed_from_u64(773);
+        for params in [KeccakParams::Keccak384, KeccakParams::Keccak512] {
+            let rate = params.rate_in_bytes();
+            for length in [rate - 1, rate, rate + 1, 3 * rate] {
+                let preimage: Vec<u8> = (0..length).map(|_| rng.gen()).collect();
+                let mut env = KeccakEnv::<Fp>::with_params(params);
+                env.hash(preimage.clone());
+                let expected = match params {
+                    KeccakParams::Keccak384 => Keccak384::digest(&preimage).to_vec(),
+                    _ => Keccak512::digest(&preimage).to_vec(),
+                };
+                assert_eq!(env.hash_output(), expected, "{params:?} of {length} bytes");
+                assert_eq!(env.blocks.len(), params.num_blocks(length));
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trace() {
+        let preimage = b"abc".to_vec();
+        let mut env = KeccakEnv::<Fp>::default();
+        env.hash(preimage.clone());
+        assert!(env.round_trace().is_empty());
+
+        env.set_record_intermediate(true);
+        env.hash(preimage.clone());
+        let trace = env.round_trace();
+        assert_eq!(trace.len(), ROUNDS);
+        for (i, round) in trace.iter().enumerate() {
+            assert_eq!(round.round, i as u64 + 1);
+            for state in [
+                &round.state_e,
+                &round.state_b,
+                &round.state_f,
+                &round.state_g,
+            ] {
+                assert_eq!(state.len(), QUARTERS * DIM * DIM);
+            }
+        }
+
+        // The output of the last round is the state squeezed into the digest
+        let last = &trace[ROUNDS - 1].state_g;
+        assert_eq!(last, &env.prev_block);
+        let dense = Keccak::collapse(&Keccak::reset(&Keccak::shift(last)));
+        let bytes: Vec<u8> = Keccak::bytestring(&dense)
+            .iter()
+            .take(HASH_BYTELENGTH)
+            .map(|byte| *byte as u8)
+            .collect();
+        assert_eq!(bytes, Keccak256::digest(&preimage).as_slice());
+    }
+
+    #[test]
+    fn test_absorb_chunks() {
+        let mut rng = StdRng::seed_from_u64(775);
+        for length in [0, 100, RATE_IN_BYTES, 300] {
+            let preimage: Vec<u8> = (0..length).map(|_| rng.gen()).collect();
+            let mut batch = KeccakEnv::<Fp>::default();
+            batch.hash(preimage.clone());
+
+            for chunk_len in [1, 7, RATE_IN_BYTES] {
+                let mut env = KeccakEnv::<Fp>::default();
+                for chunk in preimage.chunks(chunk_len) {
+                    env.absorb_chunk(chunk);
+                }
+                let digest = env.finalize();
+                assert_eq!(
+                    digest,
+                    Keccak256::digest(&preimage).as_slice(),
+                    "{length} bytes in chunks of {chunk_len}"
+                );
+                assert_eq!(digest, batch.digest());
+                assert_eq!(env.pad_len, batch.pad_len);
+                assert_eq!(env.step_counter, batch.step_counter);
+                assert_eq!(env.public_inputs(), batch.public_inputs());
+                // The streamed blocks are absorbed without being kept
+                assert!(env.blocks.is_empty());
+            }
+        }
+
+        // The steps of a stream are the same as the ones of the batch hash
+        let preimage: Vec<u8> = (0..300).map(|_| rng.gen()).collect();
+        let mut batch = KeccakEnv::<Fp>::default();
+        batch.set_record_trace(true);
+        batch.hash(preimage.clone());
+        let mut env = KeccakEnv::<Fp>::default();
+        env.set_record_trace(true);
+        for chunk in preimage.chunks(7) {
+            env.absorb_chunk(chunk);
+        }
+        env.finalize();
+        assert_eq!(env.to_kimchi_witness(), batch.to_kimchi_witness());
+    }
+
+    #[test]
+    fn test_padding_boundaries() {
+        // The empty preimage is a single block made only of padding
+        let mut env = KeccakEnv::<Fp>::default();
+        env.set_record_trace(true);
+        env.hash(vec![]);
+        assert_eq!(env.blocks.len(), 1);
+        assert_eq!(env.pad_len, RATE_IN_BYTES as u64);
+        // A single absorb, both first and last, followed by the rounds and the squeeze
+        assert_eq!(env.trace.len(), ROUNDS + 2);
+        for flag in [
+            KeccakColumn::FlagAbsorb,
+            KeccakColumn::FlagRoot,
+            KeccakColumn::FlagPad,
+        ] {
+            assert_eq!(env.trace[0][flag].to_literal_ref(), Some(&Fp::from(1u8)));
+        }
+        assert_eq!(
+            hex::encode(env.digest()),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+
+        // One byte short of a block leaves a single byte of padding, holding both pad bits
+        let preimage = vec![0xab; RATE_IN_BYTES - 1];
+        let mut env = KeccakEnv::<Fp>::default();
+        env.hash(preimage.clone());
+        assert_eq!(env.blocks.len(), 1);
+        assert_eq!(env.pad_len, 1);
+        assert_eq!(env.blocks[0][RATE_IN_BYTES - 1], 0x81);
+        assert_eq!(env.digest(), Keccak256::digest(&preimage).as_slice());
+    }
+
+    #[test]
+    fn test_absorbs_around_rate() {
+        let flag = |columns: &KeccakColumns<E<Fp>>, column| {
+            columns[column].to_literal_ref() == Some(&Fp::from(1u8))
+        };
+        let mut rng = StdRng::seed_from_u64(777);
+        for (length, absorbs) in [
+            (RATE_IN_BYTES - 1, vec![Absorb::FirstAndLast]),
+            (RATE_IN_BYTES, vec![Absorb::First, Absorb::Last]),
+            (RATE_IN_BYTES + 1, vec![Absorb::First, Absorb::Last]),
+            (
+                2 * RATE_IN_BYTES,
+                vec![Absorb::First, Absorb::Middle, Absorb::Last],
+            ),
+        ] {
+            let preimage: Vec<u8> = (0..length).map(|_| rng.gen()).collect();
+            let mut env = KeccakEnv::<Fp>::default();
+            env.set_record_trace(true);
+            env.hash(preimage.clone());
+            assert_eq!(env.digest(), Keccak256::digest(&preimage).as_slice());
+            assert_eq!(env.trace.len(), num_steps(absorbs.len()));
+
+            // Each block is absorbed once, with the root and pad flags of its position
+            let rows: Vec<_> = env
+                .trace
+                .iter()
+                .filter(|columns| flag(columns, KeccakColumn::FlagAbsorb))
+                .collect();
+            assert_eq!(rows.len(), absorbs.len(), "length {length}");
+            for (columns, absorb) in rows.iter().zip(absorbs) {
+                let (root, pad) = match absorb {
+                    Absorb::First => (true, false),
+                    Absorb::Middle => (false, false),
+                    Absorb::Last => (false, true),
+                    Absorb::FirstAndLast => (true, true),
+                };
+                assert_eq!(flag(columns, KeccakColumn::FlagRoot), root);
+                assert_eq!(flag(columns, KeccakColumn::FlagPad), pad);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sponge_bytes_range_checked() {
+        let mut rng = StdRng::seed_from_u64(778);
+        let preimage: Vec<u8> = (0..RATE_IN_BYTES + 10).map(|_| rng.gen()).collect();
+        let mut env = KeccakEnv::<Fp>::default();
+        env.set_record_trace(true);
+        env.hash(preimage);
+        let squeeze = env.trace.len() - 1;
+
+        // The sponge lookups range check each of the SpongeBytes columns, capacity included,
+        // so that absorbs and squeezes need no other byte lookup, while rounds check none
+        for (step, checked) in [
+            (0, 2 * STATE_LEN),
+            (1, 0),
+            (ROUNDS + 1, 2 * STATE_LEN),
+            (squeeze, 2 * STATE_LEN),
+        ] {
+            let mut row = trace_env(&env, step);
+            row.lookups_sponge();
+            let ranges = row
+                .lookups
+                .iter()
+                .filter(|lookup| matches!(lookup.table_id, LookupTable::ByteLookup))
+                .collect::<Vec<_>>();
+            assert_eq!(ranges.len(), 2 * STATE_LEN);
+            for (i, lookup) in ranges.iter().enumerate() {
+                assert_eq!(lookup.magnitude, row.is_sponge());
+                assert_eq!(lookup.value, vec![row.sponge_bytes(i)]);
+            }
+            let active = ranges
+                .iter()
+                .filter(|lookup| lookup.magnitude.to_literal_ref() != Some(&Fp::from(0u8)))
+                .count();
+            assert_eq!(active, checked, "step {step}");
+        }
+    }
+
+    #[test]
+    fn test_chi_columns_shifts() {
+        let mut rng = StdRng::seed_from_u64(779);
+        let state_b: Vec<u64> = (0..STATE_LEN)
+            .map(|_| Keccak::expand(rng.gen::<u16>() as u64))
+            .collect();
+        let chi = Chi::create(&state_b);
+        // The shifts of B and of their sums, for each of the four shifts of the state
+        assert_eq!(chi_columns(&chi).len(), 2 * SHIFTS * STATE_LEN);
+    }
+
+    #[test]
+    fn test_parallel_round_columns() {
+        let mut rng = StdRng::seed_from_u64(779);
+        let state_a: Vec<u64> = (0..STATE_LEN)
+            .map(|_| Keccak::expand(rng.gen::<u16>() as u64))
+            .collect();
+
+        // One full round, with each step written both serially and in parallel
+        let theta = Theta::create(&state_a);
+        let pirho = PiRho::create(&theta.state_e());
+        let chi = Chi::create(&pirho.state_b());
+        let mut serial = KeccakEnv::<Fp>::default();
+        serial.write_theta(&theta, &state_a, false);
+        serial.write_pirho(&pirho, false);
+        serial.write_chi(&chi, false);
+        let mut parallel = KeccakEnv::<Fp>::default();
+        parallel.write_theta(&theta, &state_a, true);
+        parallel.write_pirho(&pirho, true);
+        parallel.write_chi(&chi, true);
+        assert_eq!(serial.keccak_state, parallel.keccak_state);
+
+        // Each column of the round is written exactly once
+        let mut columns: Vec<_> = [
+            theta_columns(&theta, &state_a),
+            pirho_columns(&pirho),
+            chi_columns(&chi),
+        ]
+        .concat()
+        .into_iter()
+        .map(|(column, _)| column.to_index())
+        .collect();
+        let len = columns.len();
+        columns.sort();
+        columns.dedup();
+        assert_eq!(columns.len(), len);
+    }
+
+    #[test]
+    fn test_step_counter_overflow() {
+        let mut env = KeccakEnv::<Fp>::default();
+        env.set_max_steps(3);
+        setup_hash(&mut env, &[0x2a; 10]);
+        env.step();
+
+        // The last step with a row left still moves on
+        assert_eq!(env.update_step(), Ok(()));
+        assert_eq!(env.step_counter, 2);
+        assert_eq!(env.keccak_step, Some(KeccakStep::Round(2)));
+
+        // The next one is rejected, leaving the environment untouched
+        assert_eq!(
+            env.update_step(),
+            Err(KeccakError::TooManySteps {
+                step: 2,
+                max_steps: 3
+            })
+        );
+        assert_eq!(env.step_counter, 2);
+        assert_eq!(env.keccak_step, Some(KeccakStep::Round(2)));
+
+        // A hash fits in exactly its number of steps, the squeeze using the last row
+        let steps = num_steps(1) as u64;
+        env.reset();
+        env.set_max_steps(steps);
+        env.hash(vec![0x2a; 10]);
+        assert_eq!(env.step_counter, steps);
+    }
+
+    #[test]
+    #[should_panic(expected = "The step counter overflows")]
+    fn test_step_counter_overflow_panics() {
+        let mut env = KeccakEnv::<Fp>::default();
+        setup_hash(&mut env, &[0x2a; 10]);
+        env.step_counter = u64::MAX;
+        env.step();
     }
 }
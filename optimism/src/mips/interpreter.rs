@@ -145,6 +145,8 @@ pub enum LookupTable {
     ByteLookup,
     // Input/Output of Keccak steps
     KeccakStepLookup,
+    // Dual-column table of the (address, byte) pairs of the Keccak preimages read from memory
+    KeccakPreimageLookup,
 }
 
 #[derive(Clone, Debug)]
@@ -1,5 +1,6 @@
 use ark_ff::Zero;
 use ark_poly::{univariate::DensePolynomial, Evaluations, Polynomial, Radix2EvaluationDomain as D};
+use ark_std::{cfg_into_iter, cfg_iter, cfg_iter_mut};
 use kimchi::circuits::domains::EvaluationDomains;
 use kimchi::plonk_sponge::FrSponge;
 use kimchi::{curve::KimchiCurve, groupmap::GroupMap};
@@ -13,6 +14,7 @@ use poly_commitment::{
     OpenProof, SRS as _,
 };
 use rand::thread_rng;
+#[cfg(feature = "parallel")]
 use rayon::iter::{
     FromParallelIterator, IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator,
     IntoParallelRefMutIterator, ParallelIterator,
@@ -25,6 +27,62 @@ pub struct WitnessColumns<G> {
     pub error: G,
 }
 
+impl<G> IntoIterator for WitnessColumns<G> {
+    type IntoIter = std::vec::IntoIter<G>;
+    type Item = G;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let mut iter_contents = Vec::with_capacity(crate::mips::witness::SCRATCH_SIZE + 2);
+        iter_contents.extend(self.scratch);
+        iter_contents.push(self.instruction_counter);
+        iter_contents.push(self.error);
+        iter_contents.into_iter()
+    }
+}
+
+impl<G: std::fmt::Debug> FromIterator<G> for WitnessColumns<G> {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = G>,
+    {
+        let mut iter_contents = iter.into_iter().collect::<Vec<_>>();
+        let error = iter_contents.pop().unwrap();
+        let instruction_counter = iter_contents.pop().unwrap();
+        WitnessColumns {
+            scratch: iter_contents.try_into().unwrap(),
+            instruction_counter,
+            error,
+        }
+    }
+}
+
+impl<'data, G> IntoIterator for &'data WitnessColumns<G> {
+    type IntoIter = std::vec::IntoIter<&'data G>;
+    type Item = &'data G;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let mut iter_contents = Vec::with_capacity(crate::mips::witness::SCRATCH_SIZE + 2);
+        iter_contents.extend(self.scratch.iter());
+        iter_contents.push(&self.instruction_counter);
+        iter_contents.push(&self.error);
+        iter_contents.into_iter()
+    }
+}
+
+impl<'data, G> IntoIterator for &'data mut WitnessColumns<G> {
+    type IntoIter = std::vec::IntoIter<&'data mut G>;
+    type Item = &'data mut G;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let mut iter_contents = Vec::with_capacity(crate::mips::witness::SCRATCH_SIZE + 2);
+        iter_contents.extend(self.scratch.iter_mut());
+        iter_contents.push(&mut self.instruction_counter);
+        iter_contents.push(&mut self.error);
+        iter_contents.into_iter()
+    }
+}
+
+#[cfg(feature = "parallel")]
 impl<G> IntoParallelIterator for WitnessColumns<G>
 where
     Vec<G>: IntoParallelIterator,
@@ -41,6 +99,7 @@ where
     }
 }
 
+#[cfg(feature = "parallel")]
 impl<G: Send + std::fmt::Debug> FromParallelIterator<G> for WitnessColumns<G> {
     fn from_par_iter<I>(par_iter: I) -> Self
     where
@@ -57,6 +116,7 @@ impl<G: Send + std::fmt::Debug> FromParallelIterator<G> for WitnessColumns<G> {
     }
 }
 
+#[cfg(feature = "parallel")]
 impl<'data, G> IntoParallelIterator for &'data WitnessColumns<G>
 where
     Vec<&'data G>: IntoParallelIterator,
@@ -73,6 +133,7 @@ where
     }
 }
 
+#[cfg(feature = "parallel")]
 impl<'data, G> IntoParallelIterator for &'data mut WitnessColumns<G>
 where
     Vec<&'data mut G>: IntoParallelIterator,
@@ -130,8 +191,7 @@ pub fn fold<
     <OpeningProof as poly_commitment::OpenProof<G>>::SRS: std::marker::Sync,
 {
     let commitments = {
-        inputs
-            .par_iter()
+        cfg_into_iter!(inputs)
             .map(|evals: &Vec<G::ScalarField>| {
                 let evals = Evaluations::<G::ScalarField, D<G::ScalarField>>::from_vec_and_domain(
                     evals.clone(),
@@ -150,14 +210,11 @@ pub fn fold<
     let scaling_challenge = ScalarChallenge(fq_sponge.challenge());
     let (_, endo_r) = G::endos();
     let scaling_challenge = scaling_challenge.to_field(endo_r);
-    accumulator
-        .evaluations
-        .par_iter_mut()
-        .zip(inputs.par_iter())
+    cfg_into_iter!(&mut accumulator.evaluations)
+        .zip(cfg_into_iter!(inputs))
         .for_each(|(accumulator, inputs)| {
-            accumulator
-                .par_iter_mut()
-                .zip(inputs.par_iter())
+            cfg_iter_mut!(accumulator)
+                .zip(cfg_iter!(inputs))
                 .for_each(|(accumulator, input)| {
                     *accumulator = *input + scaling_challenge * *accumulator
                 });
@@ -188,7 +245,9 @@ where
             Evaluations::<G::ScalarField, D<G::ScalarField>>::from_vec_and_domain(evals, domain.d1)
                 .interpolate()
         };
-        let scratch = scratch.into_par_iter().map(eval_col).collect::<Vec<_>>();
+        let scratch = cfg_into_iter!(Vec::from(scratch))
+            .map(eval_col)
+            .collect::<Vec<_>>();
         WitnessColumns {
             scratch: scratch.try_into().unwrap(),
             instruction_counter: eval_col(instruction_counter),
@@ -202,7 +261,7 @@ where
             error,
         } = &polys;
         let comm = |poly: &DensePolynomial<G::ScalarField>| srs.commit_non_hiding(poly, 1, None);
-        let scratch = scratch.par_iter().map(comm).collect::<Vec<_>>();
+        let scratch = cfg_iter!(scratch).map(comm).collect::<Vec<_>>();
         WitnessColumns {
             scratch: scratch.try_into().unwrap(),
             instruction_counter: comm(instruction_counter),
@@ -229,7 +288,7 @@ where
             error,
         } = &polys;
         let comm = |poly: &DensePolynomial<G::ScalarField>| poly.evaluate(point);
-        let scratch = scratch.par_iter().map(comm).collect::<Vec<_>>();
+        let scratch = cfg_iter!(scratch).map(comm).collect::<Vec<_>>();
         WitnessColumns {
             scratch: scratch.try_into().unwrap(),
             instruction_counter: comm(instruction_counter),
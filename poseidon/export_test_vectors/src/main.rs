@@ -1,22 +1,28 @@
 mod vectors;
 use inner::*;
 
-/// "Usage: cargo run --all-features --bin export_test_vectors -- [hex|b10] [legacy|kimchi] <OUTPUT_FILE>",
+/// "Usage: cargo run --all-features --bin export_test_vectors -- [hex|b10|jsonl|cbor] [legacy|kimchi] [fp|fq]? <OUTPUT_FILE>",
+/// "   or: cargo run --all-features --bin export_test_vectors -- verify [hex|b10|jsonl|cbor] <INPUT_FILE>",
 fn main() {
     inner::main();
 }
 
 mod inner {
     use super::vectors;
+    use serde::{Deserialize, Serialize};
     use std::env;
     use std::fs::File;
     use std::io::{self, Write};
     use std::str::FromStr;
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Copy)]
     pub enum Mode {
         Hex,
         B10,
+        /// JSON Lines, with field elements encoded in hex
+        JsonL,
+        /// CBOR, with field elements encoded as raw bytes
+        Cbor,
     }
 
     impl FromStr for Mode {
@@ -26,12 +32,14 @@ mod inner {
             match input.to_lowercase().as_str() {
                 "b10" => Ok(Mode::B10),
                 "hex" => Ok(Mode::Hex),
+                "jsonl" => Ok(Mode::JsonL),
+                "cbor" => Ok(Mode::Cbor),
                 _ => Err(()),
             }
         }
     }
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Copy)]
     pub enum ParamType {
         Legacy,
         Kimchi,
@@ -49,10 +57,62 @@ mod inner {
         }
     }
 
+    /// The field over which the vectors are generated, [Field::Fp] by default
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum Field {
+        #[default]
+        Fp,
+        Fq,
+    }
+
+    impl Field {
+        /// whether this is the default field, which is left out of the serialized vectors
+        /// so that [Field::Fp] vectors are identical to the ones written before fields existed
+        pub fn is_fp(&self) -> bool {
+            *self == Field::Fp
+        }
+    }
+
+    impl FromStr for Field {
+        type Err = ();
+
+        fn from_str(input: &str) -> Result<Self, Self::Err> {
+            match input.to_lowercase().as_str() {
+                "fp" => Ok(Field::Fp),
+                "fq" => Ok(Field::Fq),
+                _ => Err(()),
+            }
+        }
+    }
+
     pub(crate) fn main() {
         let args: Vec<String> = env::args().collect();
         match args.len() {
-            4 => {
+            4 if args[1] == "verify" => {
+                // parse command-line args
+                let mode: Mode = args
+                    .get(2)
+                    .expect("missing mode")
+                    .parse()
+                    .expect("invalid mode");
+                let input_file = args.get(3).expect("missing file");
+
+                // read vectors from input file
+                let reader = File::open(input_file).expect("could not open file");
+                let vectors =
+                    vectors::read_vectors(reader, &mode).expect("could not parse test vectors");
+
+                // check vectors against the current implementation
+                match vectors::verify_vectors(&vectors, mode) {
+                    Ok(()) => println!("all test vectors match"),
+                    Err(indices) => {
+                        println!("mismatching test vectors: {:?}", indices);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            4 | 5 => {
                 // parse command-line args
                 let mode: Mode = args
                     .get(1)
@@ -64,24 +124,37 @@ mod inner {
                     .expect("missing param type")
                     .parse()
                     .expect("invalid param type");
-                let output_file = args.get(3).expect("missing file");
+                let field: Field = match args.len() {
+                    5 => args[3].parse().expect("invalid field"),
+                    _ => Field::default(),
+                };
+                let output_file = args.last().expect("missing file");
 
                 // generate vectors
-                let vectors = vectors::generate(mode, param_type);
+                let vectors = vectors::generate(mode, param_type, field);
 
                 // save to output file
                 let writer: Box<dyn Write> = match output_file.as_str() {
                     "-" => Box::new(io::stdout()),
                     _ => Box::new(File::create(output_file).expect("could not create file")),
                 };
-                serde_json::to_writer_pretty(writer, &vectors).expect("could not write to file");
+                vectors::write_vectors(&vectors, &mode, writer).expect("could not write to file");
             }
             _ => {
                 println!(
-                "usage: cargo run -p export_test_vectors -- [{:?}|{:?}] [legacy|kimchi] <OUTPUT_FILE>",
+                "usage: cargo run -p export_test_vectors -- [{:?}|{:?}|{:?}|{:?}] [legacy|kimchi] [fp|fq]? <OUTPUT_FILE>",
                 Mode::Hex,
                 Mode::B10,
+                Mode::JsonL,
+                Mode::Cbor,
             );
+                println!(
+                    "   or: cargo run -p export_test_vectors -- verify [{:?}|{:?}|{:?}|{:?}] <INPUT_FILE>",
+                    Mode::Hex,
+                    Mode::B10,
+                    Mode::JsonL,
+                    Mode::Cbor,
+                );
             }
         }
     }
@@ -1,15 +1,17 @@
-use super::{Mode, ParamType};
-use ark_ff::{fields::PrimeField as _, UniformRand as _};
+use super::{Field, Mode, ParamType};
+use ark_ff::fields::PrimeField;
 use ark_serialize::CanonicalSerialize as _;
-use mina_curves::pasta::Fp;
+use mina_curves::pasta::{Fp, Fq};
 use mina_poseidon::{
-    constants::{self, SpongeConstants},
-    pasta,
-    poseidon::{ArithmeticSponge as Poseidon, ArithmeticSpongeParams, Sponge as _},
+    constants, pasta,
+    poseidon::{self, ArithmeticSpongeParams},
 };
 use num_bigint::BigUint;
 use rand::{prelude::*, Rng};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, Bytes};
+use std::error::Error;
+use std::io::{BufRead, BufReader, Read, Write};
 
 //
 // generate different test vectors depending on [ParamType]
@@ -19,95 +21,451 @@ use serde::Serialize;
 // structs
 //
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TestVectors {
     name: String,
+    /// field of the vectors, [Field::Fp] if missing
+    #[serde(default, skip_serializing_if = "Field::is_fp")]
+    field: Field,
     test_vectors: Vec<TestVector>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TestVector {
+    /// stable identifier of the vector, e.g. `basic/len3`
+    #[serde(default)]
+    id: String,
     input: Vec<String>,
     output: String,
 }
 
+/// first line of the JSON Lines format, followed by one [TestVector] per line
+#[derive(Debug, Serialize, Deserialize)]
+struct JsonLHeader {
+    name: String,
+    #[serde(default, skip_serializing_if = "Field::is_fp")]
+    field: Field,
+}
+
+/// [TestVectors] as written in the CBOR format, with raw bytes instead of hex strings
+#[derive(Debug, Serialize, Deserialize)]
+struct CborTestVectors {
+    name: String,
+    #[serde(default, skip_serializing_if = "Field::is_fp")]
+    field: Field,
+    test_vectors: Vec<CborTestVector>,
+}
+
+#[serde_as]
+#[derive(Debug, Serialize, Deserialize)]
+struct CborTestVector {
+    #[serde(default)]
+    id: String,
+    #[serde_as(as = "Vec<Bytes>")]
+    input: Vec<Vec<u8>>,
+    #[serde_as(as = "Bytes")]
+    output: Vec<u8>,
+}
+
+#[cfg(test)]
+impl TestVectors {
+    /// returns the test vector with the given `id`, if any
+    pub fn get(&self, id: &str) -> Option<&TestVector> {
+        self.test_vectors.iter().find(|vector| vector.id == id)
+    }
+}
+
 //
 // logic
 //
 
-/// Computes the poseidon hash of several field elements.
-/// Uses the 'basic' configuration with N states and M rounds.
-fn poseidon<SC: SpongeConstants>(input: &[Fp], params: &'static ArithmeticSpongeParams<Fp>) -> Fp {
-    let mut s = Poseidon::<Fp, SC>::new(params);
-    s.absorb(input);
-    s.squeeze()
+/// the sponge constants and parameters used to hash, chosen at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpongeVariant {
+    /// [constants::PlonkSpongeConstantsLegacy] with the legacy parameters
+    Basic,
+    /// [constants::PlonkSpongeConstantsKimchi] (15 wires) with the kimchi parameters
+    Fifteen,
+}
+
+impl From<ParamType> for SpongeVariant {
+    fn from(param_type: ParamType) -> Self {
+        match param_type {
+            ParamType::Legacy => SpongeVariant::Basic,
+            ParamType::Kimchi => SpongeVariant::Fifteen,
+        }
+    }
+}
+
+impl SpongeVariant {
+    /// computes the poseidon hash of `input` with the constants and parameters of this variant
+    fn hash<F: PastaField>(self, input: &[F]) -> F {
+        let params = F::static_params(self);
+        match self {
+            SpongeVariant::Basic => {
+                poseidon::hash::<F, constants::PlonkSpongeConstantsLegacy>(params, input)
+            }
+            SpongeVariant::Fifteen => {
+                poseidon::hash::<F, constants::PlonkSpongeConstantsKimchi>(params, input)
+            }
+        }
+    }
+}
+
+/// a pasta field for which poseidon parameters are available
+trait PastaField: PrimeField {
+    /// returns the sponge parameters of `variant` over this field
+    fn static_params(variant: SpongeVariant) -> &'static ArithmeticSpongeParams<Self>;
+}
+
+impl PastaField for Fp {
+    fn static_params(variant: SpongeVariant) -> &'static ArithmeticSpongeParams<Self> {
+        match variant {
+            SpongeVariant::Basic => pasta::fp_legacy::static_params(),
+            SpongeVariant::Fifteen => pasta::fp_kimchi::static_params(),
+        }
+    }
+}
+
+impl PastaField for Fq {
+    fn static_params(variant: SpongeVariant) -> &'static ArithmeticSpongeParams<Self> {
+        match variant {
+            SpongeVariant::Basic => pasta::fq_legacy::static_params(),
+            SpongeVariant::Fifteen => pasta::fq_kimchi::static_params(),
+        }
+    }
 }
 
 /// generates a vector of `length` field elements
-fn rand_fields(rng: &mut impl Rng, length: u8) -> Vec<Fp> {
+fn rand_fields<F: PrimeField>(rng: &mut impl Rng, length: u8) -> Vec<F> {
     let mut fields = vec![];
     for _ in 0..length {
-        let fe = Fp::rand(rng);
+        let fe = F::rand(rng);
         fields.push(fe)
     }
     fields
 }
 
-/// creates a set of test vectors
-pub fn generate(mode: Mode, param_type: ParamType) -> TestVectors {
+/// serializes a field element according to `mode`
+fn encode<F: PrimeField>(elem: F, mode: &Mode) -> String {
+    let mut bytes = vec![];
+    elem.into_repr()
+        .serialize(&mut bytes)
+        .expect("canonical serialiation should work");
+    match mode {
+        // CBOR vectors are kept in hex until they are written as raw bytes
+        Mode::Hex | Mode::JsonL | Mode::Cbor => hex::encode(&bytes),
+        Mode::B10 => BigUint::from_bytes_le(&bytes).to_string(),
+    }
+}
+
+/// deserializes a field element encoded according to `mode`
+fn decode<F: PrimeField>(elem: &str, mode: &Mode) -> Option<F> {
+    let bytes = match mode {
+        Mode::Hex | Mode::JsonL | Mode::Cbor => hex::decode(elem).ok()?,
+        Mode::B10 => elem.parse::<BigUint>().ok()?.to_bytes_le(),
+    };
+    Some(F::from_le_bytes_mod_order(&bytes))
+}
+
+/// creates a set of test vectors over `field`
+pub fn generate(mode: Mode, param_type: ParamType, field: Field) -> TestVectors {
+    let test_vectors = match field {
+        Field::Fp => generate_vectors::<Fp>(&mode, &param_type),
+        Field::Fq => generate_vectors::<Fq>(&mode, &param_type),
+    };
+
+    let name = match param_type {
+        ParamType::Legacy => "legacy",
+        ParamType::Kimchi => "kimchi",
+    }
+    .into();
+
+    TestVectors {
+        name,
+        field,
+        test_vectors,
+    }
+}
+
+/// creates the test vectors over `F`, from a fixed seed
+fn generate_vectors<F: PastaField>(mode: &Mode, param_type: &ParamType) -> Vec<TestVector> {
     let mut rng = &mut rand::rngs::StdRng::from_seed([0u8; 32]);
     let mut test_vectors = vec![];
 
     // generate inputs of different lengths
     for length in 0..6 {
         // generate input & hash
-        let input = rand_fields(&mut rng, length);
-        let output = match param_type {
-            ParamType::Legacy => poseidon::<constants::PlonkSpongeConstantsLegacy>(
-                &input,
-                pasta::fp_legacy::static_params(),
-            ),
-            ParamType::Kimchi => poseidon::<constants::PlonkSpongeConstantsKimchi>(
-                &input,
-                pasta::fp_kimchi::static_params(),
-            ),
-        };
+        let input = rand_fields::<F>(&mut rng, length);
+        let output = SpongeVariant::from(*param_type).hash(&input);
 
         // serialize input & output
-        let input = input
-            .into_iter()
-            .map(|elem| {
-                let mut input_bytes = vec![];
-                elem.into_repr()
-                    .serialize(&mut input_bytes)
-                    .expect("canonical serialiation should work");
-                match mode {
-                    Mode::Hex => hex::encode(&input_bytes),
-                    Mode::B10 => BigUint::from_bytes_le(&input_bytes).to_string(),
-                }
-            })
-            .collect();
-        let mut output_bytes = vec![];
-        output
-            .into_repr()
-            .serialize(&mut output_bytes)
-            .expect("canonical serialization should work");
+        let input = input.into_iter().map(|elem| encode(elem, mode)).collect();
 
         // add vector
         test_vectors.push(TestVector {
+            id: format!("basic/len{length}"),
             input,
-            output: match mode {
-                Mode::Hex => hex::encode(&output_bytes),
-                Mode::B10 => BigUint::from_bytes_le(&output_bytes).to_string(),
-            },
+            output: encode(output, mode),
         })
     }
 
-    let name = match param_type {
-        ParamType::Legacy => "legacy",
-        ParamType::Kimchi => "kimchi",
+    test_vectors
+}
+
+/// writes a set of test vectors in the format of `mode`:
+/// pretty JSON for [Mode::Hex] and [Mode::B10], JSON Lines for [Mode::JsonL], and CBOR for [Mode::Cbor]
+pub fn write_vectors(
+    vectors: &TestVectors,
+    mode: &Mode,
+    mut writer: impl Write,
+) -> Result<(), Box<dyn Error>> {
+    match mode {
+        Mode::Hex | Mode::B10 => serde_json::to_writer_pretty(writer, vectors)?,
+        Mode::JsonL => {
+            let header = JsonLHeader {
+                name: vectors.name.clone(),
+                field: vectors.field,
+            };
+            serde_json::to_writer(&mut writer, &header)?;
+            writeln!(writer)?;
+            for vector in &vectors.test_vectors {
+                serde_json::to_writer(&mut writer, vector)?;
+                writeln!(writer)?;
+            }
+        }
+        Mode::Cbor => {
+            let to_bytes = |elem: &String| hex::decode(elem);
+            let test_vectors = vectors
+                .test_vectors
+                .iter()
+                .map(|vector| -> Result<_, hex::FromHexError> {
+                    Ok(CborTestVector {
+                        id: vector.id.clone(),
+                        input: vector
+                            .input
+                            .iter()
+                            .map(to_bytes)
+                            .collect::<Result<_, _>>()?,
+                        output: to_bytes(&vector.output)?,
+                    })
+                })
+                .collect::<Result<_, hex::FromHexError>>()?;
+            let vectors = CborTestVectors {
+                name: vectors.name.clone(),
+                field: vectors.field,
+                test_vectors,
+            };
+            ciborium::ser::into_writer(&vectors, writer)?;
+        }
+    }
+    Ok(())
+}
+
+/// reads a set of test vectors written by [write_vectors] in the format of `mode`
+pub fn read_vectors(reader: impl Read, mode: &Mode) -> Result<TestVectors, Box<dyn Error>> {
+    let vectors = match mode {
+        Mode::Hex | Mode::B10 => serde_json::from_reader(reader)?,
+        Mode::JsonL => {
+            let mut lines = BufReader::new(reader).lines();
+            let header: JsonLHeader =
+                serde_json::from_str(&lines.next().ok_or("missing JSON Lines header")??)?;
+            let test_vectors = lines
+                .map(|line| -> Result<TestVector, Box<dyn Error>> {
+                    Ok(serde_json::from_str(&line?)?)
+                })
+                .collect::<Result<_, Box<dyn Error>>>()?;
+            TestVectors {
+                name: header.name,
+                field: header.field,
+                test_vectors,
+            }
+        }
+        Mode::Cbor => {
+            let vectors: CborTestVectors = ciborium::de::from_reader(reader)?;
+            let test_vectors = vectors
+                .test_vectors
+                .into_iter()
+                .map(|vector| TestVector {
+                    id: vector.id,
+                    input: vector.input.iter().map(hex::encode).collect(),
+                    output: hex::encode(vector.output),
+                })
+                .collect();
+            TestVectors {
+                name: vectors.name,
+                field: vectors.field,
+                test_vectors,
+            }
+        }
+    };
+    Ok(vectors)
+}
+
+/// verifies a set of test vectors against the current implementation,
+/// returning the indices of the vectors whose output does not match
+pub fn verify_vectors(vectors: &TestVectors, mode: Mode) -> Result<(), Vec<usize>> {
+    let param_type: ParamType = vectors.name.parse().expect("invalid param type");
+
+    let mismatches = match vectors.field {
+        Field::Fp => mismatching_vectors::<Fp>(vectors, &mode, &param_type),
+        Field::Fq => mismatching_vectors::<Fq>(vectors, &mode, &param_type),
+    };
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(mismatches)
+    }
+}
+
+/// returns the indices of the vectors over `F` whose output does not match
+fn mismatching_vectors<F: PastaField>(
+    vectors: &TestVectors,
+    mode: &Mode,
+    param_type: &ParamType,
+) -> Vec<usize> {
+    vectors
+        .test_vectors
+        .iter()
+        .enumerate()
+        .filter(|(_, vector)| {
+            let input: Option<Vec<F>> =
+                vector.input.iter().map(|elem| decode(elem, mode)).collect();
+            match (input, decode::<F>(&vector.output, mode)) {
+                (Some(input), Some(output)) => {
+                    SpongeVariant::from(*param_type).hash(&input) != output
+                }
+                _ => true,
+            }
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_vectors() {
+        for param_type in [ParamType::Legacy, ParamType::Kimchi] {
+            let vectors = generate(Mode::Hex, param_type, Field::Fp);
+            let json = serde_json::to_string(&vectors).unwrap();
+            let mut vectors: TestVectors = serde_json::from_str(&json).unwrap();
+            assert_eq!(verify_vectors(&vectors, Mode::Hex), Ok(()));
+
+            // tamper with the output of one of the vectors
+            vectors.test_vectors[3].output = vectors.test_vectors[2].output.clone();
+            assert_eq!(verify_vectors(&vectors, Mode::Hex), Err(vec![3]));
+        }
+    }
+
+    #[test]
+    fn test_vector_ids() {
+        let vectors = generate(Mode::B10, ParamType::Kimchi, Field::Fp);
+        for (length, vector) in vectors.test_vectors.iter().enumerate() {
+            let id = format!("basic/len{length}");
+            assert_eq!(vector.id, id);
+            let found = vectors.get(&id).expect("vector should be found by id");
+            assert_eq!(found.input.len(), length);
+            assert_eq!(found.output, vector.output);
+        }
+        assert_eq!(vectors.test_vectors.len(), 6);
+        assert!(vectors.get("basic/len6").is_none());
+    }
+
+    #[test]
+    fn test_jsonl_roundtrip() {
+        let vectors = generate(Mode::JsonL, ParamType::Kimchi, Field::Fq);
+        let mut buf = vec![];
+        write_vectors(&vectors, &Mode::JsonL, &mut buf).unwrap();
+
+        // one header line, then one line per vector
+        let text = String::from_utf8(buf.clone()).unwrap();
+        assert_eq!(text.lines().count(), vectors.test_vectors.len() + 1);
+
+        let read = read_vectors(buf.as_slice(), &Mode::JsonL).unwrap();
+        assert_eq!(read, vectors);
+        assert_eq!(verify_vectors(&read, Mode::JsonL), Ok(()));
+    }
+
+    #[test]
+    fn test_cbor_roundtrip() {
+        let vectors = generate(Mode::Cbor, ParamType::Legacy, Field::Fq);
+        let mut buf = vec![];
+        write_vectors(&vectors, &Mode::Cbor, &mut buf).unwrap();
+
+        // the output is written as raw bytes, not as a hex string
+        let output = &vectors.test_vectors[0].output;
+        let raw = hex::decode(output).unwrap();
+        assert!(buf
+            .windows(raw.len())
+            .any(|window| window == raw.as_slice()));
+        assert!(!buf
+            .windows(output.len())
+            .any(|window| window == output.as_bytes()));
+
+        let read = read_vectors(buf.as_slice(), &Mode::Cbor).unwrap();
+        assert_eq!(read, vectors);
+        assert_eq!(verify_vectors(&read, Mode::Cbor), Ok(()));
     }
-    .into();
 
-    TestVectors { name, test_vectors }
+    #[test]
+    fn test_fp_and_fq_vectors() {
+        for param_type in [ParamType::Legacy, ParamType::Kimchi] {
+            let fp = generate(Mode::Hex, param_type, Field::Fp);
+            let fq = generate(Mode::Hex, param_type, Field::Fq);
+            assert_eq!(fq.field, Field::Fq);
+            assert_eq!(verify_vectors(&fp, Mode::Hex), Ok(()));
+            assert_eq!(verify_vectors(&fq, Mode::Hex), Ok(()));
+
+            // the fixed seed makes the vectors deterministic
+            assert_eq!(generate(Mode::Hex, param_type, Field::Fq), fq);
+
+            // but the hashes differ between the two fields
+            for (fp, fq) in fp.test_vectors.iter().zip(&fq.test_vectors) {
+                assert_eq!(fp.id, fq.id);
+                assert_ne!(fp.output, fq.output);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fp_field_not_serialized() {
+        for mode in [Mode::Hex, Mode::JsonL, Mode::Cbor] {
+            let mut buf = vec![];
+            let fp = generate(mode, ParamType::Kimchi, Field::Fp);
+            write_vectors(&fp, &mode, &mut buf).unwrap();
+            assert!(!buf.windows(5).any(|window| window == b"field"));
+            assert_eq!(read_vectors(buf.as_slice(), &mode).unwrap(), fp);
+
+            let mut buf = vec![];
+            let fq = generate(mode, ParamType::Kimchi, Field::Fq);
+            write_vectors(&fq, &mode, &mut buf).unwrap();
+            assert!(buf.windows(5).any(|window| window == b"field"));
+            assert_eq!(read_vectors(buf.as_slice(), &mode).unwrap(), fq);
+        }
+    }
+
+    #[test]
+    fn test_sponge_variants_differ() {
+        let input = [Fp::from(1u64), Fp::from(2u64), Fp::from(3u64)];
+        let basic = SpongeVariant::Basic.hash(&input);
+        let fifteen = SpongeVariant::Fifteen.hash(&input);
+        assert_ne!(basic, fifteen);
+
+        assert_eq!(
+            fifteen,
+            poseidon::hash::<Fp, constants::PlonkSpongeConstantsKimchi>(
+                pasta::fp_kimchi::static_params(),
+                &input
+            )
+        );
+        assert_eq!(SpongeVariant::from(ParamType::Legacy), SpongeVariant::Basic);
+        assert_eq!(
+            SpongeVariant::from(ParamType::Kimchi),
+            SpongeVariant::Fifteen
+        );
+    }
 }
@@ -0,0 +1,41 @@
+//! This module implements a simple hash-based commitment scheme on top of the Poseidon sponge
+//!
+//! A commitment to `value` with `randomness` is the digest of the sponge after absorbing
+//! a domain separator together with the randomness and the length of the value, and then
+//! the committed value. Absorbing the length first keeps values that only differ by
+//! trailing zeros, such as `[a]` and `[a, 0]`, from having the same commitment.
+//! The commitment is hiding as long as the randomness is uniformly random and kept secret,
+//! and binding thanks to the collision resistance of the Poseidon hash.
+
+use crate::constants::SpongeConstants;
+use crate::poseidon::{ArithmeticSponge, ArithmeticSpongeParams, Sponge};
+use ark_ff::Field;
+
+/// Domain separator absorbed along with the randomness of a commitment ("commit" in ASCII)
+pub const COMMITMENT_DOMAIN_SEPARATOR: u64 = 0x636f6d6d6974;
+
+/// Commits to `value` using `randomness` as the blinding factor
+pub fn commit<F: Field, SC: SpongeConstants>(
+    params: &'static ArithmeticSpongeParams<F>,
+    value: &[F],
+    randomness: F,
+) -> F {
+    let mut sponge = ArithmeticSponge::<F, SC>::new(params);
+    sponge.absorb(&[
+        F::from(COMMITMENT_DOMAIN_SEPARATOR),
+        randomness,
+        F::from(value.len() as u64),
+    ]);
+    sponge.absorb(value);
+    sponge.squeeze()
+}
+
+/// Checks that `commitment` opens to `value` with `randomness`
+pub fn verify_commit<F: Field, SC: SpongeConstants>(
+    params: &'static ArithmeticSpongeParams<F>,
+    commitment: F,
+    value: &[F],
+    randomness: F,
+) -> bool {
+    commit::<F, SC>(params, value, randomness) == commitment
+}
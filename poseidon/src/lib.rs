@@ -1,8 +1,11 @@
+pub mod commitment;
 pub mod constants;
 pub mod dummy_values;
+pub mod merkle;
 pub mod pasta;
 pub mod permutation;
 pub mod poseidon;
+pub mod rng;
 pub mod sponge;
 
 #[cfg(test)]
@@ -0,0 +1,156 @@
+//! This module implements Merkle trees over Poseidon.
+//! Leaves, internal nodes and the root are hashed under distinct sponge domains,
+//! so that a leaf can never be mistaken for a node, and the root commits to the
+//! number of leaves, so that trees only differing by a duplicated last leaf
+//! (e.g. `[a, b, c]` and `[a, b, c, c]`) have different roots.
+
+use crate::constants::SpongeConstants;
+use crate::poseidon::{ArithmeticSponge, ArithmeticSpongeParams, Sponge};
+use ark_ff::Field;
+
+/// Domain under which the leaves are hashed
+const LEAF_DOMAIN: u64 = 1;
+/// Domain under which two children are hashed into their parent
+const NODE_DOMAIN: u64 = 2;
+/// Domain under which the top node is bound to the number of leaves
+const ROOT_DOMAIN: u64 = 3;
+
+fn hash_with_domain<F: Field, SC: SpongeConstants>(
+    params: &'static ArithmeticSpongeParams<F>,
+    domain: u64,
+    input: &[F],
+) -> F {
+    let mut sponge = ArithmeticSponge::<F, SC>::new_with_domain(params, F::from(domain));
+    sponge.absorb(input);
+    sponge.squeeze()
+}
+
+/// Hashes a leaf into the bottom level of the tree
+pub fn hash_leaf<F: Field, SC: SpongeConstants>(
+    params: &'static ArithmeticSpongeParams<F>,
+    leaf: F,
+) -> F {
+    hash_with_domain::<F, SC>(params, LEAF_DOMAIN, &[leaf])
+}
+
+/// Hashes two children into their parent node
+pub fn hash_node<F: Field, SC: SpongeConstants>(
+    params: &'static ArithmeticSpongeParams<F>,
+    left: F,
+    right: F,
+) -> F {
+    hash_with_domain::<F, SC>(params, NODE_DOMAIN, &[left, right])
+}
+
+/// Binds the top node of a tree with `num_leaves` leaves into its root
+pub fn hash_root<F: Field, SC: SpongeConstants>(
+    params: &'static ArithmeticSpongeParams<F>,
+    num_leaves: usize,
+    top: F,
+) -> F {
+    hash_with_domain::<F, SC>(params, ROOT_DOMAIN, &[F::from(num_leaves as u64), top])
+}
+
+/// Returns the length of the authentication paths of a tree with `num_leaves` leaves
+fn depth(num_leaves: usize) -> usize {
+    // ceil(log2(num_leaves)), as each level halves the number of nodes rounding up
+    match num_leaves {
+        0 | 1 => 0,
+        n => (usize::BITS - (n - 1).leading_zeros()) as usize,
+    }
+}
+
+/// Hashes one level of the tree into the next one,
+/// duplicating the last node if the level has an odd number of nodes
+fn next_level<F: Field, SC: SpongeConstants>(
+    params: &'static ArithmeticSpongeParams<F>,
+    level: &[F],
+) -> Vec<F> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_node::<F, SC>(params, *left, *right),
+            [last] => hash_node::<F, SC>(params, *last, *last),
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Hashes the leaves into the bottom level of the tree
+fn leaf_level<F: Field, SC: SpongeConstants>(
+    params: &'static ArithmeticSpongeParams<F>,
+    leaves: &[F],
+) -> Vec<F> {
+    leaves
+        .iter()
+        .map(|leaf| hash_leaf::<F, SC>(params, *leaf))
+        .collect()
+}
+
+/// Computes the root of the Merkle tree with the given `leaves`
+///
+/// # Panics
+///
+/// Will panic if `leaves` is empty.
+pub fn merkle_root<F: Field, SC: SpongeConstants>(
+    params: &'static ArithmeticSpongeParams<F>,
+    leaves: &[F],
+) -> F {
+    assert!(!leaves.is_empty(), "a Merkle tree needs at least one leaf");
+    let mut level = leaf_level::<F, SC>(params, leaves);
+    while level.len() > 1 {
+        level = next_level::<F, SC>(params, &level);
+    }
+    hash_root::<F, SC>(params, leaves.len(), level[0])
+}
+
+/// Computes the authentication path of the leaf at `index`,
+/// that is the sibling of each node from that leaf up to the top node
+///
+/// # Panics
+///
+/// Will panic if `index` is out of bounds of `leaves`.
+pub fn merkle_path<F: Field, SC: SpongeConstants>(
+    params: &'static ArithmeticSpongeParams<F>,
+    leaves: &[F],
+    index: usize,
+) -> Vec<F> {
+    assert!(index < leaves.len(), "leaf index out of bounds");
+    let mut path = vec![];
+    let mut level = leaf_level::<F, SC>(params, leaves);
+    let mut index = index;
+    while level.len() > 1 {
+        // the last node of an odd level is its own sibling
+        let sibling = level.get(index ^ 1).unwrap_or(&level[index]);
+        path.push(*sibling);
+        level = next_level::<F, SC>(params, &level);
+        index /= 2;
+    }
+    path
+}
+
+/// Checks that `path` authenticates `leaf` at `index` in the tree of `num_leaves` leaves
+/// with the given `root`. Indices out of bounds of the tree and paths whose length does
+/// not match the depth of the tree are rejected.
+pub fn verify_merkle_path<F: Field, SC: SpongeConstants>(
+    params: &'static ArithmeticSpongeParams<F>,
+    leaf: F,
+    index: usize,
+    num_leaves: usize,
+    path: &[F],
+    root: F,
+) -> bool {
+    if index >= num_leaves || path.len() != depth(num_leaves) {
+        return false;
+    }
+    let leaf = hash_leaf::<F, SC>(params, leaf);
+    let (top, _) = path.iter().fold((leaf, index), |(node, index), sibling| {
+        let parent = if index % 2 == 0 {
+            hash_node::<F, SC>(params, node, *sibling)
+        } else {
+            hash_node::<F, SC>(params, *sibling, node)
+        };
+        (parent, index / 2)
+    });
+    hash_root::<F, SC>(params, num_leaves, top) == root
+}
@@ -3,6 +3,8 @@
 use crate::constants::SpongeConstants;
 use crate::permutation::{full_round, poseidon_block_cipher};
 use ark_ff::Field;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 
@@ -52,6 +54,16 @@ pub struct ArithmeticSponge<F: Field, SC: SpongeConstants> {
 }
 
 impl<F: Field, SC: SpongeConstants> ArithmeticSponge<F, SC> {
+    /// Create a new sponge whose capacity starts at `domain` instead of zero, so that
+    /// sponges created for different domains behave as independent hash functions.
+    /// The domain `0` gives the same sponge as [Sponge::new].
+    /// Note that [Sponge::reset] clears the domain along with the rest of the state.
+    pub fn new_with_domain(params: &'static ArithmeticSpongeParams<F>, domain: F) -> Self {
+        let mut sponge = <Self as Sponge<F, F>>::new(params);
+        sponge.state[sponge.rate] = domain;
+        sponge
+    }
+
     pub fn full_round(&mut self, r: usize) {
         full_round::<F, SC>(self.params, &mut self.state, r);
     }
@@ -59,6 +71,40 @@ impl<F: Field, SC: SpongeConstants> ArithmeticSponge<F, SC> {
     fn poseidon_block_cipher(&mut self) {
         poseidon_block_cipher::<F, SC>(self.params, &mut self.state);
     }
+
+    /// Absorb a single field element `x`. Absorbing elements one at a time, or in chunks of
+    /// any size, results in the same state as absorbing all of them at once, since the state
+    /// is only permuted once a full rate of elements has been absorbed.
+    pub fn absorb_one(&mut self, x: F) {
+        match self.sponge_state {
+            SpongeState::Absorbed(n) => {
+                if n == self.rate {
+                    self.poseidon_block_cipher();
+                    self.sponge_state = SpongeState::Absorbed(1);
+                    self.state[0] += x;
+                } else {
+                    self.sponge_state = SpongeState::Absorbed(n + 1);
+                    self.state[n] += x;
+                }
+            }
+            SpongeState::Squeezed(_n) => {
+                self.state[0] += x;
+                self.sponge_state = SpongeState::Absorbed(1);
+            }
+        }
+    }
+
+    /// Squeeze `n` outputs from the sponge
+    pub fn squeeze_n(&mut self, n: usize) -> Vec<F> {
+        (0..n).map(|_| self.squeeze()).collect()
+    }
+
+    /// Squeeze as many outputs from the sponge as fit in `out`, without allocating
+    pub fn squeeze_into(&mut self, out: &mut [F]) {
+        for elem in out.iter_mut() {
+            *elem = self.squeeze();
+        }
+    }
 }
 
 impl<F: Field, SC: SpongeConstants> Sponge<F, F> for ArithmeticSponge<F, SC> {
@@ -83,22 +129,7 @@ impl<F: Field, SC: SpongeConstants> Sponge<F, F> for ArithmeticSponge<F, SC> {
 
     fn absorb(&mut self, x: &[F]) {
         for x in x.iter() {
-            match self.sponge_state {
-                SpongeState::Absorbed(n) => {
-                    if n == self.rate {
-                        self.poseidon_block_cipher();
-                        self.sponge_state = SpongeState::Absorbed(1);
-                        self.state[0].add_assign(x);
-                    } else {
-                        self.sponge_state = SpongeState::Absorbed(n + 1);
-                        self.state[n].add_assign(x);
-                    }
-                }
-                SpongeState::Squeezed(_n) => {
-                    self.state[0].add_assign(x);
-                    self.sponge_state = SpongeState::Absorbed(1);
-                }
-            }
+            self.absorb_one(*x);
         }
     }
 
@@ -123,7 +154,33 @@ impl<F: Field, SC: SpongeConstants> Sponge<F, F> for ArithmeticSponge<F, SC> {
     }
 
     fn reset(&mut self) {
-        self.state = vec![F::zero(); self.state.len()];
+        // zero the state in place, so that the sponge can be reused without allocating
+        self.state.iter_mut().for_each(|x| *x = F::zero());
         self.sponge_state = SpongeState::Absorbed(0);
     }
 }
+
+/// Hashes `input` with a fresh sponge, absorbing all of it and squeezing once
+pub fn hash<F: Field, SC: SpongeConstants>(
+    params: &'static ArithmeticSpongeParams<F>,
+    input: &[F],
+) -> F {
+    let mut sponge = ArithmeticSponge::<F, SC>::new(params);
+    sponge.absorb(input);
+    sponge.squeeze()
+}
+
+/// Hashes each of the `inputs` independently, with a fresh sponge each, spreading them
+/// across the rayon thread pool if the `parallel` feature is enabled.
+/// Either way, the hashes are in the order of the inputs.
+pub fn hash_many<F: Field, SC: SpongeConstants>(
+    params: &'static ArithmeticSpongeParams<F>,
+    inputs: &[Vec<F>],
+) -> Vec<F> {
+    let hash_one = |input: &Vec<F>| hash::<F, SC>(params, input);
+    #[cfg(feature = "parallel")]
+    let hashes = inputs.par_iter().map(hash_one).collect();
+    #[cfg(not(feature = "parallel"))]
+    let hashes = inputs.iter().map(hash_one).collect();
+    hashes
+}
@@ -0,0 +1,33 @@
+//! This module implements a deterministic generator of challenges on top of the Poseidon sponge
+//!
+//! The generator is seeded with a transcript, and each challenge is squeezed from the sponge.
+//! New data can be mixed in at any point, which changes all the subsequent challenges.
+
+use crate::constants::SpongeConstants;
+use crate::poseidon::{ArithmeticSponge, ArithmeticSpongeParams, Sponge};
+use ark_ff::Field;
+
+/// Generator of Fiat-Shamir challenges backed by an arithmetic sponge
+#[derive(Clone)]
+pub struct SpongeRng<F: Field, SC: SpongeConstants> {
+    sponge: ArithmeticSponge<F, SC>,
+}
+
+impl<F: Field, SC: SpongeConstants> SpongeRng<F, SC> {
+    /// Creates a generator whose sponge is seeded with `seed`
+    pub fn new(params: &'static ArithmeticSpongeParams<F>, seed: &[F]) -> Self {
+        let mut sponge = ArithmeticSponge::<F, SC>::new(params);
+        sponge.absorb(seed);
+        SpongeRng { sponge }
+    }
+
+    /// Mixes `transcript` into the state of the generator
+    pub fn absorb_transcript(&mut self, transcript: &[F]) {
+        self.sponge.absorb(transcript);
+    }
+
+    /// Returns the next challenge, squeezing one element from the sponge
+    pub fn next_challenge(&mut self) -> F {
+        self.sponge.squeeze()
+    }
+}
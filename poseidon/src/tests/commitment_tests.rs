@@ -0,0 +1,57 @@
+use crate::{
+    commitment::{commit, verify_commit},
+    constants::PlonkSpongeConstantsKimchi,
+    pasta::fp_kimchi as SpongeParametersKimchi,
+};
+use ark_ff::UniformRand;
+use mina_curves::pasta::Fp;
+
+#[test]
+fn commitment_opens_with_right_randomness() {
+    let rng = &mut rand::thread_rng();
+    let params = SpongeParametersKimchi::static_params();
+    let value: Vec<Fp> = (0..3).map(|_| Fp::rand(rng)).collect();
+    let randomness = Fp::rand(rng);
+
+    let commitment = commit::<Fp, PlonkSpongeConstantsKimchi>(params, &value, randomness);
+    assert!(verify_commit::<Fp, PlonkSpongeConstantsKimchi>(
+        params, commitment, &value, randomness
+    ));
+
+    // wrong randomness
+    assert!(!verify_commit::<Fp, PlonkSpongeConstantsKimchi>(
+        params,
+        commitment,
+        &value,
+        randomness + Fp::from(1u64)
+    ));
+
+    // wrong value
+    let mut wrong_value = value.clone();
+    wrong_value[1] += Fp::from(1u64);
+    assert!(!verify_commit::<Fp, PlonkSpongeConstantsKimchi>(
+        params,
+        commitment,
+        &wrong_value,
+        randomness
+    ));
+}
+
+#[test]
+fn commitment_binds_value_length() {
+    let rng = &mut rand::thread_rng();
+    let params = SpongeParametersKimchi::static_params();
+    let a = Fp::rand(rng);
+    let randomness = Fp::rand(rng);
+
+    // Trailing zeros do not change the absorbed state, but the length does
+    let short = commit::<Fp, PlonkSpongeConstantsKimchi>(params, &[a], randomness);
+    let long = commit::<Fp, PlonkSpongeConstantsKimchi>(params, &[a, Fp::from(0u64)], randomness);
+    assert_ne!(short, long);
+    assert!(!verify_commit::<Fp, PlonkSpongeConstantsKimchi>(
+        params,
+        short,
+        &[a, Fp::from(0u64)],
+        randomness
+    ));
+}
@@ -0,0 +1,108 @@
+use crate::{
+    constants::PlonkSpongeConstantsKimchi,
+    merkle::{hash_leaf, hash_node, hash_root, merkle_path, merkle_root, verify_merkle_path},
+    pasta::fp_kimchi as SpongeParametersKimchi,
+    poseidon::hash,
+};
+use mina_curves::pasta::Fp;
+
+type Kimchi = PlonkSpongeConstantsKimchi;
+
+fn leaves(n: u64) -> Vec<Fp> {
+    (0..n).map(|i| Fp::from(i + 1)).collect()
+}
+
+#[test]
+fn merkle_root_small_trees() {
+    let params = SpongeParametersKimchi::static_params();
+    let leaf = |x| hash_leaf::<Fp, Kimchi>(params, x);
+    let node = |left, right| hash_node::<Fp, Kimchi>(params, left, right);
+    let root = |n, top| hash_root::<Fp, Kimchi>(params, n, top);
+    let [a, b, c, d]: [Fp; 4] = leaves(4).try_into().unwrap();
+
+    assert_eq!(merkle_root::<Fp, Kimchi>(params, &[a]), root(1, leaf(a)));
+    assert_eq!(
+        merkle_root::<Fp, Kimchi>(params, &[a, b]),
+        root(2, node(leaf(a), leaf(b)))
+    );
+    // the last node of an odd level is duplicated
+    assert_eq!(
+        merkle_root::<Fp, Kimchi>(params, &[a, b, c]),
+        root(3, node(node(leaf(a), leaf(b)), node(leaf(c), leaf(c))))
+    );
+    assert_eq!(
+        merkle_root::<Fp, Kimchi>(params, &[a, b, c, d]),
+        root(4, node(node(leaf(a), leaf(b)), node(leaf(c), leaf(d))))
+    );
+}
+
+#[test]
+fn merkle_path_roundtrip() {
+    let params = SpongeParametersKimchi::static_params();
+    for n in 1..=9 {
+        let leaves = leaves(n);
+        let num_leaves = leaves.len();
+        let root = merkle_root::<Fp, Kimchi>(params, &leaves);
+        for (index, leaf) in leaves.iter().enumerate() {
+            let path = merkle_path::<Fp, Kimchi>(params, &leaves, index);
+            assert!(verify_merkle_path::<Fp, Kimchi>(
+                params, *leaf, index, num_leaves, &path, root
+            ));
+
+            // the path does not authenticate another leaf
+            assert!(!verify_merkle_path::<Fp, Kimchi>(
+                params,
+                *leaf + Fp::from(1u64),
+                index,
+                num_leaves,
+                &path,
+                root
+            ));
+        }
+    }
+}
+
+#[test]
+fn merkle_path_rejects_out_of_range_index() {
+    let params = SpongeParametersKimchi::static_params();
+    let leaves = leaves(3);
+    let root = merkle_root::<Fp, Kimchi>(params, &leaves);
+    // the duplicated last node would authenticate the last leaf at index 3
+    let path = merkle_path::<Fp, Kimchi>(params, &leaves, 2);
+    assert!(verify_merkle_path::<Fp, Kimchi>(
+        params, leaves[2], 2, 3, &path, root
+    ));
+    assert!(!verify_merkle_path::<Fp, Kimchi>(
+        params, leaves[2], 3, 3, &path, root
+    ));
+    // nor can the tree be passed off as having a fourth leaf
+    assert!(!verify_merkle_path::<Fp, Kimchi>(
+        params, leaves[2], 3, 4, &path, root
+    ));
+}
+
+#[test]
+fn merkle_root_duplicated_last_leaf() {
+    let params = SpongeParametersKimchi::static_params();
+    let [a, b, c]: [Fp; 3] = leaves(3).try_into().unwrap();
+    assert_ne!(
+        merkle_root::<Fp, Kimchi>(params, &[a, b, c]),
+        merkle_root::<Fp, Kimchi>(params, &[a, b, c, c])
+    );
+}
+
+#[test]
+fn merkle_root_leaf_node_separation() {
+    let params = SpongeParametersKimchi::static_params();
+    let [a, b]: [Fp; 2] = leaves(2).try_into().unwrap();
+    let root = merkle_root::<Fp, Kimchi>(params, &[a, b]);
+    // an internal node or a plain hash of the children is not a valid leaf
+    let plain = hash::<Fp, Kimchi>(params, &[a, b]);
+    assert_ne!(root, merkle_root::<Fp, Kimchi>(params, &[plain]));
+    let top = hash_node::<Fp, Kimchi>(
+        params,
+        hash_leaf::<Fp, Kimchi>(params, a),
+        hash_leaf::<Fp, Kimchi>(params, b),
+    );
+    assert_ne!(root, merkle_root::<Fp, Kimchi>(params, &[top]));
+}
@@ -1 +1,4 @@
+mod commitment_tests;
+mod merkle_tests;
 mod poseidon_tests;
+mod rng_tests;
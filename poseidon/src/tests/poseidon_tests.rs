@@ -4,6 +4,7 @@ use crate::{
     pasta::fp_legacy as SpongeParametersLegacy,
     poseidon::ArithmeticSponge as Poseidon,
     poseidon::Sponge as _,
+    poseidon::{hash, hash_many},
 };
 use mina_curves::pasta::Fp;
 use o1_utils::FieldHelpers;
@@ -81,3 +82,171 @@ fn poseidon_test_vectors_kimchi() {
     }
     test_vectors("kimchi.json", hash);
 }
+
+#[test]
+fn poseidon_squeeze_into_matches_squeeze_n() {
+    let params = SpongeParametersKimchi::static_params();
+    let input = [Fp::from(1u64), Fp::from(2u64), Fp::from(3u64)];
+
+    let mut sponge = Poseidon::<Fp, PlonkSpongeConstantsKimchi>::new(params);
+    sponge.absorb(&input);
+    let expected = sponge.squeeze_n(3);
+
+    let mut sponge = Poseidon::<Fp, PlonkSpongeConstantsKimchi>::new(params);
+    sponge.absorb(&input);
+    let mut out = [Fp::from(0u64); 3];
+    sponge.squeeze_into(&mut out);
+
+    assert_eq!(out.to_vec(), expected);
+}
+
+#[test]
+fn poseidon_absorb_in_chunks() {
+    let params = SpongeParametersKimchi::static_params();
+    let [a, b, c, d] = [1u64, 2, 3, 4].map(Fp::from);
+
+    let mut sponge = Poseidon::<Fp, PlonkSpongeConstantsKimchi>::new(params);
+    sponge.absorb(&[a, b, c]);
+    let expected = sponge.squeeze();
+
+    let mut chunks = Poseidon::<Fp, PlonkSpongeConstantsKimchi>::new(params);
+    chunks.absorb(&[a]);
+    chunks.absorb(&[b, c]);
+    assert_eq!(chunks.squeeze(), expected);
+
+    let mut one_by_one = Poseidon::<Fp, PlonkSpongeConstantsKimchi>::new(params);
+    for x in [a, b, c] {
+        one_by_one.absorb_one(x);
+    }
+    assert_eq!(one_by_one.squeeze(), expected);
+
+    // Chunks crossing the rate permute the state in between
+    let mut sponge = Poseidon::<Fp, PlonkSpongeConstantsKimchi>::new(params);
+    sponge.absorb(&[a, b, c, d, a]);
+    let expected = sponge.squeeze();
+
+    let mut chunks = Poseidon::<Fp, PlonkSpongeConstantsKimchi>::new(params);
+    chunks.absorb(&[a, b, c]);
+    chunks.absorb(&[d]);
+    chunks.absorb_one(a);
+    assert_eq!(chunks.squeeze(), expected);
+}
+
+#[test]
+fn poseidon_domain_separation() {
+    let params = SpongeParametersKimchi::static_params();
+    let input = [Fp::from(1u64), Fp::from(2u64), Fp::from(3u64)];
+    let hash = |mut sponge: Poseidon<Fp, PlonkSpongeConstantsKimchi>| {
+        sponge.absorb(&input);
+        sponge.squeeze()
+    };
+
+    let merkle = hash(Poseidon::new_with_domain(params, Fp::from(1u64)));
+    let nullifier = hash(Poseidon::new_with_domain(params, Fp::from(2u64)));
+    assert_ne!(merkle, nullifier);
+
+    // The zero domain is the default sponge
+    let default = hash(Poseidon::new(params));
+    assert_eq!(
+        hash(Poseidon::new_with_domain(params, Fp::from(0u64))),
+        default
+    );
+    assert_ne!(merkle, default);
+}
+
+#[test]
+fn poseidon_hash_many() {
+    let params = SpongeParametersKimchi::static_params();
+    let inputs: Vec<Vec<Fp>> = (0..20u64)
+        .map(|i| (0..i).map(|j| Fp::from(i * 100 + j)).collect())
+        .collect();
+
+    // Each input is hashed with its own sponge
+    let hashes = hash_many::<Fp, PlonkSpongeConstantsKimchi>(params, &inputs);
+    assert_eq!(hashes.len(), inputs.len());
+    for (input, hash) in inputs.iter().zip(hashes.iter()) {
+        let mut sponge = Poseidon::<Fp, PlonkSpongeConstantsKimchi>::new(params);
+        sponge.absorb(input);
+        assert_eq!(sponge.squeeze(), *hash);
+    }
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn poseidon_hash_many_parallel() {
+    let params = SpongeParametersKimchi::static_params();
+    let inputs: Vec<Vec<Fp>> = (0..20u64)
+        .map(|i| (0..i).map(|j| Fp::from(i * 100 + j)).collect())
+        .collect();
+
+    // The parallel hashes agree with the serial ones
+    let serial: Vec<_> = inputs
+        .iter()
+        .map(|input| hash::<Fp, PlonkSpongeConstantsKimchi>(params, input))
+        .collect();
+    assert_eq!(
+        hash_many::<Fp, PlonkSpongeConstantsKimchi>(params, &inputs),
+        serial
+    );
+}
+
+#[test]
+fn poseidon_squeeze_n_matches_squeeze() {
+    let params = SpongeParametersKimchi::static_params();
+    let input = [Fp::from(1u64), Fp::from(2u64), Fp::from(3u64)];
+
+    // More outputs than the rate, so that the state is permuted in between
+    let mut sponge = Poseidon::<Fp, PlonkSpongeConstantsKimchi>::new(params);
+    sponge.absorb(&input);
+    let outputs = sponge.squeeze_n(3);
+
+    let mut sponge = Poseidon::<Fp, PlonkSpongeConstantsKimchi>::new(params);
+    sponge.absorb(&input);
+    let expected: Vec<_> = (0..3).map(|_| sponge.squeeze()).collect();
+    assert_eq!(outputs, expected);
+
+    assert_ne!(outputs[0], outputs[1]);
+    assert_ne!(outputs[1], outputs[2]);
+    assert_ne!(outputs[0], outputs[2]);
+}
+
+#[test]
+fn poseidon_reset_matches_fresh_sponge() {
+    let params = SpongeParametersKimchi::static_params();
+    let input = [Fp::from(1u64), Fp::from(2u64), Fp::from(3u64)];
+
+    let mut fresh = Poseidon::<Fp, PlonkSpongeConstantsKimchi>::new(params);
+    fresh.absorb(&input);
+    let expected = fresh.squeeze_n(3);
+
+    // dirty the sponge, both absorbing and squeezing, before resetting it
+    let mut reused = Poseidon::<Fp, PlonkSpongeConstantsKimchi>::new(params);
+    reused.absorb(&[Fp::from(42u64)]);
+    reused.squeeze();
+    reused.reset();
+    reused.absorb(&input);
+    assert_eq!(reused.squeeze_n(3), expected);
+}
+
+#[test]
+fn poseidon_hash_matches_sponge() {
+    for length in 0..6u64 {
+        let input: Vec<Fp> = (0..length).map(|i| Fp::from(i + 1)).collect();
+
+        let params = SpongeParametersLegacy::static_params();
+        let mut sponge = Poseidon::<Fp, PlonkSpongeConstantsLegacy>::new(params);
+        sponge.absorb(&input);
+        assert_eq!(
+            hash::<Fp, PlonkSpongeConstantsLegacy>(params, &input),
+            sponge.squeeze()
+        );
+
+        let params = SpongeParametersKimchi::static_params();
+        let mut sponge = Poseidon::<Fp, PlonkSpongeConstantsKimchi>::new(params);
+        sponge.absorb(&input);
+        assert_eq!(
+            hash::<Fp, PlonkSpongeConstantsKimchi>(params, &input),
+            sponge.squeeze()
+        );
+    }
+}
@@ -0,0 +1,33 @@
+use crate::{
+    constants::PlonkSpongeConstantsKimchi, pasta::fp_kimchi as SpongeParametersKimchi,
+    rng::SpongeRng,
+};
+use mina_curves::pasta::Fp;
+
+#[test]
+fn sponge_rng_is_deterministic() {
+    let params = SpongeParametersKimchi::static_params();
+    let seed = [Fp::from(1u64), Fp::from(2u64)];
+
+    let mut rng1 = SpongeRng::<Fp, PlonkSpongeConstantsKimchi>::new(params, &seed);
+    let mut rng2 = SpongeRng::<Fp, PlonkSpongeConstantsKimchi>::new(params, &seed);
+    let challenges1: Vec<Fp> = (0..5).map(|_| rng1.next_challenge()).collect();
+    let challenges2: Vec<Fp> = (0..5).map(|_| rng2.next_challenge()).collect();
+    assert_eq!(challenges1, challenges2);
+
+    // consecutive challenges differ
+    assert_ne!(challenges1[0], challenges1[1]);
+}
+
+#[test]
+fn sponge_rng_absorb_changes_challenges() {
+    let params = SpongeParametersKimchi::static_params();
+    let seed = [Fp::from(1u64), Fp::from(2u64)];
+
+    let mut rng1 = SpongeRng::<Fp, PlonkSpongeConstantsKimchi>::new(params, &seed);
+    let mut rng2 = rng1.clone();
+    assert_eq!(rng1.next_challenge(), rng2.next_challenge());
+
+    rng2.absorb_transcript(&[Fp::from(3u64)]);
+    assert_ne!(rng1.next_challenge(), rng2.next_challenge());
+}